@@ -1,3 +1,4 @@
+use std::ffi::OsStr;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::path::{Path, PathBuf};
@@ -6,7 +7,11 @@ use std::str::FromStr;
 use clap::{Parser, Subcommand};
 
 use lindera::character_filter::CharacterFilterLoader;
-use lindera::dictionary::{resolve_builder, DictionaryKind};
+use lindera::dictionary::{
+    dump_dictionary, dump_user_dictionary, load_dictionary_from_kind, load_dictionary_from_path,
+    load_user_dictionary_from_bin, resolve_builder, CompressAlgorithm, DictionaryKind, Metadata,
+    DICTIONARY_FILE_EXTENSION,
+};
 use lindera::error::{LinderaError, LinderaErrorKind};
 use lindera::mode::Mode;
 use lindera::token::Token;
@@ -25,7 +30,9 @@ struct Args {
 enum Commands {
     List(ListArgs),
     Tokenize(TokenizeArgs),
+    Lattice(LatticeArgs),
     Build(BuildArgs),
+    Dump(DumpArgs),
 }
 
 #[derive(Debug, clap::Args)]
@@ -34,7 +41,14 @@ enum Commands {
     about = "List a contained morphological analysis dictionaries",
     version
 )]
-struct ListArgs {}
+struct ListArgs {
+    #[clap(
+        short = 'd',
+        long = "dictionary-path",
+        help = "Show the external dictionary at this path instead"
+    )]
+    dic_dir: Option<PathBuf>,
+}
 
 #[derive(Debug, clap::Args)]
 #[clap(
@@ -83,6 +97,51 @@ struct TokenizeArgs {
         help = "Specify token filter. e.g. stop_word:{\"words\":[\"a\", \"the\"]}"
     )]
     token_filters: Option<Vec<String>>,
+    #[clap(
+        short = 'N',
+        long = "nbest",
+        help = "Output the N best results. e.g. 3"
+    )]
+    nbest: Option<usize>,
+    #[clap(help = "Input text file path")]
+    input_file: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+#[clap(
+    author,
+    about = "Dump the lattice built for text, with costs and the best path",
+    version
+)]
+struct LatticeArgs {
+    #[clap(short = 'k', long = "dictionary-kind", help = "Kind of dictionary")]
+    dic_type: Option<DictionaryKind>,
+    #[clap(
+        short = 'd',
+        long = "dictionary-path",
+        help = "Dictionary directory path"
+    )]
+    dic_dir: Option<PathBuf>,
+    #[clap(
+        short = 'u',
+        long = "user-dictionary-path",
+        help = "User dictionary file path"
+    )]
+    user_dic_file: Option<PathBuf>,
+    #[clap(
+        short = 'm',
+        long = "mode",
+        default_value = "normal",
+        help = "Tokenization mode. normal"
+    )]
+    mode: Mode,
+    #[clap(
+        short = 'o',
+        long = "output-format",
+        default_value = "dot",
+        help = "Output format. dot or json"
+    )]
+    output_format: String,
     #[clap(help = "Input text file path")]
     input_file: Option<PathBuf>,
 }
@@ -98,9 +157,52 @@ struct BuildArgs {
     build_user_dic: bool,
     #[clap(short = 'k', long = "dictionary-kind", help = "Kind of dictionary")]
     dic_type: DictionaryKind,
+    #[clap(
+        short = 'd',
+        long = "dictionary-path",
+        help = "System dictionary the user dictionary is for (a directory or a .linderadic file), whose context ids the simple entries get"
+    )]
+    dic_dir: Option<PathBuf>,
+    #[clap(
+        long = "compress",
+        help = "Compression algorithm of the dictionary files (deflate, zlib, gzip, zstd, lz4 or raw)"
+    )]
+    compress: Option<CompressAlgorithm>,
+    #[clap(
+        long = "reading-index",
+        help = "Build an index of the readings of the words"
+    )]
+    reading_index: bool,
     #[clap(help = "Dictionary source path")]
     src_path: PathBuf,
-    #[clap(help = "Dictionary destination path")]
+    #[clap(help = "Dictionary destination path (a directory, or a .linderadic file)")]
+    dest_path: PathBuf,
+}
+
+#[derive(Debug, clap::Args)]
+#[clap(
+    author,
+    about = "Dump a built dictionary back to its source files",
+    version
+)]
+struct DumpArgs {
+    #[clap(
+        short = 'u',
+        long = "dump-user-dictionary",
+        help = "Dump user dictionary flag"
+    )]
+    dump_user_dic: bool,
+    #[clap(
+        short = 'k',
+        long = "dictionary-kind",
+        help = "Kind of dictionary of the user dictionary (required with --dump-user-dictionary)"
+    )]
+    dic_type: Option<DictionaryKind>,
+    #[clap(
+        help = "Built dictionary path (a directory or a .linderadic file, or a user dictionary .bin file)"
+    )]
+    src_path: PathBuf,
+    #[clap(help = "Destination directory path")]
     dest_path: PathBuf,
 }
 
@@ -125,23 +227,76 @@ impl FromStr for Format {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+/// Lattice formatter type
+pub enum LatticeFormat {
+    Dot,
+    Json,
+}
+
+impl FromStr for LatticeFormat {
+    type Err = LinderaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(LatticeFormat::Dot),
+            "json" => Ok(LatticeFormat::Json),
+            _ => Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!("Invalid format: {}", s))),
+        }
+    }
+}
+
 fn main() -> LinderaResult<()> {
     let args = Args::parse();
 
     match args.command {
         Commands::List(args) => list(args),
         Commands::Tokenize(args) => tokenize(args),
+        Commands::Lattice(args) => lattice(args),
         Commands::Build(args) => build(args),
+        Commands::Dump(args) => dump(args),
     }
 }
 
-fn list(_args: ListArgs) -> LinderaResult<()> {
+fn list(args: ListArgs) -> LinderaResult<()> {
+    if let Some(dic_dir) = args.dic_dir {
+        let dictionary = load_dictionary_from_path(&dic_dir)?;
+        println!("{}", dic_dir.display());
+        print_metadata(dictionary.metadata()?);
+        return Ok(());
+    }
+
     for dic in DictionaryKind::contained_variants() {
+        let dictionary = load_dictionary_from_kind(dic.clone())?;
         println!("{}", dic.as_str());
+        print_metadata(dictionary.metadata()?);
     }
     Ok(())
 }
 
+fn print_metadata(metadata: Option<&Metadata>) {
+    let Some(metadata) = metadata else {
+        println!("  (no metadata)");
+        return;
+    };
+
+    println!("  name: {}", metadata.name);
+    println!("  source: {}", metadata.source);
+    println!("  encoding: {}", metadata.encoding);
+    println!("  detail fields: {}", metadata.detail_fields.join(","));
+    println!("  entries: {}", metadata.entry_count);
+    println!("  unknown entries: {}", metadata.unknown_entry_count);
+    println!(
+        "  connection size: {}x{}",
+        metadata.connection_size.0, metadata.connection_size.1
+    );
+    println!("  build timestamp: {}", metadata.build_timestamp);
+    println!("  lindera version: {}", metadata.lindera_version);
+    for (key, value) in metadata.build_options.iter() {
+        println!("  {}: {}", key, value);
+    }
+}
+
 fn mecab_output(mut tokens: Vec<Token>) -> LinderaResult<()> {
     for token in tokens.iter_mut() {
         let details = token.details().join(",");
@@ -152,7 +307,7 @@ fn mecab_output(mut tokens: Vec<Token>) -> LinderaResult<()> {
     Ok(())
 }
 
-fn json_output(mut tokens: Vec<Token>) -> LinderaResult<()> {
+fn json_tokens(mut tokens: Vec<Token>) -> Vec<serde_json::Value> {
     let mut json_tokens = Vec::new();
     for token in tokens.iter_mut() {
         let json_token = serde_json::json!({
@@ -161,9 +316,15 @@ fn json_output(mut tokens: Vec<Token>) -> LinderaResult<()> {
             "byte_start": token.byte_start,
             "byte_end": token.byte_end,
             "word_id": token.word_id,
+            "origin": token.origin(),
         });
         json_tokens.push(json_token);
     }
+    json_tokens
+}
+
+fn json_output(tokens: Vec<Token>) -> LinderaResult<()> {
+    let json_tokens = json_tokens(tokens);
 
     println!(
         "{}",
@@ -174,6 +335,26 @@ fn json_output(mut tokens: Vec<Token>) -> LinderaResult<()> {
     Ok(())
 }
 
+fn json_nbest_output(nbest: Vec<(Vec<Token>, i64)>) -> LinderaResult<()> {
+    let json_results = nbest
+        .into_iter()
+        .map(|(tokens, cost)| {
+            serde_json::json!({
+                "cost": cost,
+                "tokens": json_tokens(tokens),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json_results)
+            .map_err(|err| { LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)) })?
+    );
+
+    Ok(())
+}
+
 fn wakati_output(tokens: Vec<Token>) -> LinderaResult<()> {
     let mut it = tokens.iter().peekable();
     while let Some(token) = it.next() {
@@ -252,6 +433,27 @@ fn tokenize(args: TokenizeArgs) -> LinderaResult<()> {
             break;
         }
 
+        if let Some(n) = args.nbest {
+            let nbest = tokenizer.tokenize_nbest(text.trim(), n)?;
+
+            match output_format {
+                Format::Mecab => {
+                    for (tokens, _cost) in nbest {
+                        mecab_output(tokens)?;
+                    }
+                }
+                Format::Json => {
+                    json_nbest_output(nbest)?;
+                }
+                Format::Wakati => {
+                    for (tokens, _cost) in nbest {
+                        wakati_output(tokens)?;
+                    }
+                }
+            }
+            continue;
+        }
+
         let tokens = tokenizer.tokenize(text.trim())?;
 
         match output_format {
@@ -270,8 +472,87 @@ fn tokenize(args: TokenizeArgs) -> LinderaResult<()> {
     Ok(())
 }
 
+fn lattice(args: LatticeArgs) -> LinderaResult<()> {
+    let mut builder = TokenizerBuilder::new()?;
+
+    // Set kind of dictionary
+    if let Some(ref dic_type) = args.dic_type {
+        builder.set_segmenter_dictionary_kind(dic_type);
+    }
+    // Set dictionary directory path
+    if let Some(dic_dir) = args.dic_dir {
+        builder.set_segmenter_dictionary_path(dic_dir.as_path());
+    }
+
+    // Set user dictionary file path
+    if let Some(user_dic_file) = args.user_dic_file {
+        builder.set_segmenter_user_dictionary_path(user_dic_file.as_path());
+
+        // If user dictionary file path is specified, set kind of user dictionary or not
+        if let Some(ref dic_type) = args.dic_type {
+            builder.set_segmenter_user_dictionary_kind(dic_type);
+        }
+    }
+
+    // Mode
+    builder.set_segmenter_mode(&args.mode);
+
+    // Tokenizer
+    let tokenizer = builder
+        .build()
+        .map_err(|err| LinderaErrorKind::Args.with_error(err))?;
+
+    // output format
+    let output_format = LatticeFormat::from_str(args.output_format.as_str())?;
+
+    // input file
+    let mut reader: Box<dyn BufRead> = if let Some(input_file) = args.input_file {
+        Box::new(BufReader::new(File::open(input_file).map_err(|err| {
+            LinderaErrorKind::Io.with_error(anyhow::anyhow!(err))
+        })?))
+    } else {
+        Box::new(BufReader::new(io::stdin()))
+    };
+
+    loop {
+        // read the text to be analyzed from stdin
+        let mut text = String::new();
+        let size = reader
+            .read_line(&mut text)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        if size == 0 {
+            // EOS
+            break;
+        }
+
+        let dumps = tokenizer.segmenter.dump_lattice(text.trim());
+
+        match output_format {
+            LatticeFormat::Dot => {
+                for dump in dumps {
+                    print!("{}", dump.to_dot());
+                }
+            }
+            LatticeFormat::Json => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&dumps).map_err(|err| {
+                        LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err))
+                    })?
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn build(args: BuildArgs) -> LinderaResult<()> {
-    let builder = resolve_builder(args.dic_type)?;
+    let mut builder = resolve_builder(args.dic_type)?;
+    if let Some(algorithm) = args.compress {
+        builder.set_compress_algorithm(algorithm);
+    }
+    builder.set_reading_index(args.reading_index);
 
     if args.build_user_dic {
         let output_file = if let Some(filename) = args.src_path.file_name() {
@@ -281,8 +562,45 @@ fn build(args: BuildArgs) -> LinderaResult<()> {
         } else {
             return Err(LinderaErrorKind::Io.with_error(anyhow::anyhow!("failed to get filename")));
         };
-        builder.build_user_dictionary(&args.src_path, &output_file)
+        let context_ids = match args.dic_dir {
+            Some(dic_dir) => {
+                let dictionary = load_dictionary_from_path(&dic_dir)?;
+                let Some(context_ids) = dictionary.context_ids()? else {
+                    return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                        "{} was built without context ids, rebuild it to resolve those of the user dictionary",
+                        dic_dir.display()
+                    )));
+                };
+                Some(context_ids.clone())
+            }
+            None => None,
+        };
+        builder.build_user_dictionary(&args.src_path, &output_file, context_ids)
+    } else if args.dest_path.extension() == Some(OsStr::new(DICTIONARY_FILE_EXTENSION)) {
+        builder.build_dictionary_file(&args.src_path, &args.dest_path)
     } else {
         builder.build_dictionary(&args.src_path, &args.dest_path)
     }
 }
+
+fn dump(args: DumpArgs) -> LinderaResult<()> {
+    if args.dump_user_dic {
+        let output_file = if let Some(filename) = args.src_path.file_name() {
+            let mut output_file = Path::new(&args.dest_path).join(filename);
+            output_file.set_extension("csv");
+            output_file
+        } else {
+            return Err(LinderaErrorKind::Io.with_error(anyhow::anyhow!("failed to get filename")));
+        };
+        let Some(dic_type) = args.dic_type else {
+            return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                "--dictionary-kind is required to dump a user dictionary"
+            )));
+        };
+        let user_dictionary = load_user_dictionary_from_bin(&args.src_path)?;
+        dump_user_dictionary(&user_dictionary, dic_type, &output_file)
+    } else {
+        let dictionary = load_dictionary_from_path(&args.src_path)?;
+        dump_dictionary(&dictionary, &args.dest_path)
+    }
+}
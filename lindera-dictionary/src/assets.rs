@@ -104,7 +104,8 @@ pub async fn fetch(
     let output_dir = build_dir.join(params.output_dir);
 
     // Fast path where the data is already in cache
-    if is_cache && output_dir.is_dir() {
+    // (dictionaries cached before metadata was written are rebuilt)
+    if is_cache && output_dir.join("metadata.json").is_file() {
         return Ok(());
     }
 
@@ -30,6 +30,18 @@ pub fn compress(data: &[u8], algorithm: Algorithm) -> anyhow::Result<CompressedD
             Ok(CompressedData::new(algorithm, e.finish()?))
         }
         Algorithm::Raw => Ok(CompressedData::new(algorithm, data.to_vec())),
+        #[cfg(feature = "zstd")]
+        Algorithm::Zstd => Ok(CompressedData::new(
+            algorithm,
+            zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)?,
+        )),
+        #[cfg(feature = "lz4")]
+        Algorithm::Lz4 => Ok(CompressedData::new(
+            algorithm,
+            lz4_flex::compress_prepend_size(data),
+        )),
+        #[allow(unreachable_patterns)]
+        algorithm => Err(crate::decompress::unsupported(algorithm)),
     }
 }
 
@@ -58,5 +70,21 @@ mod tests {
         let data = decompress(compress_data).unwrap();
 
         assert_eq!(&buf, &data);
+
+        #[cfg(feature = "zstd")]
+        assert_eq!(
+            decompress(compress(&buf, Algorithm::Zstd).unwrap()).unwrap(),
+            buf
+        );
+        #[cfg(not(feature = "zstd"))]
+        assert!(compress(&buf, Algorithm::Zstd).is_err());
+
+        #[cfg(feature = "lz4")]
+        assert_eq!(
+            decompress(compress(&buf, Algorithm::Lz4).unwrap()).unwrap(),
+            buf
+        );
+        #[cfg(not(feature = "lz4"))]
+        assert!(compress(&buf, Algorithm::Lz4).is_err());
     }
 }
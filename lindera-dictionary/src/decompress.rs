@@ -1,14 +1,60 @@
+use std::fmt;
 use std::io::Read;
+use std::str::FromStr;
 
+use byteorder::{ByteOrder, LittleEndian};
 use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+use crate::error::{LinderaError, LinderaErrorKind};
+use crate::LinderaResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Algorithm {
     Deflate,
     Zlib,
     Gzip,
     Raw,
+    /// Zstandard, available with the `zstd` feature.
+    Zstd,
+    /// LZ4, available with the `lz4` feature.
+    Lz4,
+}
+
+impl Algorithm {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Algorithm::Deflate => "deflate",
+            Algorithm::Zlib => "zlib",
+            Algorithm::Gzip => "gzip",
+            Algorithm::Raw => "raw",
+            Algorithm::Zstd => "zstd",
+            Algorithm::Lz4 => "lz4",
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = LinderaError;
+
+    fn from_str(input: &str) -> Result<Algorithm, Self::Err> {
+        match input {
+            "deflate" => Ok(Algorithm::Deflate),
+            "zlib" => Ok(Algorithm::Zlib),
+            "gzip" => Ok(Algorithm::Gzip),
+            "raw" | "none" => Ok(Algorithm::Raw),
+            "zstd" => Ok(Algorithm::Zstd),
+            "lz4" => Ok(Algorithm::Lz4),
+            _ => Err(LinderaErrorKind::Args
+                .with_error(anyhow::anyhow!("Invalid compression algorithm: {}", input))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +67,80 @@ impl CompressedData {
     pub fn new(algorithm: Algorithm, data: Vec<u8>) -> Self {
         CompressedData { algorithm, data }
     }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// The magic number that starts the dictionary files written compressed by
+/// [`compress_write`](crate::util::compress_write).
+pub const COMPRESSED_MAGIC: &[u8; 8] = b"LNDRZIP\0";
+
+/// The length of the header of a bincode-serialized [`CompressedData`]: the algorithm as a `u32`
+/// and the length of the compressed bytes as a `u64`.
+const ENVELOPE_HEADER_LEN: usize = 12;
+
+impl CompressedData {
+    /// Serializes the data with [`COMPRESSED_MAGIC`] in front of it.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = COMPRESSED_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    /// Reads a dictionary file written by [`compress_write`](crate::util::compress_write).
+    ///
+    /// Returns `None` if the file is raw data. A compressed file starts with
+    /// [`COMPRESSED_MAGIC`], followed by the bincode-serialized [`CompressedData`]. Files written
+    /// before the magic number was introduced are still recognized by their envelope alone: an
+    /// algorithm as a `u32`, then the length of the compressed bytes as a `u64` that accounts for
+    /// exactly the rest of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file starts with the magic number but its envelope is truncated,
+    /// has trailing bytes or names an unknown algorithm.
+    pub fn from_bytes(data: &[u8]) -> LinderaResult<Option<CompressedData>> {
+        let envelope = match data.strip_prefix(COMPRESSED_MAGIC.as_slice()) {
+            Some(envelope) => {
+                check_envelope(envelope)?;
+                envelope
+            }
+            None if check_envelope(data).is_ok() => data,
+            None => return Ok(None),
+        };
+        bincode::deserialize(envelope)
+            .map(Some)
+            .map_err(|err| LinderaErrorKind::Decompress.with_error(anyhow::anyhow!(err)))
+    }
+}
+
+/// Checks that `envelope` is a bincode-serialized [`CompressedData`] of a known algorithm whose
+/// length matches the data.
+fn check_envelope(envelope: &[u8]) -> LinderaResult<()> {
+    let invalid =
+        |message: String| Err(LinderaErrorKind::Decompress.with_error(anyhow::anyhow!(message)));
+
+    if envelope.len() < ENVELOPE_HEADER_LEN {
+        return invalid(format!(
+            "compressed data is truncated: {} bytes is too short for its header",
+            envelope.len()
+        ));
+    }
+    let algorithm = LittleEndian::read_u32(&envelope[0..4]);
+    if algorithm > Algorithm::Lz4 as u32 {
+        return invalid(format!("unknown compression algorithm: {}", algorithm));
+    }
+    let len = LittleEndian::read_u64(&envelope[4..12]);
+    let actual_len = (envelope.len() - ENVELOPE_HEADER_LEN) as u64;
+    if len != actual_len {
+        return invalid(format!(
+            "compressed data is truncated or corrupted: expected {} bytes, found {}",
+            len, actual_len
+        ));
+    }
+    Ok(())
 }
 
 pub fn decompress(data: CompressedData) -> anyhow::Result<Vec<u8>> {
@@ -44,5 +164,81 @@ pub fn decompress(data: CompressedData) -> anyhow::Result<Vec<u8>> {
             Ok(output_data)
         }
         Algorithm::Raw => Ok(data.data),
+        #[cfg(feature = "zstd")]
+        Algorithm::Zstd => Ok(zstd::decode_all(data.data.as_slice())?),
+        #[cfg(feature = "lz4")]
+        Algorithm::Lz4 => Ok(lz4_flex::decompress_size_prepended(&data.data)?),
+        #[allow(unreachable_patterns)]
+        algorithm => Err(unsupported(algorithm)),
+    }
+}
+
+/// The error for an algorithm whose cargo feature is not enabled.
+pub(crate) fn unsupported(algorithm: Algorithm) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} compression is not supported: enable the `{}` feature",
+        algorithm,
+        algorithm
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_from_bytes() {
+        use crate::compress::compress;
+        use crate::decompress::{Algorithm, CompressedData, COMPRESSED_MAGIC};
+
+        let data = b"lindera dictionary data".to_vec();
+        for algorithm in [
+            Algorithm::Deflate,
+            Algorithm::Zlib,
+            Algorithm::Gzip,
+            Algorithm::Raw,
+        ] {
+            let compressed = compress(&data, algorithm).unwrap();
+            let bytes = compressed.to_bytes().unwrap();
+            assert!(bytes.starts_with(COMPRESSED_MAGIC));
+            assert!(CompressedData::from_bytes(&bytes).unwrap().is_some());
+
+            // Files written without the magic number are still recognized.
+            let legacy = bincode::serialize(&compressed).unwrap();
+            assert!(CompressedData::from_bytes(&legacy).unwrap().is_some());
+
+            // A truncated or extended file with the magic number is an error, not raw data.
+            assert!(CompressedData::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+            assert!(CompressedData::from_bytes(&bytes[..COMPRESSED_MAGIC.len() + 4]).is_err());
+            assert!(CompressedData::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+        }
+
+        // An unknown algorithm after the magic number is an error too.
+        let mut bytes = compress(&data, Algorithm::Raw).unwrap().to_bytes().unwrap();
+        bytes[COMPRESSED_MAGIC.len()] = 42;
+        assert!(CompressedData::from_bytes(&bytes).is_err());
+
+        assert!(CompressedData::from_bytes(&data).unwrap().is_none());
+        assert!(CompressedData::from_bytes(&[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_algorithm_from_str() {
+        use std::str::FromStr;
+
+        use crate::decompress::Algorithm;
+
+        for algorithm in [
+            Algorithm::Deflate,
+            Algorithm::Zlib,
+            Algorithm::Gzip,
+            Algorithm::Raw,
+            Algorithm::Zstd,
+            Algorithm::Lz4,
+        ] {
+            assert_eq!(
+                Algorithm::from_str(&algorithm.to_string()).unwrap(),
+                algorithm
+            );
+        }
+        assert!(Algorithm::from_str("brotli").is_err());
     }
 }
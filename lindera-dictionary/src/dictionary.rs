@@ -1,8 +1,14 @@
 pub mod character_definition;
 pub mod connection_cost_matrix;
+pub mod context_id;
+pub mod metadata;
 pub mod prefix_dictionary;
+pub mod reading_index;
 pub mod unknown_dictionary;
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
 use std::str;
 
 use byteorder::{ByteOrder, LittleEndian};
@@ -11,66 +17,343 @@ use serde::{Deserialize, Serialize};
 
 use crate::dictionary::character_definition::CharacterDefinition;
 use crate::dictionary::connection_cost_matrix::ConnectionCostMatrix;
+use crate::dictionary::context_id::ContextIds;
+use crate::dictionary::metadata::Metadata;
 use crate::dictionary::prefix_dictionary::PrefixDictionary;
+use crate::dictionary::reading_index::ReadingIndex;
 use crate::dictionary::unknown_dictionary::UnknownDictionary;
+use crate::dictionary_builder::{DictionaryBuilder, UserDictionaryEntries};
 use crate::error::LinderaErrorKind;
+use crate::util::Deferred;
+use crate::viterbi::{WordEntry, WordId};
 use crate::LinderaResult;
 
 pub static UNK: Lazy<Vec<&str>> = Lazy::new(|| vec!["UNK"]);
 
+/// A dictionary entry found by a lookup, with its details decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupEntry<'a> {
+    pub surface: Cow<'a, str>,
+    pub word_id: WordId,
+    pub word_cost: i16,
+    pub left_id: u16,
+    pub right_id: u16,
+    pub details: Vec<&'a str>,
+}
+
+impl<'a> LookupEntry<'a> {
+    fn new(surface: Cow<'a, str>, word_entry: WordEntry, details: Vec<&'a str>) -> Self {
+        LookupEntry {
+            surface,
+            word_id: word_entry.word_id,
+            word_cost: word_entry.word_cost,
+            left_id: word_entry.left_id,
+            right_id: word_entry.right_id,
+            details,
+        }
+    }
+
+    /// Returns whether the part-of-speech of the entry starts with `pos`, given as comma-separated
+    /// detail fields such as `名詞,固有名詞`.
+    pub fn has_pos(&self, pos: &str) -> bool {
+        has_pos(&self.details, pos)
+    }
+}
+
+/// A predicted entry ranked by its cost and then by the order of its surface.
+struct RankedEntry<'a> {
+    rank: (i16, usize),
+    entry: LookupEntry<'a>,
+}
+
+impl PartialEq for RankedEntry<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank
+    }
+}
+
+impl Eq for RankedEntry<'_> {}
+
+impl PartialOrd for RankedEntry<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedEntry<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+/// Returns the `limit` entries of lowest cost among the words of `prefix_dictionary` whose
+/// surface starts with `prefix`, filtered by `pos`. Entries of the same cost are in the byte
+/// order of their surfaces.
+///
+/// Only the best `limit` entries are kept while the trie is walked, and the details of an entry
+/// are only decoded if it ranks among them.
+fn rank_predictions<'a>(
+    prefix_dictionary: &PrefixDictionary,
+    prefix: &str,
+    word_details: impl Fn(usize) -> Vec<&'a str>,
+    limit: usize,
+    pos: Option<&str>,
+) -> Vec<LookupEntry<'a>> {
+    if limit == 0 {
+        return Vec::new();
+    }
+
+    // A max-heap, so that the worst of the best entries so far is the one to evict.
+    let mut best = BinaryHeap::with_capacity(limit);
+    let mut order = 0;
+    prefix_dictionary.for_each_prediction(prefix, |surface, word_entry| {
+        let rank = (word_entry.word_cost, order);
+        order += 1;
+        if best.len() == limit
+            && best
+                .peek()
+                .is_some_and(|worst: &RankedEntry| rank >= worst.rank)
+        {
+            return;
+        }
+
+        let details = match pos {
+            Some(pos) => {
+                let details = word_details(word_entry.word_id.id as usize);
+                if !has_pos(&details, pos) {
+                    return;
+                }
+                details
+            }
+            // Decoded once the best entries are known.
+            None => Vec::new(),
+        };
+
+        if best.len() == limit {
+            best.pop();
+        }
+        best.push(RankedEntry {
+            rank,
+            entry: LookupEntry::new(Cow::Owned(surface.to_string()), word_entry, details),
+        });
+    });
+
+    best.into_sorted_vec()
+        .into_iter()
+        .map(|ranked| {
+            let mut entry = ranked.entry;
+            if pos.is_none() {
+                entry.details = word_details(entry.word_id.id as usize);
+            }
+            entry
+        })
+        .collect()
+}
+
+/// Returns whether the details start with `pos`, given as comma-separated detail fields.
+fn has_pos(details: &[&str], pos: &str) -> bool {
+    let pos = pos.split(',').collect::<Vec<_>>();
+    details.len() >= pos.len() && details.iter().zip(pos.iter()).all(|(a, b)| a == b)
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Dictionary {
-    pub prefix_dictionary: PrefixDictionary<Vec<u8>>,
+    pub prefix_dictionary: PrefixDictionary,
     pub connection_cost_matrix: ConnectionCostMatrix,
     pub character_definition: CharacterDefinition,
     pub unknown_dictionary: UnknownDictionary,
+    /// How the dictionary was built, if it was built with metadata. See `metadata`.
+    pub metadata: Option<Deferred<Metadata>>,
+    /// The index of the readings of the words, if the dictionary was built with one.
+    pub reading_index: Option<ReadingIndex>,
+    /// The context ids of the parts of speech, if the dictionary was built with them. See
+    /// `context_ids`.
+    pub context_ids: Option<Deferred<ContextIds>>,
 }
 
 impl Dictionary {
-    pub fn word_details(&self, word_id: usize) -> Vec<&str> {
-        if 4 * word_id >= self.prefix_dictionary.words_idx_data.len() {
-            return vec![];
-        }
+    /// Returns how the dictionary was built, if it was built with metadata. It is deserialized the
+    /// first time it is used.
+    pub fn metadata(&self) -> LinderaResult<Option<&Metadata>> {
+        self.metadata.as_ref().map(Deferred::get).transpose()
+    }
+
+    /// Returns the context ids of the parts of speech, if the dictionary was built with them. They
+    /// are deserialized the first time they are used.
+    pub fn context_ids(&self) -> LinderaResult<Option<&ContextIds>> {
+        self.context_ids.as_ref().map(Deferred::get).transpose()
+    }
+
+    /// Returns the entries whose surface is `surface`.
+    pub fn lookup<'a>(&'a self, surface: &'a str) -> Vec<LookupEntry<'a>> {
+        self.prefix_dictionary
+            .find_surface(surface)
+            .into_iter()
+            .map(|word_entry| {
+                let details = self.word_details(word_entry.word_id.id as usize);
+                LookupEntry::new(Cow::Borrowed(surface), word_entry, details)
+            })
+            .collect()
+    }
+
+    /// Returns the entries whose surface is a prefix of `text`, from the shortest surface.
+    pub fn common_prefixes<'a>(&'a self, text: &'a str) -> Vec<LookupEntry<'a>> {
+        self.prefix_dictionary
+            .prefix(text)
+            .map(|(len, word_entry)| {
+                let details = self.word_details(word_entry.word_id.id as usize);
+                LookupEntry::new(Cow::Borrowed(&text[..len]), word_entry, details)
+            })
+            .collect()
+    }
 
-        let idx: usize = match LittleEndian::read_u32(
-            &self.prefix_dictionary.words_idx_data[4 * word_id..][..4],
+    /// Returns at most `limit` entries whose surface starts with `prefix`, from the lowest word
+    /// cost. If `pos` is given, only the entries whose part-of-speech starts with it are returned.
+    pub fn predict(&self, prefix: &str, limit: usize, pos: Option<&str>) -> Vec<LookupEntry<'_>> {
+        rank_predictions(
+            &self.prefix_dictionary,
+            prefix,
+            |word_id| self.word_details(word_id),
+            limit,
+            pos,
         )
-        .try_into()
-        {
-            Ok(value) => value,
-            Err(_) => return UNK.to_vec(), // return empty vector if conversion fails
-        };
-        let data = &self.prefix_dictionary.words_data[idx..];
-        let joined_details_len: usize = match LittleEndian::read_u32(data).try_into() {
-            Ok(value) => value,
-            Err(_) => return UNK.to_vec(), // return empty vector if conversion fails
-        };
-        let joined_details_bytes: &[u8] =
-            &self.prefix_dictionary.words_data[idx + 4..idx + 4 + joined_details_len];
-
-        let mut details = Vec::new();
-        for bytes in joined_details_bytes.split(|&b| b == 0) {
-            let detail = match str::from_utf8(bytes) {
-                Ok(s) => s,
-                Err(_) => return UNK.to_vec(), // return empty vector if conversion fails
-            };
-            details.push(detail);
-        }
-        details
     }
+
+    /// Returns the entries whose reading is `reading`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dictionary was built without a reading index.
+    pub fn lookup_by_reading(&self, reading: &str) -> LinderaResult<Vec<LookupEntry<'_>>> {
+        let reading_index = self.reading_index()?;
+        Ok(self.reading_entries(reading_index.find_reading(reading)))
+    }
+
+    /// Returns the entries whose reading starts with `prefix`, in the byte order of the readings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dictionary was built without a reading index.
+    pub fn lookup_by_reading_prefix(&self, prefix: &str) -> LinderaResult<Vec<LookupEntry<'_>>> {
+        let reading_index = self.reading_index()?;
+        Ok(self.reading_entries(reading_index.predict(prefix)))
+    }
+
+    fn reading_index(&self) -> LinderaResult<&ReadingIndex> {
+        self.reading_index.as_ref().ok_or_else(|| {
+            LinderaErrorKind::Dictionary.with_error(anyhow::anyhow!(
+                "the dictionary was built without a reading index"
+            ))
+        })
+    }
+
+    fn reading_entries<'a>(&'a self, words: Vec<(&'a str, u32)>) -> Vec<LookupEntry<'a>> {
+        words
+            .into_iter()
+            .filter_map(|(surface, word_id)| {
+                let word_entry = self
+                    .prefix_dictionary
+                    .find_surface(surface)
+                    .into_iter()
+                    .find(|word_entry| word_entry.word_id.id == word_id)?;
+                let details = self.word_details(word_id as usize);
+                Some(LookupEntry::new(
+                    Cow::Borrowed(surface),
+                    word_entry,
+                    details,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn word_details(&self, word_id: usize) -> Vec<&str> {
+        self.prefix_dictionary.word_details(word_id)
+    }
+}
+
+/// A part of a user dictionary word that is segmented into several tokens.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WordPart {
+    pub surface: String,
+    pub details: Vec<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct UserDictionary {
-    pub dict: PrefixDictionary<Vec<u8>>,
+    pub dict: PrefixDictionary,
+    /// The parts of the words that are segmented into several tokens, by word id.
+    pub segmentations: BTreeMap<u32, Vec<WordPart>>,
+}
+
+// The layout of user dictionaries built before custom segmentations were supported.
+#[derive(Deserialize)]
+struct UserDictionaryWithoutSegmentations {
+    dict: PrefixDictionary,
 }
 
 impl UserDictionary {
+    /// Returns a builder of a user dictionary whose entries are added in memory, for the kind of
+    /// dictionary built by `builder`.
+    pub fn builder(builder: &dyn DictionaryBuilder) -> UserDictionaryEntries {
+        UserDictionaryEntries::new(builder.user_dictionary_builder())
+    }
+
     pub fn load(user_dict_data: &[u8]) -> LinderaResult<UserDictionary> {
         bincode::deserialize(user_dict_data)
+            .or_else(|err| {
+                // Fall back to the older layout, which has no custom segmentations.
+                bincode::deserialize::<UserDictionaryWithoutSegmentations>(user_dict_data)
+                    .map(|user_dict| UserDictionary {
+                        dict: user_dict.dict,
+                        segmentations: BTreeMap::new(),
+                    })
+                    .map_err(|_| err)
+            })
             .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
     }
 
+    /// Returns the parts of a word that is segmented into several tokens, if any.
+    pub fn word_parts(&self, word_id: usize) -> Option<&[WordPart]> {
+        self.segmentations
+            .get(&(word_id as u32))
+            .map(|parts| parts.as_slice())
+    }
+
+    /// Returns the entries whose surface is `surface`.
+    pub fn lookup<'a>(&'a self, surface: &'a str) -> Vec<LookupEntry<'a>> {
+        self.dict
+            .find_surface(surface)
+            .into_iter()
+            .map(|word_entry| {
+                let details = self.word_details(word_entry.word_id.id as usize);
+                LookupEntry::new(Cow::Borrowed(surface), word_entry, details)
+            })
+            .collect()
+    }
+
+    /// Returns the entries whose surface is a prefix of `text`, from the shortest surface.
+    pub fn common_prefixes<'a>(&'a self, text: &'a str) -> Vec<LookupEntry<'a>> {
+        self.dict
+            .prefix(text)
+            .map(|(len, word_entry)| {
+                let details = self.word_details(word_entry.word_id.id as usize);
+                LookupEntry::new(Cow::Borrowed(&text[..len]), word_entry, details)
+            })
+            .collect()
+    }
+
+    /// Returns at most `limit` entries whose surface starts with `prefix`, from the lowest word
+    /// cost. If `pos` is given, only the entries whose part-of-speech starts with it are returned.
+    pub fn predict(&self, prefix: &str, limit: usize, pos: Option<&str>) -> Vec<LookupEntry<'_>> {
+        rank_predictions(
+            &self.dict,
+            prefix,
+            |word_id| self.word_details(word_id),
+            limit,
+            pos,
+        )
+    }
+
     pub fn word_details(&self, word_id: usize) -> Vec<&str> {
         if 4 * word_id >= self.dict.words_idx_data.len() {
             return UNK.to_vec(); // return empty vector if conversion fails
@@ -83,3 +366,189 @@ impl UserDictionary {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::dictionary::{LookupEntry, UserDictionary};
+
+    #[test]
+    fn test_load_user_dictionary_without_segmentations() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../resources")
+            .join("ipadic_simple_userdic.bin");
+        let user_dict = UserDictionary::load(&std::fs::read(path).unwrap()).unwrap();
+        assert!(user_dict.segmentations.is_empty());
+        assert_eq!(user_dict.word_details(0)[0], "カスタム名詞");
+    }
+
+    #[test]
+    fn test_lookup() {
+        use crate::test_utils::mini_ipadic;
+
+        let dictionary = mini_ipadic();
+        let entries = dictionary.lookup("東京");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].surface, "東京");
+        assert!(entries[0].word_id.is_system());
+        assert_eq!(entries[0].word_cost, 3000);
+        assert_eq!((entries[0].left_id, entries[0].right_id), (4, 4));
+        assert_eq!(entries[0].details[0], "名詞");
+        assert_eq!(entries[0].details[7], "トウキョウ");
+
+        assert!(dictionary.lookup("東京タワー").is_empty());
+        assert!(dictionary.lookup("").is_empty());
+    }
+
+    #[test]
+    fn test_common_prefixes() {
+        use crate::test_utils::mini_ipadic;
+
+        let dictionary = mini_ipadic();
+        let entries = dictionary.common_prefixes("東京都に行く");
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.surface.as_ref(), entry.details[7]))
+                .collect::<Vec<_>>(),
+            vec![
+                ("東", "ヒガシ"),
+                ("東京", "トウキョウ"),
+                ("東京都", "トウキョウト")
+            ]
+        );
+        assert!(dictionary.common_prefixes("大阪").is_empty());
+    }
+
+    #[test]
+    fn test_lookup_user_dictionary() {
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../resources")
+            .join("mini_ipadic_userdic.csv");
+        let user_dict = IpadicBuilder::new().build_user_dict(&path, None).unwrap();
+
+        let entries = user_dict.lookup("東京都庁");
+        assert_eq!(entries.len(), 1);
+        assert!(!entries[0].word_id.is_system());
+        assert_eq!(entries[0].word_cost, 1000);
+        assert_eq!(entries[0].details[7], "トウキョウトチョウ");
+
+        let entries = user_dict.common_prefixes("東京都庁舎");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].surface, "東京都庁");
+
+        let entries = user_dict.predict("東京", 10, None);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].surface, "東京都庁");
+    }
+
+    #[test]
+    fn test_predict() {
+        use crate::test_utils::mini_ipadic;
+
+        let dictionary = mini_ipadic();
+        let surfaces = |entries: Vec<LookupEntry>| {
+            entries
+                .into_iter()
+                .map(|entry| (entry.surface.into_owned(), entry.word_cost))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            surfaces(dictionary.predict("東", 10, None)),
+            vec![
+                ("東京".to_string(), 3000),
+                ("東".to_string(), 4000),
+                ("東京都".to_string(), 5000)
+            ]
+        );
+        assert_eq!(
+            surfaces(dictionary.predict("東", 2, None)),
+            vec![("東京".to_string(), 3000), ("東".to_string(), 4000)]
+        );
+        assert!(dictionary.predict("東", 0, None).is_empty());
+
+        // Only particles, out of the whole dictionary.
+        let entries = dictionary.predict("", 100, Some("助詞"));
+        assert_eq!(entries.len(), 4);
+        assert!(entries.iter().all(|entry| entry.details[0] == "助詞"));
+        assert!(entries
+            .windows(2)
+            .all(|pair| pair[0].word_cost <= pair[1].word_cost));
+        assert!(dictionary
+            .predict("東", 10, Some("名詞,固有名詞,人名"))
+            .is_empty());
+
+        // Keeping only the best entries gives the same result as sorting all of them.
+        let mut all = dictionary.prefix_dictionary.predict("");
+        all.sort_by_key(|(_, word_entry)| word_entry.word_cost);
+        for limit in 1..=all.len() {
+            let entries = dictionary.predict("", limit, None);
+            assert_eq!(
+                entries
+                    .iter()
+                    .map(|entry| (entry.surface.to_string(), entry.word_id))
+                    .collect::<Vec<_>>(),
+                all[..limit]
+                    .iter()
+                    .map(|(surface, word_entry)| (surface.clone(), word_entry.word_id))
+                    .collect::<Vec<_>>()
+            );
+            assert!(entries
+                .iter()
+                .all(|entry| entry.details == dictionary.word_details(entry.word_id.id as usize)));
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_reading() {
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::dictionary_file::DictionaryFile;
+        use crate::test_utils::{load_dictionary, mini_ipadic, mini_ipadic_dir, temp_dir};
+
+        // The index is opt-in.
+        let dictionary = mini_ipadic();
+        assert!(dictionary.reading_index.is_none());
+        assert!(dictionary.lookup_by_reading("トウキョウ").is_err());
+
+        let output_dir = temp_dir("reading-index");
+        let mut builder = IpadicBuilder::new();
+        builder.set_reading_index(true);
+        builder
+            .build_dictionary(&mini_ipadic_dir(), &output_dir)
+            .unwrap();
+        let output_file = temp_dir("reading-index.linderadic");
+        builder
+            .build_dictionary_file(&mini_ipadic_dir(), &output_file)
+            .unwrap();
+
+        for dictionary in [
+            load_dictionary(&output_dir),
+            DictionaryFile::map(&output_file)
+                .unwrap()
+                .load_dictionary()
+                .unwrap(),
+        ] {
+            let entries = dictionary.lookup_by_reading("トウキョウ").unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].surface, "東京");
+            assert_eq!(entries[0], dictionary.lookup("東京")[0]);
+
+            let entries = dictionary.lookup_by_reading_prefix("トウキョウ").unwrap();
+            assert_eq!(
+                entries
+                    .iter()
+                    .map(|entry| entry.surface.as_ref())
+                    .collect::<Vec<_>>(),
+                vec!["東京", "東京都"]
+            );
+            assert_eq!(dictionary.lookup_by_reading_prefix("").unwrap().len(), 19);
+            assert!(dictionary.lookup_by_reading("オオサカ").unwrap().is_empty());
+            assert!(dictionary.lookup_by_reading("東京").unwrap().is_empty());
+        }
+    }
+}
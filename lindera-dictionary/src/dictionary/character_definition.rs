@@ -1,6 +1,10 @@
+use std::str;
+
+use byteorder::{ByteOrder, LittleEndian};
 use serde::{Deserialize, Serialize};
 
 use crate::error::LinderaErrorKind;
+use crate::util::{check_offsets, check_sections, offset_range, section, write_sections, Data};
 use crate::LinderaResult;
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
@@ -41,41 +45,270 @@ impl<T: Copy + Clone> LookupTable<T> {
             .unwrap_or_else(|val| val - 1);
         &self.values[idx][..]
     }
+
+    /// Returns true if every target has values, which a table deserialized from corrupted data
+    /// may not.
+    pub fn is_complete(&self) -> bool {
+        self.boundaries.first() == Some(&0) && self.boundaries.len() == self.values.len()
+    }
+
+    /// Returns the values of all the ranges.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.values.iter().flatten()
+    }
+
+    /// Returns the ranges between two consecutive boundaries, with their values. The range after
+    /// the last boundary is not included.
+    pub fn ranges(&self) -> Vec<(u32, u32, &[T])> {
+        self.boundaries
+            .windows(2)
+            .zip(self.values.iter())
+            .map(|(bounds, values)| (bounds[0], bounds[1] - 1, &values[..]))
+            .collect()
+    }
 }
 
+/// The magic number of character definitions, which older versions wrote with bincode instead.
+const MAGIC: &[u8; 4] = b"LCHR";
+
+// The sections of a character definition, whose numbers are little-endian `u32`s.
+/// The invoke and group flags and the length of each category.
+const CATEGORIES: usize = 0;
+/// The byte offsets of the names of the categories in `NAMES`, and the end of the last one.
+const NAME_OFFSETS: usize = 1;
+const NAMES: usize = 2;
+/// The first character of each range of characters of the lookup table.
+const BOUNDARIES: usize = 3;
+/// The byte offsets of the categories of each range in `VALUES`, and the end of the last one.
+const VALUE_OFFSETS: usize = 4;
+/// The category ids of the ranges.
+const VALUES: usize = 5;
+const SECTION_COUNT: usize = 6;
+
+const CATEGORY_SIZE: usize = 12;
+
+/// The categories of characters of `char.def`, in a layout that is read in place, so that a
+/// definition embedded in the binary or mapped from a file is used without being copied or
+/// parsed.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct CharacterDefinition {
-    pub category_definitions: Vec<CategoryData>,
-    pub category_names: Vec<String>,
-    pub mapping: LookupTable<CategoryId>,
+    pub data: Data,
+}
+
+// The layout of character definitions built by earlier versions.
+#[derive(Deserialize)]
+struct CharacterDefinitionV1 {
+    category_definitions: Vec<CategoryData>,
+    category_names: Vec<String>,
+    mapping: LookupTable<CategoryId>,
 }
 
 impl CharacterDefinition {
-    pub fn categories(&self) -> &[String] {
-        &self.category_names[..]
+    /// Lays out the categories, with their names, and the table of the categories of the
+    /// characters.
+    pub fn new(
+        category_definitions: &[CategoryData],
+        category_names: &[String],
+        mapping: &LookupTable<CategoryId>,
+    ) -> CharacterDefinition {
+        let mut sections = vec![Vec::new(); SECTION_COUNT];
+        for category in category_definitions.iter() {
+            for value in [
+                category.invoke as u32,
+                category.group as u32,
+                category.length,
+            ] {
+                sections[CATEGORIES].extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        sections[NAME_OFFSETS].extend_from_slice(&0u32.to_le_bytes());
+        for name in category_names.iter() {
+            sections[NAMES].extend_from_slice(name.as_bytes());
+            let offset = sections[NAMES].len() as u32;
+            sections[NAME_OFFSETS].extend_from_slice(&offset.to_le_bytes());
+        }
+        sections[VALUE_OFFSETS].extend_from_slice(&0u32.to_le_bytes());
+        for (boundary, category_ids) in mapping.boundaries.iter().zip(mapping.values.iter()) {
+            sections[BOUNDARIES].extend_from_slice(&boundary.to_le_bytes());
+            for category_id in category_ids.iter() {
+                sections[VALUES].extend_from_slice(&(category_id.0 as u32).to_le_bytes());
+            }
+            let offset = sections[VALUES].len() as u32;
+            sections[VALUE_OFFSETS].extend_from_slice(&offset.to_le_bytes());
+        }
+        CharacterDefinition {
+            data: write_sections(MAGIC, &sections).into(),
+        }
     }
 
     pub fn load(char_def_data: &[u8]) -> LinderaResult<CharacterDefinition> {
-        bincode::deserialize(char_def_data)
-            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+        Self::from_data(char_def_data.to_vec().into())
+    }
+
+    /// Creates a character definition that borrows data embedded in the binary, without copying
+    /// or parsing it.
+    pub fn load_static(char_def_data: &'static [u8]) -> LinderaResult<CharacterDefinition> {
+        if !char_def_data.starts_with(MAGIC) {
+            return Self::load(char_def_data);
+        }
+        Ok(CharacterDefinition {
+            data: char_def_data.into(),
+        })
+    }
+
+    /// Creates a character definition that reads the given data in place, after checking that
+    /// its lookups stay in bounds. A definition built by an earlier version is converted.
+    pub fn from_data(char_def_data: Data) -> LinderaResult<CharacterDefinition> {
+        let char_def_data = if char_def_data.starts_with(MAGIC) {
+            char_def_data
+        } else {
+            let char_def = bincode::deserialize::<CharacterDefinitionV1>(&char_def_data)
+                .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))?;
+            if char_def.mapping.boundaries.len() != char_def.mapping.values.len() {
+                return Err(LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(
+                    "invalid character definition: the lookup table is incomplete"
+                )));
+            }
+            Self::new(
+                &char_def.category_definitions,
+                &char_def.category_names,
+                &char_def.mapping,
+            )
+            .data
+        };
+        check(&char_def_data).map_err(|message| {
+            LinderaErrorKind::Deserialize
+                .with_error(anyhow::anyhow!("invalid character definition: {}", message))
+        })?;
+        Ok(CharacterDefinition {
+            data: char_def_data,
+        })
+    }
+
+    fn section(&self, index: usize) -> &[u8] {
+        section(&self.data, index)
+    }
+
+    /// Returns the names of the categories, in the order of their ids.
+    pub fn categories(&self) -> Vec<&str> {
+        (0..self.section(CATEGORIES).len() / CATEGORY_SIZE)
+            .map(|category_id| self.category_name(CategoryId(category_id)))
+            .collect()
     }
 
-    pub fn lookup_definition(&self, category_id: CategoryId) -> &CategoryData {
-        &self.category_definitions[category_id.0]
+    pub fn lookup_definition(&self, category_id: CategoryId) -> CategoryData {
+        let data = &self.section(CATEGORIES)[category_id.0 * CATEGORY_SIZE..][..CATEGORY_SIZE];
+        CategoryData {
+            invoke: LittleEndian::read_u32(&data[0..4]) != 0,
+            group: LittleEndian::read_u32(&data[4..8]) != 0,
+            length: LittleEndian::read_u32(&data[8..12]),
+        }
     }
 
     pub fn category_name(&self, category_id: CategoryId) -> &str {
-        &self.category_names[category_id.0]
+        let name = &self.section(NAMES)[offset_range(self.section(NAME_OFFSETS), category_id.0)];
+        str::from_utf8(name).unwrap_or_default()
+    }
+
+    /// Returns the categories of a character.
+    pub fn lookup_categories(&self, c: char) -> impl Iterator<Item = CategoryId> + '_ {
+        let boundaries = self.section(BOUNDARIES);
+        // The range of the character starts at the last boundary that is not after it, and the
+        // first boundary is 0.
+        let (mut low, mut high) = (0, boundaries.len() / 4);
+        while low < high {
+            let mid = (low + high) / 2;
+            if LittleEndian::read_u32(&boundaries[4 * mid..]) <= c as u32 {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        self.range_categories(low - 1)
+    }
+
+    fn range_categories(&self, index: usize) -> impl Iterator<Item = CategoryId> + '_ {
+        self.section(VALUES)[offset_range(self.section(VALUE_OFFSETS), index)]
+            .chunks_exact(4)
+            .map(|value| CategoryId(LittleEndian::read_u32(value) as usize))
     }
 
-    pub fn lookup_categories(&self, c: char) -> &[CategoryId] {
-        self.mapping.eval(c as u32)
+    /// Returns the ranges of characters between two consecutive boundaries of the lookup table,
+    /// with their categories. The range after the last boundary is not included.
+    pub fn ranges(&self) -> Vec<(u32, u32, Vec<CategoryId>)> {
+        let boundaries = self
+            .section(BOUNDARIES)
+            .chunks_exact(4)
+            .map(LittleEndian::read_u32)
+            .collect::<Vec<_>>();
+        boundaries
+            .windows(2)
+            .enumerate()
+            .map(|(index, bounds)| {
+                (
+                    bounds[0],
+                    bounds[1] - 1,
+                    self.range_categories(index).collect(),
+                )
+            })
+            .collect()
     }
 }
 
+/// Checks that the lookups in a character definition stay in bounds.
+fn check(data: &[u8]) -> Result<(), String> {
+    check_sections(data, MAGIC, SECTION_COUNT)?;
+
+    let categories = section(data, CATEGORIES);
+    if !categories.len().is_multiple_of(CATEGORY_SIZE) {
+        return Err("truncated categories".to_string());
+    }
+    let category_count = categories.len() / CATEGORY_SIZE;
+    let names = section(data, NAMES);
+    let name_offsets = section(data, NAME_OFFSETS);
+    check_offsets(name_offsets, category_count, names.len())?;
+    for category_id in 0..category_count {
+        str::from_utf8(&names[offset_range(name_offsets, category_id)])
+            .map_err(|err| err.to_string())?;
+    }
+
+    let boundaries = section(data, BOUNDARIES);
+    let boundaries = boundaries
+        .chunks_exact(4)
+        .map(LittleEndian::read_u32)
+        .collect::<Vec<_>>();
+    if boundaries.first() != Some(&0) || boundaries.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err("the lookup table does not map every character to a range".to_string());
+    }
+    let values = section(data, VALUES);
+    let value_offsets = section(data, VALUE_OFFSETS);
+    check_offsets(value_offsets, boundaries.len(), values.len())?;
+    if value_offsets
+        .chunks_exact(4)
+        .any(|offset| !LittleEndian::read_u32(offset).is_multiple_of(4))
+        || !values.len().is_multiple_of(4)
+    {
+        return Err("misaligned category ids".to_string());
+    }
+    if let Some(category_id) = values
+        .chunks_exact(4)
+        .map(LittleEndian::read_u32)
+        .find(|&category_id| category_id as usize >= category_count)
+    {
+        return Err(format!(
+            "category {} beyond the {} categories",
+            category_id, category_count
+        ));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::dictionary::character_definition::LookupTable;
+    use crate::dictionary::character_definition::{
+        CategoryData, CategoryId, CharacterDefinition, LookupTable,
+    };
 
     #[test]
     fn test_lookup_table() {
@@ -93,4 +326,78 @@ mod tests {
             assert_eq!(lookup_table.eval(i), &v[..]);
         }
     }
+
+    #[test]
+    fn test_character_definition() {
+        use serde::Serialize;
+
+        let category_definitions = vec![
+            CategoryData {
+                invoke: false,
+                group: true,
+                length: 0,
+            },
+            CategoryData {
+                invoke: true,
+                group: true,
+                length: 2,
+            },
+        ];
+        let category_names = vec!["DEFAULT".to_string(), "ALPHA".to_string()];
+        let mapping = LookupTable::from_fn(vec![0x41, 0x5B], &|c, output| {
+            if (0x41..0x5B).contains(&c) {
+                output.push(CategoryId(1));
+            }
+            output.push(CategoryId(0));
+        });
+        let char_def = CharacterDefinition::new(&category_definitions, &category_names, &mapping);
+
+        assert_eq!(char_def.categories(), vec!["DEFAULT", "ALPHA"]);
+        assert_eq!(char_def.category_name(CategoryId(1)), "ALPHA");
+        assert!(char_def.lookup_definition(CategoryId(1)).invoke);
+        assert_eq!(char_def.lookup_definition(CategoryId(1)).length, 2);
+        for c in ['0', 'A', 'Z', '[', 'あ'] {
+            assert_eq!(
+                char_def.lookup_categories(c).collect::<Vec<_>>(),
+                mapping.eval(c as u32),
+                "{}",
+                c
+            );
+        }
+        assert_eq!(
+            char_def.ranges(),
+            vec![
+                (0, 0x40, vec![CategoryId(0)]),
+                (0x41, 0x5A, vec![CategoryId(1), CategoryId(0)]),
+            ]
+        );
+
+        // The data is read in place as it is written.
+        let data: &'static [u8] = char_def.data.to_vec().leak();
+        let loaded = CharacterDefinition::load_static(data).unwrap();
+        assert_eq!(&loaded.data[..], &char_def.data[..]);
+
+        // Definitions built by earlier versions are converted.
+        #[derive(Serialize)]
+        struct CharacterDefinitionV1 {
+            category_definitions: Vec<CategoryData>,
+            category_names: Vec<String>,
+            mapping: LookupTable<CategoryId>,
+        }
+        let data = bincode::serialize(&CharacterDefinitionV1 {
+            category_definitions,
+            category_names,
+            mapping,
+        })
+        .unwrap();
+        let loaded = CharacterDefinition::load(&data).unwrap();
+        assert_eq!(&loaded.data[..], &char_def.data[..]);
+
+        // Categories beyond the definitions are found when the data is loaded.
+        let mut corrupted = char_def.data.to_vec();
+        let last = corrupted.len() - 4;
+        corrupted[last] = 2;
+        assert!(CharacterDefinition::load(&corrupted).is_err());
+        assert!(CharacterDefinition::load(&[1, 2, 3]).is_err());
+    }
 }
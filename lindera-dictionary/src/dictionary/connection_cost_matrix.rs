@@ -1,27 +1,28 @@
-use std::borrow::Cow;
-
 use byteorder::{ByteOrder, LittleEndian};
 use serde::{Deserialize, Serialize};
 
+use crate::util::Data;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ConnectionCostMatrix {
-    pub costs_data: Cow<'static, [u8]>,
+    pub costs_data: Data,
     pub backward_size: u32,
 }
 
 impl ConnectionCostMatrix {
     pub fn load_static(conn_data: &'static [u8]) -> ConnectionCostMatrix {
-        let backward_size = LittleEndian::read_i16(&conn_data[2..4]);
-        ConnectionCostMatrix {
-            costs_data: Cow::Borrowed(&conn_data[4..]),
-            backward_size: backward_size as u32,
-        }
+        Self::from_data(Data::Static(conn_data))
     }
 
     pub fn load(conn_data: &[u8]) -> ConnectionCostMatrix {
+        Self::from_data(conn_data.to_vec().into())
+    }
+
+    /// Creates a matrix that reads the costs from the given data in place, without copying it.
+    pub fn from_data(conn_data: Data) -> ConnectionCostMatrix {
         let backward_size = LittleEndian::read_i16(&conn_data[2..4]);
         ConnectionCostMatrix {
-            costs_data: Cow::Owned(conn_data[4..].to_vec()),
+            costs_data: conn_data.skip(4),
             backward_size: backward_size as u32,
         }
     }
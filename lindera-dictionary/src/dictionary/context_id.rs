@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::LinderaErrorKind;
+use crate::LinderaResult;
+
+/// The context ids of the parts of speech of a dictionary, as in its `left-id.def` and
+/// `right-id.def`, with the costs of its words by context ids.
+///
+/// They let the entries of user dictionaries that only give a part of speech get the context ids
+/// and a cost comparable to the words of the dictionary.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextIds {
+    /// The left context ids with their features, in the order of `left-id.def`.
+    pub left_ids: Vec<(u16, String)>,
+    /// The right context ids with their features, in the order of `right-id.def`.
+    pub right_ids: Vec<(u16, String)>,
+    /// The median cost of the words of each pair of left and right context ids.
+    pub costs: BTreeMap<(u16, u16), i16>,
+}
+
+impl ContextIds {
+    pub fn load(context_ids_data: &[u8]) -> LinderaResult<ContextIds> {
+        bincode::deserialize(context_ids_data)
+            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+    }
+
+    /// Parses `left-id.def` and `right-id.def`, whose lines are a context id and its features
+    /// separated by a space.
+    pub fn parse(left_id_def: &str, right_id_def: &str) -> LinderaResult<ContextIds> {
+        Ok(ContextIds {
+            left_ids: parse_id_def(left_id_def)?,
+            right_ids: parse_id_def(right_id_def)?,
+            costs: BTreeMap::new(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left_ids.is_empty() || self.right_ids.is_empty()
+    }
+
+    /// Returns the left and right context ids of a part of speech, given as the leading features
+    /// of the context ids separated by commas, such as `名詞,固有名詞,組織`.
+    ///
+    /// Among the context ids whose features start with the given ones, the one whose other
+    /// features are the most often `*` wins, so that the most general context id is chosen.
+    /// Returns `None` if several context ids are the most general ones, as the part of speech
+    /// does not tell which of them the word should get.
+    pub fn resolve(&self, pos: &str) -> Option<(u16, u16)> {
+        let fields = pos.split(',').map(str::trim).collect::<Vec<_>>();
+        Some((
+            find_context_id(&self.left_ids, &fields)?,
+            find_context_id(&self.right_ids, &fields)?,
+        ))
+    }
+
+    /// Returns the cost estimated for a word with the given context ids: the median cost of the
+    /// words with the same context ids, else of the words with the same left context id, else of
+    /// all the words. Returns `None` if the costs of the words are not known.
+    pub fn estimate_cost(&self, left_id: u16, right_id: u16) -> Option<i16> {
+        if let Some(&cost) = self.costs.get(&(left_id, right_id)) {
+            return Some(cost);
+        }
+        let same_left_id = self
+            .costs
+            .range((left_id, 0)..=(left_id, u16::MAX))
+            .map(|(_, &cost)| cost)
+            .collect::<Vec<_>>();
+        median(same_left_id).or_else(|| median(self.costs.values().copied().collect()))
+    }
+}
+
+/// Returns the median of the costs, the lower one if there are two.
+pub(crate) fn median(mut costs: Vec<i16>) -> Option<i16> {
+    costs.sort_unstable();
+    costs.get(costs.len().checked_sub(1)? / 2).copied()
+}
+
+fn parse_id_def(text: &str) -> LinderaResult<Vec<(u16, String)>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (id, features) = line.trim_end().split_once(' ').ok_or_else(|| {
+                LinderaErrorKind::Content
+                    .with_error(anyhow::anyhow!("invalid context id line: {}", line))
+            })?;
+            let id = id.parse::<u16>().map_err(|_err| {
+                LinderaErrorKind::Parse
+                    .with_error(anyhow::anyhow!("failed to parse context id: {}", line))
+            })?;
+            Ok((id, features.to_string()))
+        })
+        .collect()
+}
+
+fn find_context_id(context_ids: &[(u16, String)], fields: &[&str]) -> Option<u16> {
+    let candidates = context_ids
+        .iter()
+        .filter_map(|(id, features)| {
+            let features = features.split(',').collect::<Vec<_>>();
+            if features.len() < fields.len() || features[..fields.len()] != *fields {
+                return None;
+            }
+            let wildcards = features[fields.len()..]
+                .iter()
+                .filter(|feature| **feature == "*")
+                .count();
+            Some((*id, wildcards))
+        })
+        .collect::<Vec<_>>();
+    let max_wildcards = candidates.iter().map(|(_, wildcards)| *wildcards).max()?;
+    match candidates
+        .iter()
+        .filter(|(_, wildcards)| *wildcards == max_wildcards)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [(id, _)] => Some(*id),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_resolve() {
+        use crate::dictionary::context_id::ContextIds;
+
+        let id_def = "0 BOS/EOS,*,*,*,*,*,BOS/EOS\n\
+                      1 名詞,サ変接続,*,*,*,*,*\n\
+                      2 名詞,一般,*,*,*,*,*\n\
+                      3 名詞,固有名詞,組織,*,*,*,*\n\
+                      4 助詞,格助詞,一般,*,*,*,が\n\
+                      5 助詞,格助詞,一般,*,*,*,*\n";
+        let mut context_ids = ContextIds::parse(id_def, id_def).unwrap();
+
+        assert_eq!(context_ids.resolve("名詞,固有名詞,組織"), Some((3, 3)));
+        assert_eq!(context_ids.resolve("名詞, 一般"), Some((2, 2)));
+        // サ変接続 and 一般 are as general as each other.
+        assert_eq!(context_ids.resolve("名詞"), None);
+        assert_eq!(context_ids.resolve("助詞,格助詞"), Some((5, 5)));
+        assert_eq!(context_ids.resolve("カスタム名詞"), None);
+
+        assert_eq!(context_ids.estimate_cost(3, 3), None);
+        context_ids.costs.insert((2, 2), 3000);
+        context_ids.costs.insert((3, 2), 4000);
+        context_ids.costs.insert((3, 3), 5000);
+        context_ids.costs.insert((3, 4), 7000);
+        assert_eq!(context_ids.estimate_cost(3, 3), Some(5000));
+        assert_eq!(context_ids.estimate_cost(3, 5), Some(5000));
+        assert_eq!(context_ids.estimate_cost(1, 1), Some(4000));
+
+        assert!(ContextIds::parse("名詞,一般", id_def).is_err());
+    }
+}
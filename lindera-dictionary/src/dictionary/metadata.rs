@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::LinderaErrorKind;
+use crate::LinderaResult;
+
+/// Describes how a dictionary was built.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    /// The name of the dictionary, such as `ipadic`.
+    pub name: String,
+    /// The source the dictionary was built from, usually the name of the source archive with its
+    /// version, such as `mecab-ipadic-2.7.0-20070801`.
+    pub source: String,
+    /// The encoding of the source files.
+    pub encoding: String,
+    /// The names of the detail fields of the entries.
+    pub detail_fields: Vec<String>,
+    /// The number of entries of the prefix dictionary.
+    pub entry_count: usize,
+    /// The number of entries of the unknown dictionary.
+    pub unknown_entry_count: usize,
+    /// The number of left context ids and right context ids of the connection cost matrix.
+    pub connection_size: (usize, usize),
+    /// The time the dictionary was built at, in seconds since the Unix epoch.
+    pub build_timestamp: u64,
+    /// The version of Lindera that built the dictionary.
+    pub lindera_version: String,
+    /// The options of the builder.
+    pub build_options: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    pub fn load(metadata_data: &[u8]) -> LinderaResult<Metadata> {
+        serde_json::from_slice(metadata_data)
+            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+    }
+}
@@ -1,87 +1,297 @@
-use std::ops::Deref;
+use std::ops::{Deref, RangeInclusive};
+use std::str;
 
+use byteorder::{ByteOrder, LittleEndian};
 use serde::{Deserialize, Serialize};
+use yada::unit::{Unit, UNIT_SIZE};
 use yada::DoubleArray;
 
+use crate::dictionary::UNK;
+use crate::util::Data;
 use crate::viterbi::WordEntry;
 
 #[derive(Serialize, Deserialize)]
 #[serde(remote = "DoubleArray")]
-struct DoubleArrayDef<T>(pub T)
+pub(crate) struct DoubleArrayDef<T>(pub T)
 where
     T: Deref<Target = [u8]>;
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct PrefixDictionary<Data = Vec<u8>> {
+pub struct PrefixDictionary {
     #[serde(with = "DoubleArrayDef")]
-    pub da: DoubleArray<Vec<u8>>,
+    pub da: DoubleArray<Data>,
     pub vals_data: Data,
-    pub words_idx_data: Vec<u8>,
-    pub words_data: Vec<u8>,
+    pub words_idx_data: Data,
+    pub words_data: Data,
     pub is_system: bool,
 }
 
-impl PrefixDictionary<&[u8]> {
+impl PrefixDictionary {
     pub fn load(
         da_data: &[u8],
         vals_data: &[u8],
         words_idx_data: &[u8],
         words_data: &[u8],
     ) -> PrefixDictionary {
-        let da = DoubleArray::new(da_data.to_vec());
+        Self::from_data(
+            da_data.to_vec().into(),
+            vals_data.to_vec().into(),
+            words_idx_data.to_vec().into(),
+            words_data.to_vec().into(),
+        )
+    }
+
+    /// Creates a system dictionary that borrows data embedded in the binary, without copying or
+    /// parsing it.
+    pub fn load_static(
+        da_data: &'static [u8],
+        vals_data: &'static [u8],
+        words_idx_data: &'static [u8],
+        words_data: &'static [u8],
+    ) -> PrefixDictionary {
+        Self::from_data(
+            da_data.into(),
+            vals_data.into(),
+            words_idx_data.into(),
+            words_data.into(),
+        )
+    }
 
+    /// Creates a system dictionary that reads the given data in place, without copying it.
+    pub fn from_data(
+        da_data: Data,
+        vals_data: Data,
+        words_idx_data: Data,
+        words_data: Data,
+    ) -> PrefixDictionary {
         PrefixDictionary {
-            da,
-            vals_data: vals_data.to_vec(),
-            words_idx_data: words_idx_data.to_vec(),
-            words_data: words_data.to_vec(),
+            da: DoubleArray::new(da_data),
+            vals_data,
+            words_idx_data,
+            words_data,
             is_system: true,
         }
     }
-}
 
-impl<D: Deref<Target = [u8]>> PrefixDictionary<D> {
     pub fn prefix<'a>(&'a self, s: &'a str) -> impl Iterator<Item = (usize, WordEntry)> + 'a {
         self.da
             .common_prefix_search(s)
             .flat_map(move |(offset_len, prefix_len)| {
-                let len = offset_len & ((1u32 << 5) - 1u32);
-                let offset = offset_len >> 5u32;
-                let offset_bytes = (offset as usize) * WordEntry::SERIALIZED_LEN;
-                let data: &[u8] = &self.vals_data[offset_bytes..];
-                (0..len as usize).map(move |i| {
-                    (
-                        prefix_len,
-                        WordEntry::deserialize(
-                            &data[WordEntry::SERIALIZED_LEN * i..],
-                            self.is_system,
-                        ),
-                    )
-                })
+                self.word_entries(offset_len)
+                    .map(move |word_entry| (prefix_len, word_entry))
             })
     }
 
     /// Find `WordEntry`s with surface
     pub fn find_surface(&self, surface: &str) -> Vec<WordEntry> {
         match self.da.exact_match_search(surface) {
-            Some(offset_len) => {
-                let offset = offset_len >> 5u32;
-                let offset_bytes = (offset as usize) * WordEntry::SERIALIZED_LEN;
-                let data: &[u8] = &self.vals_data[offset_bytes..];
-                let len = offset_len & ((1u32 << 5) - 1u32);
-                (0..len as usize)
-                    .map(|i| {
-                        WordEntry::deserialize(
-                            &data[WordEntry::SERIALIZED_LEN * i..],
-                            self.is_system,
-                        )
-                    })
-                    .collect::<Vec<WordEntry>>()
-            }
+            Some(offset_len) => self.word_entries(offset_len).collect(),
             None => vec![],
         }
     }
+
+    /// Finds the entries whose surface starts with `prefix`, in the byte order of the surfaces.
+    pub fn predict(&self, prefix: &str) -> Vec<(String, WordEntry)> {
+        let mut predictions = Vec::new();
+        self.for_each_prediction(prefix, |surface, word_entry| {
+            predictions.push((surface.to_string(), word_entry));
+        });
+        predictions
+    }
+
+    /// Calls `f` with the surface and the entry of each word whose surface starts with `prefix`,
+    /// in the byte order of the surfaces, without collecting them.
+    pub fn for_each_prediction(&self, prefix: &str, mut f: impl FnMut(&str, WordEntry)) {
+        predictive_search(&self.da, prefix, |surface, offset_len| {
+            for word_entry in self.word_entries(offset_len) {
+                f(surface, word_entry);
+            }
+        });
+    }
+
+    /// Returns the details of a word of a system dictionary.
+    pub fn word_details(&self, word_id: usize) -> Vec<&str> {
+        if 4 * word_id >= self.words_idx_data.len() {
+            return vec![];
+        }
+
+        let idx: usize =
+            match LittleEndian::read_u32(&self.words_idx_data[4 * word_id..][..4]).try_into() {
+                Ok(value) => value,
+                Err(_) => return UNK.to_vec(), // return empty vector if conversion fails
+            };
+        let data = &self.words_data[idx..];
+        let joined_details_len: usize = match LittleEndian::read_u32(data).try_into() {
+            Ok(value) => value,
+            Err(_) => return UNK.to_vec(), // return empty vector if conversion fails
+        };
+        let joined_details_bytes: &[u8] = &self.words_data[idx + 4..idx + 4 + joined_details_len];
+
+        let mut details = Vec::new();
+        for bytes in joined_details_bytes.split(|&b| b == 0) {
+            let detail = match str::from_utf8(bytes) {
+                Ok(s) => s,
+                Err(_) => return UNK.to_vec(), // return empty vector if conversion fails
+            };
+            details.push(detail);
+        }
+        details
+    }
+
+    fn word_entries(&self, offset_len: u32) -> impl Iterator<Item = WordEntry> + '_ {
+        let len = offset_len & ((1u32 << 5) - 1u32);
+        let offset = offset_len >> 5u32;
+        let offset_bytes = (offset as usize) * WordEntry::SERIALIZED_LEN;
+        let data: &[u8] = &self.vals_data[offset_bytes..];
+        (0..len as usize).map(move |i| {
+            WordEntry::deserialize(&data[WordEntry::SERIALIZED_LEN * i..], self.is_system)
+        })
+    }
+}
+
+fn unit<T: Deref<Target = [u8]>>(da: &DoubleArray<T>, unit_id: usize) -> Option<Unit> {
+    let bytes = da.0.get(unit_id * UNIT_SIZE..(unit_id + 1) * UNIT_SIZE)?;
+    Some(Unit::from_u32(LittleEndian::read_u32(bytes)))
+}
+
+/// Calls `f` with each key of a double array that starts with `prefix` and its value, in the
+/// byte order of the keys.
+pub(crate) fn predictive_search<T: Deref<Target = [u8]>>(
+    da: &DoubleArray<T>,
+    prefix: &str,
+    mut f: impl FnMut(&str, u32),
+) {
+    // Find the node reached by the prefix.
+    let mut unit_id = 0;
+    let Some(mut node) = unit(da, unit_id) else {
+        return;
+    };
+    for &c in prefix.as_bytes() {
+        unit_id = (node.offset() ^ unit_id as u32 ^ c as u32) as usize;
+        match unit(da, unit_id) {
+            Some(child) if child.label() == c as u32 => node = child,
+            _ => return,
+        }
+    }
+
+    visit_keys(da, unit_id, &mut prefix.as_bytes().to_vec(), 0, &mut f);
+}
+
+/// Visits the keys of the node and of all its descendants, depth first.
+///
+/// The keys are UTF-8 strings, so only the bytes that can follow the key so far are probed as
+/// labels: continuation bytes while `pending` bytes of a character are missing, and ASCII or
+/// leading bytes otherwise.
+fn visit_keys<T: Deref<Target = [u8]>>(
+    da: &DoubleArray<T>,
+    unit_id: usize,
+    key: &mut Vec<u8>,
+    pending: usize,
+    f: &mut impl FnMut(&str, u32),
+) {
+    let Some(node) = unit(da, unit_id) else {
+        return;
+    };
+    if pending == 0 && node.has_leaf() {
+        let leaf = unit(da, (node.offset() ^ unit_id as u32) as usize);
+        if let (Some(leaf), Ok(key)) = (leaf, str::from_utf8(key)) {
+            f(key, leaf.value());
+        }
+    }
+
+    let labels: &[RangeInclusive<u8>] = if pending > 0 {
+        &[0x80..=0xBF]
+    } else {
+        &[0x01..=0x7F, 0xC2..=0xF4]
+    };
+    for c in labels.iter().cloned().flatten() {
+        let child_id = (node.offset() ^ unit_id as u32 ^ c as u32) as usize;
+        if matches!(unit(da, child_id), Some(child) if child.label() == c as u32) {
+            let pending = match c {
+                0x80..=0xBF => pending - 1,
+                0xC2..=0xDF => 1,
+                0xE0..=0xEF => 2,
+                0xF0..=0xF4 => 3,
+                _ => 0,
+            };
+            key.push(c);
+            visit_keys(da, child_id, key, pending, f);
+            key.pop();
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    #[test]
+    fn test_load_static() {
+        use crate::decompress::Algorithm;
+        use crate::dictionary::prefix_dictionary::PrefixDictionary;
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::test_utils::{mini_ipadic_dir, temp_dir};
+        use crate::util::Data;
+
+        // The built files are read as they are, so they must not be compressed.
+        let output_dir = temp_dir("load-static");
+        let mut builder = IpadicBuilder::new();
+        builder.set_compress_algorithm(Algorithm::Raw);
+        builder
+            .build_dictionary(&mini_ipadic_dir(), &output_dir)
+            .unwrap();
+        let read = |name: &str| -> &'static [u8] {
+            Box::leak(
+                std::fs::read(output_dir.join(name))
+                    .unwrap()
+                    .into_boxed_slice(),
+            )
+        };
+        let (da_data, vals_data, words_idx_data, words_data) = (
+            read("dict.da"),
+            read("dict.vals"),
+            read("dict.wordsidx"),
+            read("dict.words"),
+        );
+
+        let copied = PrefixDictionary::load(da_data, vals_data, words_idx_data, words_data);
+        let borrowed =
+            PrefixDictionary::load_static(da_data, vals_data, words_idx_data, words_data);
+        assert!(
+            matches!(borrowed.words_data, Data::Static(data) if data.as_ptr() == words_data.as_ptr())
+        );
+        assert!(!borrowed.find_surface("東京").is_empty());
+        assert_eq!(borrowed.find_surface("東京"), copied.find_surface("東京"));
+        assert_eq!(
+            borrowed.prefix("東京都").collect::<Vec<_>>(),
+            copied.prefix("東京都").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_predict() {
+        use crate::test_utils::mini_ipadic;
+
+        let dictionary = mini_ipadic();
+        let surfaces = |prefix: &str| {
+            dictionary
+                .prefix_dictionary
+                .predict(prefix)
+                .into_iter()
+                .map(|(surface, _)| surface)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(surfaces("東"), vec!["東", "東京", "東京都"]);
+        assert_eq!(surfaces("東京"), vec!["東京", "東京都"]);
+        assert_eq!(surfaces("国際"), vec!["国際", "国際空港"]);
+        assert!(surfaces("大").is_empty());
+        assert!(surfaces("東京都庁").is_empty());
+        // Every entry starts with the empty prefix.
+        assert_eq!(surfaces("").len(), 19);
+
+        let (_, word_entry) = &dictionary.prefix_dictionary.predict("東京都")[0];
+        assert_eq!(
+            word_entry,
+            &dictionary.prefix_dictionary.find_surface("東京都")[0]
+        );
+    }
+}
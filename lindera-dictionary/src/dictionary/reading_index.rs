@@ -0,0 +1,84 @@
+use std::str;
+
+use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
+use yada::DoubleArray;
+
+use crate::dictionary::prefix_dictionary::{predictive_search, DoubleArrayDef};
+use crate::util::Data;
+
+/// An index from the readings of the words of a dictionary to their surfaces and word ids.
+///
+/// The double array maps each reading to its number. `offsets_data` holds, for each reading, the
+/// offset of its words in `entries_data`, followed by the end of the last one. Each word is
+/// stored as its word id, the length of its surface and the surface, with little-endian `u32`s.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReadingIndex {
+    #[serde(with = "DoubleArrayDef")]
+    pub da: DoubleArray<Data>,
+    pub offsets_data: Data,
+    pub entries_data: Data,
+}
+
+impl ReadingIndex {
+    pub fn load(da_data: &[u8], offsets_data: &[u8], entries_data: &[u8]) -> ReadingIndex {
+        Self::from_data(
+            da_data.to_vec().into(),
+            offsets_data.to_vec().into(),
+            entries_data.to_vec().into(),
+        )
+    }
+
+    /// Creates an index that reads the given data in place, without copying it.
+    pub fn from_data(da_data: Data, offsets_data: Data, entries_data: Data) -> ReadingIndex {
+        ReadingIndex {
+            da: DoubleArray::new(da_data),
+            offsets_data,
+            entries_data,
+        }
+    }
+
+    /// Returns the surfaces and word ids of the words whose reading is `reading`.
+    pub fn find_reading(&self, reading: &str) -> Vec<(&str, u32)> {
+        match self.da.exact_match_search(reading) {
+            Some(reading_id) => self.entries(reading_id),
+            None => vec![],
+        }
+    }
+
+    /// Returns the surfaces and word ids of the words whose reading starts with `prefix`, in the
+    /// byte order of the readings.
+    pub fn predict(&self, prefix: &str) -> Vec<(&str, u32)> {
+        let mut entries = Vec::new();
+        predictive_search(&self.da, prefix, |_, reading_id| {
+            entries.extend(self.entries(reading_id));
+        });
+        entries
+    }
+
+    fn entries(&self, reading_id: u32) -> Vec<(&str, u32)> {
+        let offset_pos = reading_id as usize * 4;
+        let Some(offsets) = self.offsets_data.get(offset_pos..offset_pos + 8) else {
+            return vec![];
+        };
+        let start = LittleEndian::read_u32(&offsets[0..4]) as usize;
+        let end = LittleEndian::read_u32(&offsets[4..8]) as usize;
+        let Some(mut data) = self.entries_data.get(start..end) else {
+            return vec![];
+        };
+
+        let mut entries = Vec::new();
+        while data.len() >= 8 {
+            let word_id = LittleEndian::read_u32(&data[0..4]);
+            let surface_len = LittleEndian::read_u32(&data[4..8]) as usize;
+            let Some(surface) = data.get(8..8 + surface_len) else {
+                break;
+            };
+            if let Ok(surface) = str::from_utf8(surface) {
+                entries.push((surface, word_id));
+            }
+            data = &data[8 + surface_len..];
+        }
+        entries
+    }
+}
@@ -1,40 +1,247 @@
-use std::str::FromStr;
+use std::str::{self, FromStr};
 
+use byteorder::{ByteOrder, LittleEndian};
 use log::warn;
 use serde::{Deserialize, Serialize};
 
 use crate::dictionary::character_definition::CategoryId;
 use crate::error::LinderaErrorKind;
+use crate::util::{check_offsets, check_sections, offset_range, section, write_sections, Data};
 use crate::viterbi::{WordEntry, WordId};
 use crate::LinderaResult;
 
+/// The magic number of unknown dictionaries, which older versions wrote with bincode instead.
+const MAGIC: &[u8; 4] = b"LUNK";
+
+// The sections of an unknown dictionary, whose numbers are little-endian.
+/// The byte offsets of the entries of each category in `ENTRY_IDS`, and the end of the last one,
+/// as `u32`s.
+const CATEGORY_OFFSETS: usize = 0;
+/// The ids of the entries of the categories, as `u32`s.
+const ENTRY_IDS: usize = 1;
+/// The left and right context ids, as `u16`s, and the cost, as an `i16`, of each entry.
+const ENTRIES: usize = 2;
+/// The byte offsets of the details of each entry in `DETAILS`, and the end of the last one, as
+/// `u32`s. It is empty if the entries have no details.
+const DETAIL_OFFSETS: usize = 3;
+/// The details of the entries, separated by NUL bytes.
+const DETAILS: usize = 4;
+const SECTION_COUNT: usize = 5;
+
+const ENTRY_SIZE: usize = 6;
+
+/// The entries of `unk.def`, in a layout that is read in place, so that a dictionary embedded in
+/// the binary or mapped from a file is used without being copied or parsed.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct UnknownDictionary {
-    pub category_references: Vec<Vec<u32>>,
-    pub costs: Vec<WordEntry>,
+    pub data: Data,
+}
+
+// The layout of unknown dictionaries built by earlier versions.
+#[derive(Deserialize)]
+struct UnknownDictionaryV1 {
+    category_references: Vec<Vec<u32>>,
+    costs: Vec<WordEntry>,
+    details: Vec<Vec<String>>,
+}
+
+// The layout of unknown dictionaries built before the feature columns were kept.
+#[derive(Deserialize)]
+struct UnknownDictionaryWithoutDetails {
+    category_references: Vec<Vec<u32>>,
+    costs: Vec<WordEntry>,
 }
 
 impl UnknownDictionary {
+    /// Lays out the ids of the entries of each category, and the context ids, the cost and the
+    /// feature columns of each entry. `details` may be empty if the entries have none.
+    pub fn new(
+        category_references: &[Vec<u32>],
+        costs: &[WordEntry],
+        details: &[Vec<String>],
+    ) -> UnknownDictionary {
+        let mut sections = vec![Vec::new(); SECTION_COUNT];
+        sections[CATEGORY_OFFSETS].extend_from_slice(&0u32.to_le_bytes());
+        for word_ids in category_references.iter() {
+            for word_id in word_ids.iter() {
+                sections[ENTRY_IDS].extend_from_slice(&word_id.to_le_bytes());
+            }
+            let offset = sections[ENTRY_IDS].len() as u32;
+            sections[CATEGORY_OFFSETS].extend_from_slice(&offset.to_le_bytes());
+        }
+        for word_entry in costs.iter() {
+            sections[ENTRIES].extend_from_slice(&word_entry.left_id.to_le_bytes());
+            sections[ENTRIES].extend_from_slice(&word_entry.right_id.to_le_bytes());
+            sections[ENTRIES].extend_from_slice(&word_entry.word_cost.to_le_bytes());
+        }
+        if !details.is_empty() {
+            sections[DETAIL_OFFSETS].extend_from_slice(&0u32.to_le_bytes());
+        }
+        for entry_details in details.iter() {
+            sections[DETAILS].extend_from_slice(entry_details.join("\0").as_bytes());
+            let offset = sections[DETAILS].len() as u32;
+            sections[DETAIL_OFFSETS].extend_from_slice(&offset.to_le_bytes());
+        }
+        UnknownDictionary {
+            data: write_sections(MAGIC, &sections).into(),
+        }
+    }
+
     pub fn load(unknown_data: &[u8]) -> LinderaResult<UnknownDictionary> {
-        bincode::deserialize(unknown_data)
-            .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))
+        Self::from_data(unknown_data.to_vec().into())
+    }
+
+    /// Creates an unknown dictionary that borrows data embedded in the binary, without copying
+    /// or parsing it.
+    pub fn load_static(unknown_data: &'static [u8]) -> LinderaResult<UnknownDictionary> {
+        if !unknown_data.starts_with(MAGIC) {
+            return Self::load(unknown_data);
+        }
+        Ok(UnknownDictionary {
+            data: unknown_data.into(),
+        })
+    }
+
+    /// Creates an unknown dictionary that reads the given data in place, after checking that its
+    /// lookups stay in bounds. A dictionary built by an earlier version is converted.
+    pub fn from_data(unknown_data: Data) -> LinderaResult<UnknownDictionary> {
+        let unknown_data = if unknown_data.starts_with(MAGIC) {
+            unknown_data
+        } else {
+            bincode::deserialize::<UnknownDictionaryV1>(&unknown_data)
+                .map(|dict| Self::new(&dict.category_references, &dict.costs, &dict.details))
+                .or_else(|err| {
+                    // Fall back to the older layout, whose unknown words have no details.
+                    bincode::deserialize::<UnknownDictionaryWithoutDetails>(&unknown_data)
+                        .map(|dict| Self::new(&dict.category_references, &dict.costs, &[]))
+                        .map_err(|_| err)
+                })
+                .map_err(|err| LinderaErrorKind::Deserialize.with_error(anyhow::anyhow!(err)))?
+                .data
+        };
+        check(&unknown_data).map_err(|message| {
+            LinderaErrorKind::Deserialize
+                .with_error(anyhow::anyhow!("invalid unknown dictionary: {}", message))
+        })?;
+        Ok(UnknownDictionary { data: unknown_data })
+    }
+
+    fn section(&self, index: usize) -> &[u8] {
+        section(&self.data, index)
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.section(ENTRIES).len() / ENTRY_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of categories, which may be more than in the character definition.
+    pub fn category_count(&self) -> usize {
+        self.section(CATEGORY_OFFSETS).len() / 4 - 1
     }
 
     pub fn word_entry(&self, word_id: u32) -> WordEntry {
-        self.costs[word_id as usize]
+        let data = &self.section(ENTRIES)[word_id as usize * ENTRY_SIZE..][..ENTRY_SIZE];
+        WordEntry {
+            word_id: WordId::unknown(word_id),
+            left_id: LittleEndian::read_u16(&data[0..2]),
+            right_id: LittleEndian::read_u16(&data[2..4]),
+            word_cost: LittleEndian::read_i16(&data[4..6]),
+        }
+    }
+
+    pub fn lookup_word_ids(&self, category_id: CategoryId) -> impl Iterator<Item = u32> + '_ {
+        self.section(ENTRY_IDS)[offset_range(self.section(CATEGORY_OFFSETS), category_id.0)]
+            .chunks_exact(4)
+            .map(LittleEndian::read_u32)
+    }
+
+    /// Returns the feature columns of an entry, or an empty vector if they are not available.
+    pub fn word_details(&self, word_id: u32) -> Vec<&str> {
+        let detail_offsets = self.section(DETAIL_OFFSETS);
+        if 4 * (word_id as usize + 1) >= detail_offsets.len() {
+            return Vec::new();
+        }
+        let details = &self.section(DETAILS)[offset_range(detail_offsets, word_id as usize)];
+        if details.is_empty() {
+            return Vec::new();
+        }
+        details
+            .split(|&b| b == 0)
+            .map(|detail| str::from_utf8(detail).unwrap_or_default())
+            .collect()
     }
 
-    pub fn lookup_word_ids(&self, category_id: CategoryId) -> &[u32] {
-        &self.category_references[category_id.0][..]
+    /// Returns the category of an entry.
+    pub fn category(&self, word_id: u32) -> Option<CategoryId> {
+        (0..self.category_count())
+            .map(CategoryId)
+            .find(|&category_id| self.lookup_word_ids(category_id).any(|id| id == word_id))
     }
 }
 
+/// Checks that the lookups in an unknown dictionary stay in bounds.
+fn check(data: &[u8]) -> Result<(), String> {
+    check_sections(data, MAGIC, SECTION_COUNT)?;
+
+    let entries = section(data, ENTRIES);
+    if !entries.len().is_multiple_of(ENTRY_SIZE) {
+        return Err("truncated entries".to_string());
+    }
+    let entry_count = entries.len() / ENTRY_SIZE;
+
+    let category_offsets = section(data, CATEGORY_OFFSETS);
+    let entry_ids = section(data, ENTRY_IDS);
+    if category_offsets.len() < 4 || !category_offsets.len().is_multiple_of(4) {
+        return Err("truncated category offsets".to_string());
+    }
+    check_offsets(
+        category_offsets,
+        category_offsets.len() / 4 - 1,
+        entry_ids.len(),
+    )?;
+    if category_offsets
+        .chunks_exact(4)
+        .any(|offset| !LittleEndian::read_u32(offset).is_multiple_of(4))
+        || !entry_ids.len().is_multiple_of(4)
+    {
+        return Err("misaligned entry ids".to_string());
+    }
+    if let Some(word_id) = entry_ids
+        .chunks_exact(4)
+        .map(LittleEndian::read_u32)
+        .find(|&word_id| word_id as usize >= entry_count)
+    {
+        return Err(format!(
+            "entry {} beyond the {} entries",
+            word_id, entry_count
+        ));
+    }
+
+    let detail_offsets = section(data, DETAIL_OFFSETS);
+    if !detail_offsets.is_empty() {
+        let details = section(data, DETAILS);
+        check_offsets(detail_offsets, entry_count, details.len())?;
+        for word_id in 0..entry_count {
+            str::from_utf8(&details[offset_range(detail_offsets, word_id)])
+                .map_err(|err| err.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct UnknownDictionaryEntry {
     pub surface: String,
     pub left_id: u32,
     pub right_id: u32,
     pub word_cost: i32,
+    pub details: Vec<String>,
 }
 
 fn parse_dictionary_entry(
@@ -61,6 +268,7 @@ fn parse_dictionary_entry(
         left_id,
         right_id,
         word_cost,
+        details: fields[4..].iter().map(|field| field.to_string()).collect(),
     })
 }
 
@@ -81,30 +289,28 @@ fn get_entry_id_matching_surface(
         .collect()
 }
 
-fn make_category_references(
-    categories: &[String],
+fn make_category_references<S: AsRef<str>>(
+    categories: &[S],
     entries: &[UnknownDictionaryEntry],
 ) -> Vec<Vec<u32>> {
     categories
         .iter()
-        .map(|category| get_entry_id_matching_surface(entries, category))
+        .map(|category| get_entry_id_matching_surface(entries, category.as_ref()))
         .collect()
 }
 
 fn make_costs_array(entries: &[UnknownDictionaryEntry]) -> Vec<WordEntry> {
     entries
         .iter()
-        .map(|e| {
+        .enumerate()
+        .map(|(entry_id, e)| {
             // Do not perform strict checks on left context id and right context id in unk.def.
             // Just output a warning.
             if e.left_id != e.right_id {
                 warn!("left id and right id are not same: {:?}", e);
             }
             WordEntry {
-                word_id: WordId {
-                    id: u32::MAX,
-                    is_system: true,
-                },
+                word_id: WordId::unknown(entry_id as u32),
                 left_id: e.left_id as u16,
                 right_id: e.right_id as u16,
                 word_cost: e.word_cost as i16,
@@ -113,8 +319,8 @@ fn make_costs_array(entries: &[UnknownDictionaryEntry]) -> Vec<WordEntry> {
         .collect()
 }
 
-pub fn parse_unk(
-    categories: &[String],
+pub fn parse_unk<S: AsRef<str>>(
+    categories: &[S],
     file_content: &str,
     expected_fields_len: usize,
 ) -> LinderaResult<UnknownDictionary> {
@@ -127,11 +333,84 @@ pub fn parse_unk(
 
     let category_references = make_category_references(categories, &unknown_dict_entries[..]);
     let costs = make_costs_array(&unknown_dict_entries[..]);
-    Ok(UnknownDictionary {
-        category_references,
-        costs,
-    })
+    let details = unknown_dict_entries
+        .into_iter()
+        .map(|entry| entry.details)
+        .collect::<Vec<_>>();
+    Ok(UnknownDictionary::new(
+        &category_references,
+        &costs,
+        &details,
+    ))
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use serde::Serialize;
+
+    use crate::dictionary::character_definition::CategoryId;
+    use crate::dictionary::unknown_dictionary::{parse_unk, UnknownDictionary};
+    use crate::viterbi::WordEntry;
+
+    const UNK_DEF: &str = "DEFAULT,5,5,4769,記号,一般,*,*,*,*,*
+KANJI,1,1,8000,名詞,一般,*,*,*,*,*
+KANJI,4,4,9000,名詞,固有名詞,地域,一般,*,*,*";
+
+    #[test]
+    fn test_parse_unk() {
+        let categories = vec!["DEFAULT".to_string(), "KANJI".to_string()];
+        let dict = parse_unk(&categories, UNK_DEF, 11).unwrap();
+
+        assert_eq!(
+            dict.lookup_word_ids(CategoryId(1)).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            dict.word_details(2),
+            vec!["名詞", "固有名詞", "地域", "一般", "*", "*", "*"]
+        );
+        assert!(dict.word_details(3).is_empty());
+        assert_eq!(dict.category(2), Some(CategoryId(1)));
+
+        let word_entry = dict.word_entry(2);
+        assert!(word_entry.word_id.is_unknown());
+        assert_eq!(word_entry.word_id.unknown_entry_id(), Some(2));
+        assert_eq!(word_entry.word_cost, 9000);
+
+        // The data is read in place as it is written.
+        let data: &'static [u8] = dict.data.to_vec().leak();
+        let loaded = UnknownDictionary::load_static(data).unwrap();
+        assert_eq!(&loaded.data[..], &dict.data[..]);
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded.word_details(0)[..2], ["記号", "一般"]);
+
+        // Offsets beyond the data are found when it is loaded.
+        let mut corrupted = dict.data.to_vec();
+        let last = corrupted.len() - 4;
+        corrupted.truncate(last);
+        assert!(UnknownDictionary::load(&corrupted).is_err());
+    }
+
+    #[test]
+    fn test_load_without_details() {
+        #[derive(Serialize)]
+        struct UnknownDictionaryWithoutDetails {
+            category_references: Vec<Vec<u32>>,
+            costs: Vec<WordEntry>,
+        }
+
+        let data = bincode::serialize(&UnknownDictionaryWithoutDetails {
+            category_references: vec![vec![0]],
+            costs: vec![WordEntry::default()],
+        })
+        .unwrap();
+        let dict = UnknownDictionary::load(&data).unwrap();
+        assert_eq!(
+            dict.lookup_word_ids(CategoryId(0)).collect::<Vec<_>>(),
+            vec![0]
+        );
+        assert!(dict.word_details(0).is_empty());
+
+        assert!(UnknownDictionary::load(&[1, 2, 3]).is_err());
+    }
+}
@@ -1,45 +1,293 @@
 pub mod cc_cedict;
 pub mod character_definition;
 pub mod connection_cost_matrix;
+pub mod context_id;
 pub mod ipadic;
 pub mod ipadic_neologd;
 pub mod ko_dic;
+pub mod metadata;
 pub mod prefix_dictionary;
+pub mod reading_index;
 pub mod unidic;
 pub mod unknown_dictionary;
 pub mod user_dictionary;
 
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 pub use character_definition::CharacterDefinitionBuilderOptions;
 pub use connection_cost_matrix::ConnectionCostMatrixBuilderOptions;
+pub use context_id::ContextIdsBuilderOptions;
+pub use metadata::MetadataBuilderOptions;
 pub use prefix_dictionary::PrefixDictionaryBuilderOptions;
+pub use reading_index::ReadingIndexBuilderOptions;
 pub use unknown_dictionary::UnknownDictionaryBuilderOptions;
-pub use user_dictionary::{build_user_dictionary, UserDictionaryBuilderOptions};
+pub use user_dictionary::{
+    build_user_dictionary, UserDictionaryBuilder, UserDictionaryBuilderOptions,
+    UserDictionaryEntries,
+};
 
+use crate::decompress::Algorithm;
 use crate::dictionary::character_definition::CharacterDefinition;
+use crate::dictionary::context_id::ContextIds;
+use crate::dictionary::metadata::Metadata;
 use crate::dictionary::UserDictionary;
+use crate::dictionary_file::DictionaryFile;
+use crate::error::LinderaErrorKind;
 use crate::LinderaResult;
 
+/// The compression of built dictionary files, unless a builder is told otherwise.
+///
+/// With the `compress` feature, it is the best codec enabled: Zstandard, then LZ4, then Deflate.
+/// Loaders detect whether a file is compressed, so this only decides what gets written.
+pub const DEFAULT_COMPRESS_ALGORITHM: Algorithm = if !cfg!(feature = "compress") {
+    Algorithm::Raw
+} else if cfg!(feature = "zstd") {
+    Algorithm::Zstd
+} else if cfg!(feature = "lz4") {
+    Algorithm::Lz4
+} else {
+    Algorithm::Deflate
+};
+
+/// The options a dictionary is built with.
+///
+/// Each kind of dictionary fixes the format of its source files, while the compression of the
+/// built files and the reading index can be changed before building. The options are recorded in
+/// the metadata of the built dictionary.
+#[derive(Debug, Clone)]
+pub struct DictionaryBuilderOptions {
+    /// The name of the dictionary.
+    pub name: &'static str,
+    /// The encoding of the source files.
+    pub encoding: &'static str,
+    /// The names of the details of the words.
+    pub detail_fields: &'static [&'static str],
+    /// The index of the reading in the details of the words.
+    pub reading_field: usize,
+    /// The number of fields of the entries of `unk.def`.
+    pub unk_fields_num: usize,
+    /// Whether the rows of the lexicon may have different numbers of fields.
+    pub flexible_csv: bool,
+    /// Whether the words are sorted by their normalized surfaces.
+    pub normalize_details: bool,
+    /// Whether the words with an invalid cost or context id are skipped instead of failing.
+    pub skip_invalid_cost_or_id: bool,
+    /// The algorithm the built dictionary files are compressed with.
+    pub compress_algorithm: Algorithm,
+    /// Whether a reading index is built.
+    pub reading_index: bool,
+}
+
+impl DictionaryBuilderOptions {
+    /// Creates the options of a dictionary whose source files are UTF-8 encoded CSV files with a
+    /// fixed number of fields.
+    pub fn new(
+        name: &'static str,
+        detail_fields: &'static [&'static str],
+        reading_field: usize,
+        unk_fields_num: usize,
+    ) -> Self {
+        DictionaryBuilderOptions {
+            name,
+            encoding: "UTF-8",
+            detail_fields,
+            reading_field,
+            unk_fields_num,
+            flexible_csv: false,
+            normalize_details: false,
+            skip_invalid_cost_or_id: false,
+            compress_algorithm: DEFAULT_COMPRESS_ALGORITHM,
+            reading_index: false,
+        }
+    }
+
+    /// Returns the options recorded in the metadata, besides the compression which is always
+    /// recorded.
+    fn build_options(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("flexible_csv", self.flexible_csv.to_string()),
+            ("normalize_details", self.normalize_details.to_string()),
+            (
+                "skip_invalid_cost_or_id",
+                self.skip_invalid_cost_or_id.to_string(),
+            ),
+            ("reading_index", self.reading_index.to_string()),
+        ]
+    }
+}
+
 pub trait DictionaryBuilder {
-    fn build_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()>;
-    fn build_user_dictionary(&self, input_path: &Path, output_path: &Path) -> LinderaResult<()>;
+    /// Returns the options the dictionary is built with.
+    fn options(&self) -> &DictionaryBuilderOptions;
+    /// Returns the options the dictionary is built with, to change them before building.
+    fn options_mut(&mut self) -> &mut DictionaryBuilderOptions;
+
+    fn build_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
+        fs::create_dir_all(output_dir)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        let chardef = self.build_character_definition(input_dir, output_dir)?;
+        self.build_unknown_dictionary(input_dir, &chardef, output_dir)?;
+        self.build_prefix_dictionary(input_dir, output_dir)?;
+        self.build_context_ids(input_dir, output_dir)?;
+        self.build_connection_cost_matrix(input_dir, output_dir)?;
+        if self.options().reading_index {
+            self.build_reading_index(output_dir)?;
+        }
+        self.build_metadata(input_dir, output_dir)?;
+
+        Ok(())
+    }
+
+    /// Builds a user dictionary from a CSV file into a `.bin` file. See `build_user_dict`.
+    fn build_user_dictionary(
+        &self,
+        input_file: &Path,
+        output_file: &Path,
+        context_ids: Option<ContextIds>,
+    ) -> LinderaResult<()> {
+        let user_dict = self.build_user_dict(input_file, context_ids)?;
+        build_user_dictionary(user_dict, output_file)
+    }
+
     fn build_character_definition(
         &self,
         input_dir: &Path,
         output_dir: &Path,
-    ) -> LinderaResult<CharacterDefinition>;
+    ) -> LinderaResult<CharacterDefinition> {
+        let options = self.options();
+        CharacterDefinitionBuilderOptions::default()
+            .encoding(options.encoding)
+            .compress_algorithm(options.compress_algorithm)
+            .builder()
+            .unwrap()
+            .build(input_dir, output_dir)
+    }
+
     fn build_unknown_dictionary(
         &self,
         input_dir: &Path,
         chardef: &CharacterDefinition,
         output_dir: &Path,
-    ) -> LinderaResult<()>;
-    fn build_prefix_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()>;
+    ) -> LinderaResult<()> {
+        let options = self.options();
+        UnknownDictionaryBuilderOptions::default()
+            .encoding(options.encoding)
+            .compress_algorithm(options.compress_algorithm)
+            .unk_fields_num(options.unk_fields_num)
+            .builder()
+            .unwrap()
+            .build(input_dir, chardef, output_dir)
+    }
+
+    fn build_prefix_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
+        let options = self.options();
+        PrefixDictionaryBuilderOptions::default()
+            .flexible_csv(options.flexible_csv)
+            .encoding(options.encoding)
+            .compress_algorithm(options.compress_algorithm)
+            .normalize_details(options.normalize_details)
+            .skip_invalid_cost_or_id(options.skip_invalid_cost_or_id)
+            .builder()
+            .unwrap()
+            .build(input_dir, output_dir)
+    }
+
     fn build_connection_cost_matrix(
         &self,
         input_dir: &Path,
         output_dir: &Path,
-    ) -> LinderaResult<()>;
-    fn build_user_dict(&self, input_file: &Path) -> LinderaResult<UserDictionary>;
+    ) -> LinderaResult<()> {
+        let options = self.options();
+        ConnectionCostMatrixBuilderOptions::default()
+            .encoding(options.encoding)
+            .compress_algorithm(options.compress_algorithm)
+            .builder()
+            .unwrap()
+            .build(input_dir, output_dir)
+    }
+
+    /// Builds the context ids of the parts of speech from `left-id.def` and `right-id.def`, with
+    /// the costs of the words of the prefix dictionary built in `output_dir`.
+    fn build_context_ids(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
+        let options = self.options();
+        ContextIdsBuilderOptions::default()
+            .encoding(options.encoding)
+            .compress_algorithm(options.compress_algorithm)
+            .builder()
+            .unwrap()
+            .build(input_dir, output_dir)
+    }
+
+    /// Returns the builder of the user dictionaries of this kind of dictionary.
+    fn user_dictionary_builder(&self) -> UserDictionaryBuilder;
+
+    /// Builds a user dictionary from a CSV file.
+    ///
+    /// `context_ids` are those of the system dictionary the user dictionary is for, which give
+    /// the simple entries the context ids of their part of speech and an estimated cost. Without
+    /// them, the simple entries get the fixed context id and cost of this kind of dictionary.
+    fn build_user_dict(
+        &self,
+        input_file: &Path,
+        context_ids: Option<ContextIds>,
+    ) -> LinderaResult<UserDictionary> {
+        self.user_dictionary_builder()
+            .with_context_ids(context_ids)
+            .build(input_file)
+    }
+
+    fn build_metadata(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<Metadata> {
+        let options = self.options();
+        MetadataBuilderOptions::default()
+            .name(options.name)
+            .encoding(options.encoding)
+            .compress_algorithm(options.compress_algorithm)
+            .detail_fields(options.detail_fields.to_vec())
+            .build_options(options.build_options())
+            .builder()
+            .unwrap()
+            .build(input_dir, output_dir)
+    }
+
+    /// Builds the index of the readings of the words, from the prefix dictionary built in
+    /// `output_dir`.
+    fn build_reading_index(&self, output_dir: &Path) -> LinderaResult<()> {
+        let options = self.options();
+        ReadingIndexBuilderOptions::default()
+            .reading_field(options.reading_field)
+            .compress_algorithm(options.compress_algorithm)
+            .builder()
+            .unwrap()
+            .build(output_dir)
+    }
+
+    /// Returns the algorithm the built dictionary files are compressed with.
+    fn compress_algorithm(&self) -> Algorithm {
+        self.options().compress_algorithm
+    }
+
+    /// Sets the algorithm the built dictionary files are compressed with.
+    fn set_compress_algorithm(&mut self, algorithm: Algorithm) {
+        self.options_mut().compress_algorithm = algorithm;
+    }
+
+    /// Sets whether `build_dictionary` builds a reading index. It is not built by default.
+    fn set_reading_index(&mut self, enabled: bool) {
+        self.options_mut().reading_index = enabled;
+    }
+
+    /// Builds the dictionary into a single dictionary file instead of a directory.
+    fn build_dictionary_file(&self, input_dir: &Path, output_file: &Path) -> LinderaResult<()> {
+        let mut work_dir = PathBuf::from(output_file).into_os_string();
+        work_dir.push(".build");
+        let work_dir = PathBuf::from(work_dir);
+
+        let result = self
+            .build_dictionary(input_dir, &work_dir)
+            .and_then(|_| DictionaryFile::write(&work_dir, output_file, self.compress_algorithm()));
+        let _ = fs::remove_dir_all(&work_dir);
+        result
+    }
 }
@@ -13,6 +13,7 @@ use crate::decompress::Algorithm;
 use crate::dictionary::character_definition::{
     CategoryData, CategoryId, CharacterDefinition, LookupTable,
 };
+use crate::dictionary_builder::DEFAULT_COMPRESS_ALGORITHM;
 use crate::error::LinderaErrorKind;
 use crate::util::{compress_write, read_file_with_encoding};
 use crate::LinderaResult;
@@ -46,7 +47,7 @@ fn parse_hex_codepoint(s: &str) -> LinderaResult<u32> {
 pub struct CharacterDefinitionBuilder {
     #[builder(default = "\"UTF-8\".into()", setter(into))]
     encoding: Cow<'static, str>,
-    #[builder(default = "Algorithm::Deflate")]
+    #[builder(default = "DEFAULT_COMPRESS_ALGORITHM")]
     compress_algorithm: Algorithm,
     #[builder(default = "Vec::new()")]
     category_definition: Vec<CategoryData>,
@@ -186,11 +187,7 @@ impl CharacterDefinitionBuilder {
             category_names[category_id.0] = category_name.clone();
         }
         let mapping = self.build_lookup_table();
-        CharacterDefinition {
-            category_definitions: self.category_definition.clone(),
-            category_names,
-            mapping,
-        }
+        CharacterDefinition::new(&self.category_definition, &category_names, &mapping)
     }
 
     pub fn build(
@@ -204,11 +201,7 @@ impl CharacterDefinitionBuilder {
 
         // let mut char_definitions_builder = CharacterDefinitionsBuilder::default();
         self.parse(&char_def)?;
-        let char_definitions = self.get_character_definition().clone();
-
-        let mut chardef_buffer = Vec::new();
-        bincode::serialize_into(&mut chardef_buffer, &char_definitions)
-            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        let char_definitions = self.get_character_definition();
 
         let wtr_chardef_path = output_dir.join(Path::new("char_def.bin"));
         let mut wtr_chardef = io::BufWriter::new(
@@ -216,7 +209,11 @@ impl CharacterDefinitionBuilder {
                 .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
         );
 
-        compress_write(&chardef_buffer, self.compress_algorithm, &mut wtr_chardef)?;
+        compress_write(
+            &char_definitions.data,
+            self.compress_algorithm,
+            &mut wtr_chardef,
+        )?;
 
         wtr_chardef
             .flush()
@@ -9,6 +9,7 @@ use derive_builder::Builder;
 use log::debug;
 
 use crate::decompress::Algorithm;
+use crate::dictionary_builder::DEFAULT_COMPRESS_ALGORITHM;
 use crate::error::LinderaErrorKind;
 use crate::util::{compress_write, read_file_with_encoding};
 use crate::LinderaResult;
@@ -19,7 +20,7 @@ use crate::LinderaResult;
 pub struct ConnectionCostMatrixBuilder {
     #[builder(default = "\"UTF-8\".into()", setter(into))]
     encoding: Cow<'static, str>,
-    #[builder(default = "Algorithm::Deflate")]
+    #[builder(default = "DEFAULT_COMPRESS_ALGORITHM")]
     compress_algorithm: Algorithm,
 }
 
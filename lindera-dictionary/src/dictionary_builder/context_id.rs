@@ -0,0 +1,73 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use derive_builder::Builder;
+use log::debug;
+
+use crate::decompress::Algorithm;
+use crate::dictionary::context_id::{median, ContextIds};
+use crate::dictionary_builder::DEFAULT_COMPRESS_ALGORITHM;
+use crate::dictionary_loader::prefix_dictionary::PrefixDictionaryLoader;
+use crate::error::LinderaErrorKind;
+use crate::util::{compress_write, read_file_with_encoding};
+use crate::LinderaResult;
+
+#[derive(Builder, Debug)]
+#[builder(name = ContextIdsBuilderOptions)]
+#[builder(build_fn(name = "builder"))]
+pub struct ContextIdsBuilder {
+    #[builder(default = "\"UTF-8\".into()", setter(into))]
+    encoding: Cow<'static, str>,
+    #[builder(default = "DEFAULT_COMPRESS_ALGORITHM")]
+    compress_algorithm: Algorithm,
+}
+
+impl ContextIdsBuilder {
+    /// Writes the context ids of `left-id.def` and `right-id.def`, with the costs of the words of
+    /// the prefix dictionary built in `output_dir`. They are empty if the source files are missing.
+    pub fn build(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
+        let left_id_def_path = input_dir.join("left-id.def");
+        let right_id_def_path = input_dir.join("right-id.def");
+        let context_ids = if left_id_def_path.exists() && right_id_def_path.exists() {
+            debug!("reading {:?} and {:?}", left_id_def_path, right_id_def_path);
+            let mut context_ids = ContextIds::parse(
+                &read_file_with_encoding(&left_id_def_path, &self.encoding)?,
+                &read_file_with_encoding(&right_id_def_path, &self.encoding)?,
+            )?;
+
+            let prefix_dictionary = PrefixDictionaryLoader::load(output_dir)?;
+            let mut costs: BTreeMap<(u16, u16), Vec<i16>> = BTreeMap::new();
+            for (_, word_entry) in prefix_dictionary.predict("") {
+                costs
+                    .entry((word_entry.left_id, word_entry.right_id))
+                    .or_default()
+                    .push(word_entry.word_cost);
+            }
+            context_ids.costs = costs
+                .into_iter()
+                .filter_map(|(ids, costs)| Some((ids, median(costs)?)))
+                .collect();
+            context_ids
+        } else {
+            ContextIds::default()
+        };
+
+        let mut context_ids_buffer = Vec::new();
+        bincode::serialize_into(&mut context_ids_buffer, &context_ids)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+
+        let mut writer = io::BufWriter::new(
+            File::create(output_dir.join("context_ids.bin"))
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+        );
+        compress_write(&context_ids_buffer, self.compress_algorithm, &mut writer)?;
+        writer
+            .flush()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        Ok(())
+    }
+}
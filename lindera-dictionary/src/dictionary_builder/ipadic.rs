@@ -1,32 +1,47 @@
-use std::fs;
-use std::path::Path;
-
 use csv::StringRecord;
 
-use crate::decompress::Algorithm;
-use crate::dictionary::character_definition::CharacterDefinition;
-use crate::dictionary::UserDictionary;
-use crate::dictionary_builder::DictionaryBuilder;
+use crate::dictionary_builder::user_dictionary::pos_details;
 use crate::dictionary_builder::{
-    build_user_dictionary, CharacterDefinitionBuilderOptions, ConnectionCostMatrixBuilderOptions,
-    PrefixDictionaryBuilderOptions, UnknownDictionaryBuilderOptions, UserDictionaryBuilderOptions,
+    DictionaryBuilder, DictionaryBuilderOptions, UserDictionaryBuilder,
+    UserDictionaryBuilderOptions,
 };
-use crate::error::LinderaErrorKind;
-use crate::LinderaResult;
 
 const SIMPLE_USERDIC_FIELDS_NUM: usize = 3;
+// The number of details that hold the part of speech of a simple entry.
+const SIMPLE_USERDIC_POS_FIELDS_NUM: usize = 4;
 const SIMPLE_WORD_COST: i16 = -10000;
 const SIMPLE_CONTEXT_ID: u16 = 0;
 const DETAILED_USERDIC_FIELDS_NUM: usize = 13;
-const COMPRESS_ALGORITHM: Algorithm = Algorithm::Deflate;
+const NAME: &str = "ipadic";
+const DETAIL_FIELDS: [&str; 9] = [
+    "pos",
+    "pos1",
+    "pos2",
+    "pos3",
+    "conjugation_type",
+    "conjugation_form",
+    "base_form",
+    "reading",
+    "pronunciation",
+];
+// The index of "reading" in the details.
+const READING_FIELD: usize = 7;
 const UNK_FIELDS_NUM: usize = 11;
 const ENCODING: &str = "EUC-JP";
 
-pub struct IpadicBuilder {}
+pub struct IpadicBuilder {
+    options: DictionaryBuilderOptions,
+}
 
 impl IpadicBuilder {
     pub fn new() -> Self {
-        IpadicBuilder {}
+        IpadicBuilder {
+            options: DictionaryBuilderOptions {
+                encoding: ENCODING,
+                normalize_details: true,
+                ..DictionaryBuilderOptions::new(NAME, &DETAIL_FIELDS, READING_FIELD, UNK_FIELDS_NUM)
+            },
+        }
     }
 }
 
@@ -37,97 +52,36 @@ impl Default for IpadicBuilder {
 }
 
 impl DictionaryBuilder for IpadicBuilder {
-    fn build_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
-        fs::create_dir_all(output_dir)
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
-
-        let chardef = self.build_character_definition(input_dir, output_dir)?;
-        self.build_unknown_dictionary(input_dir, &chardef, output_dir)?;
-        self.build_prefix_dictionary(input_dir, output_dir)?;
-        self.build_connection_cost_matrix(input_dir, output_dir)?;
-
-        Ok(())
-    }
-
-    fn build_user_dictionary(&self, input_file: &Path, output_file: &Path) -> LinderaResult<()> {
-        let user_dict = self.build_user_dict(input_file)?;
-        build_user_dictionary(user_dict, output_file)
-    }
-
-    fn build_character_definition(
-        &self,
-        input_dir: &Path,
-        output_dir: &Path,
-    ) -> LinderaResult<CharacterDefinition> {
-        CharacterDefinitionBuilderOptions::default()
-            .encoding(ENCODING)
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .builder()
-            .unwrap()
-            .build(input_dir, output_dir)
+    fn options(&self) -> &DictionaryBuilderOptions {
+        &self.options
     }
 
-    fn build_unknown_dictionary(
-        &self,
-        input_dir: &Path,
-        chardef: &CharacterDefinition,
-        output_dir: &Path,
-    ) -> LinderaResult<()> {
-        UnknownDictionaryBuilderOptions::default()
-            .encoding(ENCODING)
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .unk_fields_num(UNK_FIELDS_NUM)
-            .builder()
-            .unwrap()
-            .build(input_dir, chardef, output_dir)
-    }
-
-    fn build_prefix_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
-        PrefixDictionaryBuilderOptions::default()
-            .flexible_csv(false)
-            .encoding(ENCODING)
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .normalize_details(true)
-            .builder()
-            .unwrap()
-            .build(input_dir, output_dir)
-    }
-
-    fn build_connection_cost_matrix(
-        &self,
-        input_dir: &Path,
-        output_dir: &Path,
-    ) -> LinderaResult<()> {
-        ConnectionCostMatrixBuilderOptions::default()
-            .encoding(ENCODING)
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .builder()
-            .unwrap()
-            .build(input_dir, output_dir)
+    fn options_mut(&mut self) -> &mut DictionaryBuilderOptions {
+        &mut self.options
     }
 
-    fn build_user_dict(&self, input_file: &Path) -> LinderaResult<UserDictionary> {
+    fn user_dictionary_builder(&self) -> UserDictionaryBuilder {
         UserDictionaryBuilderOptions::default()
             .simple_userdic_fields_num(SIMPLE_USERDIC_FIELDS_NUM)
             .detailed_userdic_fields_num(DETAILED_USERDIC_FIELDS_NUM)
             .simple_word_cost(SIMPLE_WORD_COST)
             .simple_context_id(SIMPLE_CONTEXT_ID)
+            .simple_userdic_pos_fields_num(SIMPLE_USERDIC_POS_FIELDS_NUM)
+            .simple_userdic_reading_field(READING_FIELD)
             .flexible_csv(true)
             .simple_userdic_details_handler(Some(Box::new(|row: &StringRecord| {
-                Ok(vec![
-                    row[1].to_string(), // POS
-                    "*".to_string(),    // POS subcategory 1
-                    "*".to_string(),    // POS subcategory 2
-                    "*".to_string(),    // POS subcategory 3
+                // The part of speech may give its subcategories, separated by commas.
+                let mut details = pos_details(&row[1], SIMPLE_USERDIC_POS_FIELDS_NUM)?;
+                details.extend([
                     "*".to_string(),    // Conjugation type
                     "*".to_string(),    // Conjugation form
                     row[0].to_string(), // Base form
                     row[2].to_string(), // Reading
                     "*".to_string(),    // Pronunciation
-                ])
+                ]);
+                Ok(details)
             })))
             .builder()
             .unwrap()
-            .build(input_file)
     }
 }
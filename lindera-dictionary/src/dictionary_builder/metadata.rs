@@ -0,0 +1,159 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use byteorder::{ByteOrder, LittleEndian};
+use derive_builder::Builder;
+use log::debug;
+
+use crate::decompress::Algorithm;
+use crate::dictionary::metadata::Metadata;
+use crate::dictionary::unknown_dictionary::UnknownDictionary;
+use crate::dictionary_builder::DEFAULT_COMPRESS_ALGORITHM;
+use crate::error::LinderaErrorKind;
+use crate::util::read_built_file;
+use crate::LinderaResult;
+
+#[derive(Builder, Debug)]
+#[builder(name = MetadataBuilderOptions)]
+#[builder(build_fn(name = "builder"))]
+pub struct MetadataBuilder {
+    #[builder(setter(into))]
+    name: Cow<'static, str>,
+    #[builder(default = "\"UTF-8\".into()", setter(into))]
+    encoding: Cow<'static, str>,
+    #[builder(default = "DEFAULT_COMPRESS_ALGORITHM")]
+    compress_algorithm: Algorithm,
+    #[builder(default)]
+    detail_fields: Vec<&'static str>,
+    #[builder(default)]
+    build_options: Vec<(&'static str, String)>,
+}
+
+impl MetadataBuilder {
+    /// Writes the metadata of a dictionary whose other files have been built in `output_dir`.
+    pub fn build(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<Metadata> {
+        let words_idx_data = read_built_file(&output_dir.join("dict.wordsidx"))?;
+        let unknown_dictionary =
+            UnknownDictionary::load(&read_built_file(&output_dir.join("unk.bin"))?)?;
+        let matrix_data = read_built_file(&output_dir.join("matrix.mtx"))?;
+        if matrix_data.len() < 4 {
+            return Err(
+                LinderaErrorKind::Content.with_error(anyhow::anyhow!("matrix.mtx is too short"))
+            );
+        }
+
+        let mut build_options = BTreeMap::new();
+        build_options.insert(
+            "compress_algorithm".to_string(),
+            self.compress_algorithm.to_string(),
+        );
+        for (key, value) in self.build_options.iter() {
+            build_options.insert(key.to_string(), value.clone());
+        }
+
+        let metadata = Metadata {
+            name: self.name.to_string(),
+            source: input_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            encoding: self.encoding.to_string(),
+            detail_fields: self
+                .detail_fields
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+            entry_count: words_idx_data.len() / 4,
+            unknown_entry_count: unknown_dictionary.len(),
+            connection_size: (
+                LittleEndian::read_i16(&matrix_data[0..2]) as usize,
+                LittleEndian::read_i16(&matrix_data[2..4]) as usize,
+            ),
+            build_timestamp: build_timestamp(),
+            lindera_version: env!("CARGO_PKG_VERSION").to_string(),
+            build_options,
+        };
+
+        let wtr_metadata_path = output_dir.join(Path::new("metadata.json"));
+        debug!("writing {:?}", wtr_metadata_path);
+        let mut wtr_metadata = io::BufWriter::new(
+            File::create(wtr_metadata_path)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+        );
+        serde_json::to_writer_pretty(&mut wtr_metadata, &metadata)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        wtr_metadata
+            .flush()
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        Ok(metadata)
+    }
+}
+
+// Honors SOURCE_DATE_EPOCH, so that builds can be reproducible.
+fn build_timestamp() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_build_metadata() {
+        use crate::dictionary::metadata::Metadata;
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::test_utils::{load_dictionary, mini_ipadic_dir, temp_dir};
+
+        let output_dir = temp_dir("metadata");
+        let mut builder = IpadicBuilder::new();
+        builder.set_reading_index(true);
+        builder
+            .build_dictionary(&mini_ipadic_dir(), &output_dir)
+            .unwrap();
+
+        let dictionary = load_dictionary(&output_dir);
+        let metadata = dictionary.metadata().unwrap().unwrap();
+        assert_eq!(metadata.name, "ipadic");
+        assert_eq!(metadata.source, "mini-ipadic");
+        assert_eq!(metadata.encoding, "EUC-JP");
+        assert_eq!(metadata.detail_fields.len(), 9);
+        assert_eq!(metadata.detail_fields[6], "base_form");
+        assert_eq!(
+            metadata.entry_count,
+            dictionary.prefix_dictionary.words_idx_data.len() / 4
+        );
+        assert_eq!(metadata.unknown_entry_count, 8);
+        assert_eq!(metadata.connection_size, (8, 8));
+        assert!(metadata.build_timestamp > 0);
+        assert_eq!(metadata.lindera_version, env!("CARGO_PKG_VERSION"));
+        // The build options are those the builder used.
+        let options = builder.options();
+        for (key, value) in [
+            ("compress_algorithm", options.compress_algorithm.to_string()),
+            ("flexible_csv", "false".to_string()),
+            ("normalize_details", "true".to_string()),
+            ("skip_invalid_cost_or_id", "false".to_string()),
+            ("reading_index", "true".to_string()),
+        ] {
+            assert_eq!(metadata.build_options[key], value);
+        }
+        assert_eq!(metadata.build_options.len(), 5);
+
+        // Metadata written by other versions may lack fields.
+        let metadata = Metadata::load(br#"{"lindera_version": "0.38.0"}"#).unwrap();
+        assert_eq!(metadata.lindera_version, "0.38.0");
+        assert!(metadata.name.is_empty());
+    }
+}
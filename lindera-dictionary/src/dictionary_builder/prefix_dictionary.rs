@@ -17,6 +17,7 @@ use log::{debug, warn};
 use yada::builder::DoubleArrayBuilder;
 
 use crate::decompress::Algorithm;
+use crate::dictionary_builder::DEFAULT_COMPRESS_ALGORITHM;
 use crate::error::LinderaErrorKind;
 use crate::util::compress_write;
 use crate::viterbi::{WordEntry, WordId};
@@ -31,7 +32,7 @@ pub struct PrefixDictionaryBuilder {
     /* If set to UTF-8, it can also read UTF-16 files with BOM. */
     #[builder(default = "\"UTF-8\".into()", setter(into))]
     encoding: Cow<'static, str>,
-    #[builder(default = "Algorithm::Deflate")]
+    #[builder(default = "DEFAULT_COMPRESS_ALGORITHM")]
     compress_algorithm: Algorithm,
     #[builder(default = "false")]
     normalize_details: bool,
@@ -199,7 +200,7 @@ impl PrefixDictionaryBuilder {
             } else {
                 row.iter().skip(4).collect::<Vec<&str>>().join("\0")
             };
-            let joined_details_len = u32::try_from(joined_details.as_bytes().len())
+            let joined_details_len = u32::try_from(joined_details.len())
                 .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
 
             dict_words_buffer
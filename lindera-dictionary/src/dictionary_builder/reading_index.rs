@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use derive_builder::Builder;
+use log::debug;
+use yada::builder::DoubleArrayBuilder;
+
+use crate::decompress::Algorithm;
+use crate::dictionary_builder::DEFAULT_COMPRESS_ALGORITHM;
+use crate::dictionary_loader::prefix_dictionary::PrefixDictionaryLoader;
+use crate::error::LinderaErrorKind;
+use crate::util::compress_write;
+use crate::LinderaResult;
+
+#[derive(Builder, Debug)]
+#[builder(name = ReadingIndexBuilderOptions)]
+#[builder(build_fn(name = "builder"))]
+pub struct ReadingIndexBuilder {
+    /// The index of the reading among the details of a word.
+    reading_field: usize,
+    #[builder(default = "DEFAULT_COMPRESS_ALGORITHM")]
+    compress_algorithm: Algorithm,
+}
+
+impl ReadingIndexBuilder {
+    /// Writes the reading index of a dictionary whose prefix dictionary has been built in
+    /// `output_dir`.
+    pub fn build(&self, output_dir: &Path) -> LinderaResult<()> {
+        let prefix_dictionary = PrefixDictionaryLoader::load(output_dir)?;
+
+        let mut readings: BTreeMap<&str, Vec<(u32, String)>> = BTreeMap::new();
+        for (surface, word_entry) in prefix_dictionary.predict("") {
+            let word_id = word_entry.word_id.id;
+            let details = prefix_dictionary.word_details(word_id as usize);
+            match details.get(self.reading_field) {
+                // Words of unknown reading have "*" instead.
+                Some(&reading) if !reading.is_empty() && reading != "*" => {
+                    readings
+                        .entry(reading)
+                        .or_default()
+                        .push((word_id, surface));
+                }
+                _ => {}
+            }
+        }
+        debug!("indexing {} readings", readings.len());
+
+        let mut keyset: Vec<(&[u8], u32)> = Vec::with_capacity(readings.len());
+        let mut offsets_buffer = Vec::new();
+        let mut entries_buffer = Vec::new();
+        for (reading_id, (reading, words)) in readings.iter().enumerate() {
+            keyset.push((reading.as_bytes(), reading_id as u32));
+            offsets_buffer
+                .write_u32::<LittleEndian>(entries_buffer.len() as u32)
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            for (word_id, surface) in words {
+                entries_buffer
+                    .write_u32::<LittleEndian>(*word_id)
+                    .and_then(|_| entries_buffer.write_u32::<LittleEndian>(surface.len() as u32))
+                    .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+                entries_buffer.extend_from_slice(surface.as_bytes());
+            }
+        }
+        offsets_buffer
+            .write_u32::<LittleEndian>(entries_buffer.len() as u32)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        let da_buffer = DoubleArrayBuilder::build(&keyset).ok_or_else(|| {
+            LinderaErrorKind::Io.with_error(anyhow::anyhow!("DoubleArray build error."))
+        })?;
+
+        for (name, buffer) in [
+            ("reading.da", &da_buffer),
+            ("reading.idx", &offsets_buffer),
+            ("reading.vals", &entries_buffer),
+        ] {
+            let mut writer = io::BufWriter::new(
+                File::create(output_dir.join(name))
+                    .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
+            );
+            compress_write(buffer, self.compress_algorithm, &mut writer)?;
+            writer
+                .flush()
+                .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,31 +1,55 @@
-use std::fs;
-use std::path::Path;
-
 use csv::StringRecord;
 
-use crate::decompress::Algorithm;
-use crate::dictionary::character_definition::CharacterDefinition;
-use crate::dictionary::UserDictionary;
-use crate::dictionary_builder::DictionaryBuilder;
+use crate::dictionary_builder::user_dictionary::pos_details;
 use crate::dictionary_builder::{
-    build_user_dictionary, CharacterDefinitionBuilderOptions, ConnectionCostMatrixBuilderOptions,
-    PrefixDictionaryBuilderOptions, UnknownDictionaryBuilderOptions, UserDictionaryBuilderOptions,
+    DictionaryBuilder, DictionaryBuilderOptions, UserDictionaryBuilder,
+    UserDictionaryBuilderOptions,
 };
-use crate::error::LinderaErrorKind;
-use crate::LinderaResult;
 
 const SIMPLE_USERDIC_FIELDS_NUM: usize = 3;
+// The number of details that hold the part of speech of a simple entry.
+const SIMPLE_USERDIC_POS_FIELDS_NUM: usize = 4;
 const SIMPLE_WORD_COST: i16 = -10000;
 const SIMPLE_CONTEXT_ID: u16 = 0;
 const DETAILED_USERDIC_FIELDS_NUM: usize = 21;
-const COMPRESS_ALGORITHM: Algorithm = Algorithm::Deflate;
+const NAME: &str = "unidic";
+const DETAIL_FIELDS: [&str; 17] = [
+    "pos1",
+    "pos2",
+    "pos3",
+    "pos4",
+    "conjugation_type",
+    "conjugation_form",
+    "lemma_reading",
+    "lemma",
+    "orthography",
+    "pronunciation",
+    "orthography_base",
+    "pronunciation_base",
+    "word_type",
+    "prefix_form",
+    "prefix_type",
+    "suffix_form",
+    "suffix_type",
+];
+// The index of "lemma_reading" in the details.
+const READING_FIELD: usize = 6;
 const UNK_FIELDS_NUM: usize = 10;
 
-pub struct UnidicBuilder {}
+pub struct UnidicBuilder {
+    options: DictionaryBuilderOptions,
+}
 
 impl UnidicBuilder {
     pub fn new() -> Self {
-        UnidicBuilder {}
+        UnidicBuilder {
+            options: DictionaryBuilderOptions::new(
+                NAME,
+                &DETAIL_FIELDS,
+                READING_FIELD,
+                UNK_FIELDS_NUM,
+            ),
+        }
     }
 }
 
@@ -36,83 +60,27 @@ impl Default for UnidicBuilder {
 }
 
 impl DictionaryBuilder for UnidicBuilder {
-    fn build_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
-        fs::create_dir_all(output_dir)
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
-
-        let chardef = self.build_character_definition(input_dir, output_dir)?;
-        self.build_unknown_dictionary(input_dir, &chardef, output_dir)?;
-        self.build_prefix_dictionary(input_dir, output_dir)?;
-        self.build_connection_cost_matrix(input_dir, output_dir)?;
-
-        Ok(())
-    }
-
-    fn build_user_dictionary(&self, input_file: &Path, output_file: &Path) -> LinderaResult<()> {
-        let user_dict = self.build_user_dict(input_file)?;
-        build_user_dictionary(user_dict, output_file)
+    fn options(&self) -> &DictionaryBuilderOptions {
+        &self.options
     }
 
-    fn build_character_definition(
-        &self,
-        input_dir: &Path,
-        output_dir: &Path,
-    ) -> LinderaResult<CharacterDefinition> {
-        CharacterDefinitionBuilderOptions::default()
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .builder()
-            .unwrap()
-            .build(input_dir, output_dir)
-    }
-
-    fn build_unknown_dictionary(
-        &self,
-        input_dir: &Path,
-        chardef: &CharacterDefinition,
-        output_dir: &Path,
-    ) -> LinderaResult<()> {
-        UnknownDictionaryBuilderOptions::default()
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .unk_fields_num(UNK_FIELDS_NUM)
-            .builder()
-            .unwrap()
-            .build(input_dir, chardef, output_dir)
-    }
-
-    fn build_prefix_dictionary(&self, input_dir: &Path, output_dir: &Path) -> LinderaResult<()> {
-        PrefixDictionaryBuilderOptions::default()
-            .flexible_csv(false)
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .builder()
-            .unwrap()
-            .build(input_dir, output_dir)
-    }
-
-    fn build_connection_cost_matrix(
-        &self,
-        input_dir: &Path,
-        output_dir: &Path,
-    ) -> LinderaResult<()> {
-        ConnectionCostMatrixBuilderOptions::default()
-            .compress_algorithm(COMPRESS_ALGORITHM)
-            .builder()
-            .unwrap()
-            .build(input_dir, output_dir)
+    fn options_mut(&mut self) -> &mut DictionaryBuilderOptions {
+        &mut self.options
     }
 
-    fn build_user_dict(&self, input_file: &Path) -> LinderaResult<UserDictionary> {
+    fn user_dictionary_builder(&self) -> UserDictionaryBuilder {
         UserDictionaryBuilderOptions::default()
             .simple_userdic_fields_num(SIMPLE_USERDIC_FIELDS_NUM)
             .detailed_userdic_fields_num(DETAILED_USERDIC_FIELDS_NUM)
             .simple_word_cost(SIMPLE_WORD_COST)
             .simple_context_id(SIMPLE_CONTEXT_ID)
+            .simple_userdic_pos_fields_num(SIMPLE_USERDIC_POS_FIELDS_NUM)
+            .simple_userdic_reading_field(READING_FIELD)
             .flexible_csv(false)
             .simple_userdic_details_handler(Some(Box::new(|row: &StringRecord| {
-                Ok(vec![
-                    row[1].to_string(), //Major POS classification
-                    "*".to_string(),    // Middle POS classification
-                    "*".to_string(),    // Small POS classification
-                    "*".to_string(),    // Fine POS classification
+                // The part of speech may give its subcategories, separated by commas.
+                let mut details = pos_details(&row[1], SIMPLE_USERDIC_POS_FIELDS_NUM)?;
+                details.extend([
                     "*".to_string(),    // Conjugation form
                     "*".to_string(),    // Conjugation type
                     row[2].to_string(), //Lexeme reading
@@ -126,10 +94,10 @@ impl DictionaryBuilder for UnidicBuilder {
                     "*".to_string(),    // Prefix of a word type
                     "*".to_string(),    // Suffix of a word form
                     "*".to_string(),    // Suffix of a word type
-                ])
+                ]);
+                Ok(details)
             })))
             .builder()
             .unwrap()
-            .build(input_file)
     }
 }
@@ -9,6 +9,7 @@ use log::debug;
 use crate::decompress::Algorithm;
 use crate::dictionary::character_definition::CharacterDefinition;
 use crate::dictionary::unknown_dictionary::parse_unk;
+use crate::dictionary_builder::DEFAULT_COMPRESS_ALGORITHM;
 use crate::error::LinderaErrorKind;
 use crate::util::{compress_write, read_file_with_encoding};
 use crate::LinderaResult;
@@ -19,7 +20,7 @@ use crate::LinderaResult;
 pub struct UnknownDictionaryBuilder {
     #[builder(default = "\"UTF-8\".into()", setter(into))]
     encoding: Cow<'static, str>,
-    #[builder(default = "Algorithm::Deflate")]
+    #[builder(default = "DEFAULT_COMPRESS_ALGORITHM")]
     compress_algorithm: Algorithm,
     #[builder(default = "11")]
     unk_fields_num: usize,
@@ -35,11 +36,7 @@ impl UnknownDictionaryBuilder {
         let unk_data_path = input_dir.join("unk.def");
         debug!("reading {:?}", unk_data_path);
         let unk_data = read_file_with_encoding(&unk_data_path, &self.encoding)?;
-        let unknown_dictionary = parse_unk(chardef.categories(), &unk_data, self.unk_fields_num)?;
-
-        let mut unk_buffer = Vec::new();
-        bincode::serialize_into(&mut unk_buffer, &unknown_dictionary)
-            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        let unknown_dictionary = parse_unk(&chardef.categories(), &unk_data, self.unk_fields_num)?;
 
         let wtr_unk_path = output_dir.join(Path::new("unk.bin"));
         let mut wtr_unk = io::BufWriter::new(
@@ -47,7 +44,11 @@ impl UnknownDictionaryBuilder {
                 .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?,
         );
 
-        compress_write(&unk_buffer, self.compress_algorithm, &mut wtr_unk)?;
+        compress_write(
+            &unknown_dictionary.data,
+            self.compress_algorithm,
+            &mut wtr_unk,
+        )?;
 
         wtr_unk
             .flush()
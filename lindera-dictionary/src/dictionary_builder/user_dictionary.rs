@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 use std::fs;
 use std::fs::File;
 use std::io;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::Path;
 
 use byteorder::{LittleEndian, WriteBytesExt};
@@ -12,8 +12,9 @@ use log::debug;
 use yada::builder::DoubleArrayBuilder;
 use yada::DoubleArray;
 
+use crate::dictionary::context_id::ContextIds;
 use crate::dictionary::prefix_dictionary::PrefixDictionary;
-use crate::dictionary::UserDictionary;
+use crate::dictionary::{UserDictionary, WordPart};
 use crate::error::LinderaErrorKind;
 use crate::viterbi::{WordEntry, WordId};
 use crate::LinderaResult;
@@ -29,6 +30,8 @@ pub struct UserDictionaryBuilder {
     simple_userdic_fields_num: usize,
     #[builder(default = "4")]
     detailed_userdic_fields_num: usize,
+    #[builder(default = "4")]
+    segmentation_userdic_fields_num: usize,
     #[builder(default = "-10000")]
     simple_word_cost: i16,
     #[builder(default = "0")]
@@ -37,17 +40,43 @@ pub struct UserDictionaryBuilder {
     flexible_csv: bool,
     #[builder(setter(strip_option), default = "None")]
     simple_userdic_details_handler: StringRecordProcessor,
+    /// The number of leading details of a simple entry that hold its part of speech.
+    #[builder(default = "1")]
+    simple_userdic_pos_fields_num: usize,
+    /// The index of the reading in the details of a simple entry.
+    #[builder(default = "1")]
+    simple_userdic_reading_field: usize,
+    #[builder(setter(skip))]
+    context_ids: Option<ContextIds>,
 }
 
 impl UserDictionaryBuilder {
+    /// Sets the context ids of the system dictionary. The simple entries whose part of speech is
+    /// found in them get its context ids and a cost estimated from the words of the system
+    /// dictionary, instead of `simple_context_id` and `simple_word_cost`.
+    pub fn with_context_ids(mut self, context_ids: Option<ContextIds>) -> Self {
+        self.context_ids = context_ids;
+        self
+    }
+
     pub fn build(&self, input_file: &Path) -> LinderaResult<UserDictionary> {
         debug!("reading {:?}", input_file);
 
+        let file = File::open(input_file)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        self.build_from_reader(file)
+    }
+
+    /// Builds a user dictionary from the CSV rows read from `reader`.
+    pub fn build_from_reader<R: Read>(&self, reader: R) -> LinderaResult<UserDictionary> {
+        self.build_from_records(self.read_records(reader)?)
+    }
+
+    fn read_records<R: Read>(&self, reader: R) -> LinderaResult<Vec<StringRecord>> {
         let mut rdr = csv::ReaderBuilder::new()
             .has_headers(false)
             .flexible(self.flexible_csv)
-            .from_path(input_file)
-            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+            .from_reader(reader);
 
         let mut rows: Vec<StringRecord> = vec![];
         for result in rdr.records() {
@@ -55,23 +84,33 @@ impl UserDictionaryBuilder {
                 result.map_err(|err| LinderaErrorKind::Content.with_error(anyhow::anyhow!(err)))?;
             rows.push(record);
         }
+        Ok(rows)
+    }
+
+    /// Builds a user dictionary from rows with the fields of the rows of a CSV file.
+    pub fn build_from_records(&self, mut rows: Vec<StringRecord>) -> LinderaResult<UserDictionary> {
+        if let Some(row) = rows.iter().find(|row| row.is_empty()) {
+            return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "user dictionary row has no fields: {:?}",
+                row
+            )));
+        }
         rows.sort_by_key(|row| row[0].to_string());
 
         let mut word_entry_map: BTreeMap<String, Vec<WordEntry>> = BTreeMap::new();
 
         for (row_id, row) in rows.iter().enumerate() {
             let surface = row[0].to_string();
-            let word_cost = if row.len() == self.simple_userdic_fields_num {
-                self.simple_word_cost
-            } else {
-                row[3].parse::<i16>().map_err(|_err| {
-                    LinderaErrorKind::Parse.with_error(anyhow::anyhow!("failed to parse word cost"))
-                })?
-            };
-            let (left_id, right_id) = if row.len() == self.simple_userdic_fields_num {
-                (self.simple_context_id, self.simple_context_id)
+            let (word_cost, left_id, right_id) = if row.len() == self.simple_userdic_fields_num {
+                self.simple_entry(&row[1])
+            } else if self.is_segmentation_row(row) {
+                self.simple_entry(&row[3])
             } else {
                 (
+                    row[3].parse::<i16>().map_err(|_err| {
+                        LinderaErrorKind::Parse
+                            .with_error(anyhow::anyhow!("failed to parse word cost"))
+                    })?,
                     row[1].parse::<u16>().map_err(|_err| {
                         LinderaErrorKind::Parse
                             .with_error(anyhow::anyhow!("failed to parse left context id"))
@@ -96,16 +135,17 @@ impl UserDictionaryBuilder {
 
         let mut words_data = Vec::<u8>::new();
         let mut words_idx_data = Vec::<u8>::new();
-        for row in rows.iter() {
+        let mut segmentations = BTreeMap::new();
+        for (row_id, row) in rows.iter().enumerate() {
             let word_detail = if row.len() == self.simple_userdic_fields_num {
-                if let Some(handler) = &self.simple_userdic_details_handler {
-                    handler(row)?
-                } else {
-                    row.iter()
-                        .skip(1)
-                        .map(|s| s.to_string())
-                        .collect::<Vec<String>>()
-                }
+                self.simple_details(row)?
+            } else if self.is_segmentation_row(row) {
+                let parts = self.word_parts(row)?;
+                segmentations.insert(row_id as u32, parts);
+
+                // The whole word has the details of a simple entry, with the readings joined.
+                let reading = row[2].split_whitespace().collect::<String>();
+                self.simple_details(&StringRecord::from(vec![&row[0], &row[3], &reading]))?
             } else if row.len() >= self.detailed_userdic_fields_num {
                 let mut tmp_word_detail = Vec::new();
                 for item in row.iter().skip(4) {
@@ -114,8 +154,9 @@ impl UserDictionaryBuilder {
                 tmp_word_detail
             } else {
                 return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
-                    "user dictionary should be a CSV with {} or {}+ fields",
+                    "user dictionary should be a CSV with {}, {} or {}+ fields",
                     self.simple_userdic_fields_num,
+                    self.segmentation_userdic_fields_num,
                     self.detailed_userdic_fields_num
                 )));
             };
@@ -153,14 +194,223 @@ impl UserDictionaryBuilder {
         }
 
         let dict = PrefixDictionary {
-            da: DoubleArray::new(da_bytes),
-            vals_data,
-            words_idx_data,
-            words_data,
+            da: DoubleArray::new(da_bytes.into()),
+            vals_data: vals_data.into(),
+            words_idx_data: words_idx_data.into(),
+            words_data: words_data.into(),
             is_system: false,
         };
 
-        Ok(UserDictionary { dict })
+        Ok(UserDictionary {
+            dict,
+            segmentations,
+        })
+    }
+
+    /// Returns the cost and the left and right context ids of a simple entry of the given part of
+    /// speech.
+    fn simple_entry(&self, pos: &str) -> (i16, u16, u16) {
+        self.context_ids
+            .as_ref()
+            .and_then(|context_ids| {
+                let (left_id, right_id) = context_ids.resolve(pos)?;
+                let word_cost = context_ids
+                    .estimate_cost(left_id, right_id)
+                    .unwrap_or(self.simple_word_cost);
+                Some((word_cost, left_id, right_id))
+            })
+            .unwrap_or((
+                self.simple_word_cost,
+                self.simple_context_id,
+                self.simple_context_id,
+            ))
+    }
+
+    /// Returns whether a row is a custom segmentation entry, i.e. `surface,segmentation,readings,pos`
+    /// where the segmentation and the readings are separated by spaces.
+    fn is_segmentation_row(&self, row: &StringRecord) -> bool {
+        row.len() == self.segmentation_userdic_fields_num
+            && row.len() < self.detailed_userdic_fields_num
+    }
+
+    fn simple_details(&self, row: &StringRecord) -> LinderaResult<Vec<String>> {
+        if let Some(handler) = &self.simple_userdic_details_handler {
+            handler(row)
+        } else {
+            Ok(row
+                .iter()
+                .skip(1)
+                .map(|s| s.to_string())
+                .collect::<Vec<String>>())
+        }
+    }
+
+    /// Returns the `surface,segmentation,readings,pos` row of a word segmented into `parts`, which
+    /// is read back into the same parts. The part of speech is the one of the first part, without
+    /// its trailing `*` features.
+    pub fn segmentation_record(&self, surface: &str, parts: &[WordPart]) -> Vec<String> {
+        let detail = |part: &WordPart, index: usize| {
+            part.details
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| "*".to_string())
+        };
+        let mut pos = parts
+            .first()
+            .map(|part| {
+                (0..self.simple_userdic_pos_fields_num)
+                    .map(|index| detail(part, index))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        while pos.len() > 1 && pos.last().map(String::as_str) == Some("*") {
+            pos.pop();
+        }
+
+        vec![
+            surface.to_string(),
+            parts
+                .iter()
+                .map(|part| part.surface.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            parts
+                .iter()
+                .map(|part| detail(part, self.simple_userdic_reading_field))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pos.join(","),
+        ]
+    }
+
+    /// Splits a custom segmentation entry into its parts, each with the details of a simple entry.
+    fn word_parts(&self, row: &StringRecord) -> LinderaResult<Vec<WordPart>> {
+        let surfaces = row[1].split_whitespace().collect::<Vec<_>>();
+        let readings = row[2].split_whitespace().collect::<Vec<_>>();
+        if surfaces.concat() != row[0] {
+            return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "segmentation does not match the surface: {}",
+                &row[0]
+            )));
+        }
+        if surfaces.len() != readings.len() {
+            return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+                "number of readings does not match the segmentation: {}",
+                &row[0]
+            )));
+        }
+
+        surfaces
+            .into_iter()
+            .zip(readings)
+            .map(|(surface, reading)| {
+                Ok(WordPart {
+                    surface: surface.to_string(),
+                    details: self
+                        .simple_details(&StringRecord::from(vec![surface, &row[3], reading]))?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Splits the part of speech of a simple entry, whose features are separated by commas, into
+/// `fields_num` details, the missing ones being `*`.
+pub fn pos_details(pos: &str, fields_num: usize) -> LinderaResult<Vec<String>> {
+    let mut details = pos
+        .split(',')
+        .map(|feature| feature.trim().to_string())
+        .collect::<Vec<_>>();
+    if details.len() > fields_num {
+        return Err(LinderaErrorKind::Content.with_error(anyhow::anyhow!(
+            "part of speech has more than {} features: {}",
+            fields_num,
+            pos
+        )));
+    }
+    details.resize(fields_num, "*".to_string());
+    Ok(details)
+}
+
+/// The entries of a user dictionary, collected in memory rather than read from a CSV file.
+///
+/// The entries are validated like the rows of a CSV file when the dictionary is built.
+pub struct UserDictionaryEntries {
+    builder: UserDictionaryBuilder,
+    rows: Vec<StringRecord>,
+}
+
+impl UserDictionaryEntries {
+    pub fn new(builder: UserDictionaryBuilder) -> Self {
+        Self {
+            builder,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Sets the context ids of the system dictionary, to give the simple entries the context ids
+    /// of their part of speech and an estimated cost. See `UserDictionaryBuilder::with_context_ids`.
+    pub fn with_context_ids(mut self, context_ids: Option<ContextIds>) -> Self {
+        self.builder = self.builder.with_context_ids(context_ids);
+        self
+    }
+
+    /// Adds a simple entry, like a `surface,pos,reading` row.
+    pub fn add_simple(self, surface: &str, pos: &str, reading: &str) -> Self {
+        self.add_record(&[surface, pos, reading])
+    }
+
+    /// Adds a detailed entry, like a `surface,left_id,right_id,cost,details...` row.
+    pub fn add_detailed(
+        self,
+        surface: &str,
+        left_id: u16,
+        right_id: u16,
+        cost: i16,
+        details: &[&str],
+    ) -> Self {
+        let (left_id, right_id, cost) =
+            (left_id.to_string(), right_id.to_string(), cost.to_string());
+        let mut record = vec![surface, &left_id, &right_id, &cost];
+        record.extend_from_slice(details);
+        self.add_record(&record)
+    }
+
+    /// Adds an entry segmented into several tokens, like a `surface,parts,readings,pos` row.
+    pub fn add_segmentation(
+        self,
+        surface: &str,
+        parts: &[&str],
+        readings: &[&str],
+        pos: &str,
+    ) -> Self {
+        self.add_record(&[surface, &parts.join(" "), &readings.join(" "), pos])
+    }
+
+    /// Adds an entry with the fields of a CSV row.
+    pub fn add_record(mut self, record: &[&str]) -> Self {
+        self.rows.push(StringRecord::from(record));
+        self
+    }
+
+    /// Adds the entries of the CSV rows read from `reader`.
+    pub fn read_csv<R: Read>(mut self, reader: R) -> LinderaResult<Self> {
+        let rows = self.builder.read_records(reader)?;
+        self.rows.extend(rows);
+        Ok(self)
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn build(self) -> LinderaResult<UserDictionary> {
+        self.builder.build_from_records(self.rows)
     }
 }
 
@@ -187,3 +437,252 @@ pub fn build_user_dictionary(user_dict: UserDictionary, output_file: &Path) -> L
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::dictionary::WordPart;
+    use crate::dictionary_builder::ipadic::IpadicBuilder;
+    use crate::dictionary_builder::DictionaryBuilder;
+    use crate::test_utils::temp_dir;
+
+    fn build(rows: &str) -> crate::LinderaResult<crate::dictionary::UserDictionary> {
+        let dir = temp_dir("userdic");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("userdic.csv");
+        std::fs::write(&path, rows).unwrap();
+        IpadicBuilder::new().build_user_dict(&path, None)
+    }
+
+    #[test]
+    fn test_build_segmentation_entry() {
+        let user_dict = build(
+            "関西国際空港,関西 国際 空港,カンサイ コクサイ クウコウ,カスタム名詞\n\
+             東京スカイツリー,カスタム名詞,トウキョウスカイツリー\n",
+        )
+        .unwrap();
+
+        // Rows are sorted by surface, so the segmentation entry comes second.
+        assert_eq!(
+            user_dict.word_details(1),
+            vec![
+                "カスタム名詞",
+                "*",
+                "*",
+                "*",
+                "*",
+                "*",
+                "関西国際空港",
+                "カンサイコクサイクウコウ",
+                "*"
+            ]
+        );
+        let parts = user_dict.word_parts(1).unwrap();
+        assert_eq!(
+            parts
+                .iter()
+                .map(|part| part.surface.as_str())
+                .collect::<Vec<_>>(),
+            vec!["関西", "国際", "空港"]
+        );
+        assert_eq!(
+            parts[2],
+            WordPart {
+                surface: "空港".to_string(),
+                details: [
+                    "カスタム名詞",
+                    "*",
+                    "*",
+                    "*",
+                    "*",
+                    "*",
+                    "空港",
+                    "クウコウ",
+                    "*"
+                ]
+                .iter()
+                .map(|detail| detail.to_string())
+                .collect(),
+            }
+        );
+        assert!(user_dict.word_parts(0).is_none());
+    }
+
+    #[test]
+    fn test_build_invalid_segmentation_entry() {
+        // The parts do not make up the surface.
+        assert!(build("関西国際空港,関西 国際,カンサイ コクサイ,カスタム名詞\n").is_err());
+        // The number of readings does not match the number of parts.
+        assert!(build("関西国際空港,関西 国際 空港,カンサイ コクサイ,カスタム名詞\n").is_err());
+    }
+
+    #[test]
+    fn test_build_user_dictionary_with_context_ids() {
+        use crate::dictionary::UserDictionary;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::test_utils::{mini_ipadic, temp_dir};
+
+        let dir = temp_dir("user-dictionary-context-ids");
+        std::fs::create_dir_all(&dir).unwrap();
+        let input_file = dir.join("userdic.csv");
+        std::fs::write(
+            &input_file,
+            "東京スカイツリー,\"名詞,固有名詞,地域\",トウキョウスカイツリー\n",
+        )
+        .unwrap();
+        let build = |context_ids| {
+            let output_file = dir.join("userdic.bin");
+            IpadicBuilder::new()
+                .build_user_dictionary(&input_file, &output_file, context_ids)
+                .unwrap();
+            let user_dict = UserDictionary::load(&std::fs::read(&output_file).unwrap()).unwrap();
+            let entry = user_dict.lookup("東京スカイツリー").remove(0);
+            (entry.left_id, entry.right_id, entry.word_cost)
+        };
+
+        // The user dictionary written to a file has the context ids of the system dictionary.
+        assert_eq!(
+            build(mini_ipadic().context_ids().unwrap().cloned()),
+            (4, 4, 3000)
+        );
+        assert_eq!(build(None), (0, 0, -10000));
+    }
+
+    #[test]
+    fn test_build_with_context_ids() {
+        use crate::dictionary::UserDictionary;
+        use crate::test_utils::mini_ipadic;
+
+        let dictionary = mini_ipadic();
+        let context_ids = dictionary.context_ids().unwrap().unwrap().clone();
+        assert_eq!(context_ids.left_ids.len(), 8);
+
+        let user_dict = UserDictionary::builder(&IpadicBuilder::new())
+            .with_context_ids(Some(context_ids))
+            .read_csv(
+                "東京スカイツリー,\"名詞,固有名詞,地域\",トウキョウスカイツリー\n\
+                 東京都庁,\"名詞,固有名詞,組織\",トウキョウトチョウ\n\
+                 関西国際空港,関西 国際 空港,カンサイ コクサイ クウコウ,\"名詞,固有名詞,地域\"\n\
+                 とうきょうスカイツリー駅,カスタム名詞,トウキョウスカイツリーエキ\n"
+                    .as_bytes(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let entry = |surface: &'static str| {
+            let entry = user_dict.lookup(surface).remove(0);
+            (
+                entry.left_id,
+                entry.right_id,
+                entry.word_cost,
+                entry.details,
+            )
+        };
+
+        // The cost is the median of the words with the same context ids.
+        assert_eq!(
+            entry("東京スカイツリー"),
+            (
+                4,
+                4,
+                3000,
+                vec![
+                    "名詞",
+                    "固有名詞",
+                    "地域",
+                    "*",
+                    "*",
+                    "*",
+                    "東京スカイツリー",
+                    "トウキョウスカイツリー",
+                    "*"
+                ]
+            )
+        );
+        assert_eq!(entry("関西国際空港").0, 4);
+        // No word has these context ids, so the cost is the median of all the words.
+        assert_eq!(entry("東京都庁").2, 3000);
+        assert_eq!(entry("東京都庁").0, 6);
+        // A part of speech that is not in the dictionary keeps the context id and cost of simple
+        // entries.
+        assert_eq!(entry("とうきょうスカイツリー駅").0, 0);
+        assert_eq!(entry("とうきょうスカイツリー駅").2, -10000);
+
+        assert!(UserDictionary::builder(&IpadicBuilder::new())
+            .add_simple(
+                "東京スカイツリー",
+                "名詞,固有名詞,地域,一般,*",
+                "トウキョウスカイツリー"
+            )
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_build_from_entries() {
+        use crate::dictionary::UserDictionary;
+
+        let rows = "東京スカイツリー,カスタム名詞,トウキョウスカイツリー\n\
+                    東武スカイツリーライン,1288,1288,-1000,名詞,固有名詞,一般,*,*,*,東武スカイツリーライン,トウブスカイツリーライン,トウブスカイツリーライン\n\
+                    関西国際空港,関西 国際 空港,カンサイ コクサイ クウコウ,カスタム名詞\n";
+        let expected = bincode::serialize(&build(rows).unwrap()).unwrap();
+
+        // Entries added in memory, in any order, build the same dictionary as the CSV file.
+        let user_dict = UserDictionary::builder(&IpadicBuilder::new())
+            .add_segmentation(
+                "関西国際空港",
+                &["関西", "国際", "空港"],
+                &["カンサイ", "コクサイ", "クウコウ"],
+                "カスタム名詞",
+            )
+            .add_detailed(
+                "東武スカイツリーライン",
+                1288,
+                1288,
+                -1000,
+                &[
+                    "名詞",
+                    "固有名詞",
+                    "一般",
+                    "*",
+                    "*",
+                    "*",
+                    "東武スカイツリーライン",
+                    "トウブスカイツリーライン",
+                    "トウブスカイツリーライン",
+                ],
+            )
+            .add_simple("東京スカイツリー", "カスタム名詞", "トウキョウスカイツリー")
+            .build()
+            .unwrap();
+        assert_eq!(bincode::serialize(&user_dict).unwrap(), expected);
+
+        // So do the rows read from any reader.
+        let entries = UserDictionary::builder(&IpadicBuilder::new())
+            .read_csv(rows.as_bytes())
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            bincode::serialize(&entries.build().unwrap()).unwrap(),
+            expected
+        );
+
+        // The entries are validated like the rows of a CSV file.
+        assert!(UserDictionary::builder(&IpadicBuilder::new())
+            .add_detailed("東京スカイツリー", 1288, 1288, -1000, &["名詞", "固有名詞"])
+            .build()
+            .is_err());
+        assert!(UserDictionary::builder(&IpadicBuilder::new())
+            .add_segmentation(
+                "関西国際空港",
+                &["関西", "国際"],
+                &["カンサイ", "コクサイ"],
+                "カスタム名詞"
+            )
+            .build()
+            .is_err());
+        assert!(UserDictionary::builder(&IpadicBuilder::new())
+            .add_record(&[])
+            .build()
+            .is_err());
+    }
+}
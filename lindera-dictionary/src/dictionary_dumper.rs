@@ -0,0 +1,468 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+use derive_builder::Builder;
+use encoding_rs::{Encoding, UTF_8};
+use log::{debug, warn};
+
+use crate::dictionary::character_definition::{CategoryId, CharacterDefinition};
+use crate::dictionary::connection_cost_matrix::ConnectionCostMatrix;
+use crate::dictionary::context_id::ContextIds;
+use crate::dictionary::metadata::Metadata;
+use crate::dictionary::prefix_dictionary::PrefixDictionary;
+use crate::dictionary::unknown_dictionary::UnknownDictionary;
+use crate::dictionary::{Dictionary, UserDictionary};
+use crate::dictionary_builder::UserDictionaryBuilder;
+use crate::error::LinderaErrorKind;
+use crate::viterbi::WordEntry;
+use crate::LinderaResult;
+
+const DEFAULT_CATEGORY_NAME: &str = "DEFAULT";
+const DEFAULT_CSV_NAME: &str = "lex";
+
+/// Writes the source files of a built dictionary, so that building them again yields the same
+/// dictionary.
+#[derive(Builder, Debug)]
+#[builder(name = DictionaryDumperOptions)]
+#[builder(build_fn(name = "builder"))]
+pub struct DictionaryDumper {
+    /// The name of the CSV file of the entries, without its extension.
+    #[builder(default = "DEFAULT_CSV_NAME.into()", setter(into))]
+    name: Cow<'static, str>,
+    #[builder(default = "\"UTF-8\".into()", setter(into))]
+    encoding: Cow<'static, str>,
+    /// Whether the dictionary was built with `normalize_details`.
+    #[builder(default = "false")]
+    normalize_details: bool,
+}
+
+impl DictionaryDumper {
+    /// Creates a dumper that writes the files in the encoding the dictionary was built from.
+    pub fn from_metadata(metadata: Option<&Metadata>) -> DictionaryDumper {
+        let mut options = DictionaryDumperOptions::default();
+        if let Some(metadata) = metadata {
+            if !metadata.name.is_empty() {
+                options.name(metadata.name.clone());
+            }
+            if !metadata.encoding.is_empty() {
+                options.encoding(metadata.encoding.clone());
+            }
+            options.normalize_details(
+                metadata
+                    .build_options
+                    .get("normalize_details")
+                    .is_some_and(|value| value == "true"),
+            );
+        }
+        options.builder().unwrap()
+    }
+
+    /// Writes the entries, `matrix.def`, `char.def` and `unk.def` of the dictionary to
+    /// `output_dir`, and `left-id.def` and `right-id.def` if it was built with them.
+    pub fn dump(&self, dictionary: &Dictionary, output_dir: &Path) -> LinderaResult<()> {
+        fs::create_dir_all(output_dir)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+
+        self.dump_prefix_dictionary(
+            &dictionary.prefix_dictionary,
+            &output_dir.join(format!("{}.csv", self.name)),
+        )?;
+        self.dump_connection_cost_matrix(
+            &dictionary.connection_cost_matrix,
+            &output_dir.join("matrix.def"),
+        )?;
+        self.dump_character_definition(
+            &dictionary.character_definition,
+            &output_dir.join("char.def"),
+        )?;
+        self.dump_unknown_dictionary(
+            &dictionary.unknown_dictionary,
+            &dictionary.character_definition,
+            &output_dir.join("unk.def"),
+        )?;
+        if let Some(context_ids) = dictionary
+            .context_ids()?
+            .filter(|context_ids| !context_ids.is_empty())
+        {
+            self.dump_context_ids(
+                context_ids,
+                &output_dir.join("left-id.def"),
+                &output_dir.join("right-id.def"),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn dump_prefix_dictionary(
+        &self,
+        prefix_dictionary: &PrefixDictionary,
+        output_file: &Path,
+    ) -> LinderaResult<()> {
+        // Characters the builder normalized can not be encoded back in every encoding, so they are
+        // restored to the ones the encoding decodes to.
+        let denormalize = self.normalize_details && self.encoding()? != UTF_8;
+        let field = |text: &str| {
+            if denormalize {
+                denormalize_text(text)
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut skipped = 0;
+        let rows = words_by_id(prefix_dictionary)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(word_id, word)| {
+                let Some((surface, word_entry)) = word else {
+                    skipped += 1;
+                    return None;
+                };
+                let mut row = entry_fields(field(&surface), word_entry);
+                row.extend(
+                    prefix_dictionary
+                        .word_details(word_id)
+                        .into_iter()
+                        .map(&field),
+                );
+                Some(row)
+            })
+            .collect::<Vec<_>>();
+        if skipped > 0 {
+            // The rows skipped for an invalid cost or context id keep a word id, but are not
+            // searchable, so their surface is lost.
+            warn!("{} words without a surface are not dumped", skipped);
+        }
+
+        self.write(output_file, &csv_text(&rows)?)
+    }
+
+    pub fn dump_connection_cost_matrix(
+        &self,
+        connection_cost_matrix: &ConnectionCostMatrix,
+        output_file: &Path,
+    ) -> LinderaResult<()> {
+        let backward_size = connection_cost_matrix.backward_size;
+        let forward_size = ((connection_cost_matrix.costs_data.len() / 2) as u32)
+            .checked_div(backward_size)
+            .unwrap_or(0);
+
+        let mut text = format!("{} {}\n", forward_size, backward_size);
+        for forward_id in 0..forward_size {
+            for backward_id in 0..backward_size {
+                text.push_str(&format!(
+                    "{} {} {}\n",
+                    forward_id,
+                    backward_id,
+                    connection_cost_matrix.cost(forward_id, backward_id)
+                ));
+            }
+        }
+
+        self.write(output_file, &text)
+    }
+
+    pub fn dump_character_definition(
+        &self,
+        character_definition: &CharacterDefinition,
+        output_file: &Path,
+    ) -> LinderaResult<()> {
+        let mut text = String::new();
+        let names = character_definition.categories();
+        for (category_id, name) in names.iter().enumerate() {
+            let category = character_definition.lookup_definition(CategoryId(category_id));
+            text.push_str(&format!(
+                "{} {} {} {}\n",
+                name, category.invoke as u32, category.group as u32, category.length
+            ));
+        }
+        text.push('\n');
+
+        // Each range between two boundaries of the lookup table is written with its categories.
+        // The characters of no range fall back to DEFAULT, so a DEFAULT range is only written when
+        // one of its boundaries would not be written otherwise.
+        let ranges = character_definition.ranges();
+        let is_default = |category_ids: &[CategoryId]| {
+            category_ids.len() == 1 && names[category_ids[0].0] == DEFAULT_CATEGORY_NAME
+        };
+        let mut written = false;
+        for (i, (start, end, category_ids)) in ranges.iter().enumerate() {
+            let next_written = ranges
+                .get(i + 1)
+                .is_some_and(|(_, _, next)| !next.is_empty() && !is_default(next));
+            if category_ids.is_empty()
+                || (is_default(category_ids) && (written || *start == 0) && next_written)
+            {
+                written = false;
+                continue;
+            }
+            if start == end {
+                text.push_str(&format!("0x{:04X}", start));
+            } else {
+                text.push_str(&format!("0x{:04X}..0x{:04X}", start, end));
+            }
+            for category_id in category_ids.iter() {
+                text.push(' ');
+                text.push_str(names[category_id.0]);
+            }
+            text.push('\n');
+            written = true;
+        }
+
+        self.write(output_file, &text)
+    }
+
+    pub fn dump_unknown_dictionary(
+        &self,
+        unknown_dictionary: &UnknownDictionary,
+        character_definition: &CharacterDefinition,
+        output_file: &Path,
+    ) -> LinderaResult<()> {
+        let mut text = String::new();
+        for word_id in 0..unknown_dictionary.len() {
+            let word_entry = unknown_dictionary.word_entry(word_id as u32);
+            let category_id = unknown_dictionary.category(word_id as u32).ok_or_else(|| {
+                LinderaErrorKind::Content
+                    .with_error(anyhow::anyhow!("unknown word {} has no category", word_id))
+            })?;
+            let mut fields = entry_fields(
+                character_definition.category_name(category_id).to_string(),
+                word_entry,
+            );
+            fields.extend(
+                unknown_dictionary
+                    .word_details(word_id as u32)
+                    .into_iter()
+                    .map(str::to_string),
+            );
+            text.push_str(&fields.join(","));
+            text.push('\n');
+        }
+
+        self.write(output_file, &text)
+    }
+
+    pub fn dump_context_ids(
+        &self,
+        context_ids: &ContextIds,
+        left_id_def_file: &Path,
+        right_id_def_file: &Path,
+    ) -> LinderaResult<()> {
+        let id_def_text = |ids: &[(u16, String)]| {
+            ids.iter()
+                .map(|(id, features)| format!("{} {}\n", id, features))
+                .collect::<String>()
+        };
+        self.write(left_id_def_file, &id_def_text(&context_ids.left_ids))?;
+        self.write(right_id_def_file, &id_def_text(&context_ids.right_ids))
+    }
+
+    fn encoding(&self) -> LinderaResult<&'static Encoding> {
+        Encoding::for_label_no_replacement(self.encoding.as_bytes()).ok_or_else(|| {
+            LinderaErrorKind::Decode
+                .with_error(anyhow::anyhow!("Invalid encoding: {}", self.encoding))
+        })
+    }
+
+    fn write(&self, output_file: &Path, text: &str) -> LinderaResult<()> {
+        debug!("writing {:?}", output_file);
+        let (bytes, _, had_errors) = self.encoding()?.encode(text);
+        if had_errors {
+            return Err(LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(
+                "{:?} has characters that can not be encoded in {}",
+                output_file,
+                self.encoding
+            )));
+        }
+        fs::write(output_file, bytes)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+    }
+}
+
+/// Writes the entries of a user dictionary as a CSV file of detailed entries.
+///
+/// The simple entries are written with the details and the cost they were given, and the words
+/// with a custom segmentation as segmentation entries, whose parts are read by `builder`.
+pub fn dump_user_dictionary(
+    user_dict: &UserDictionary,
+    builder: &UserDictionaryBuilder,
+    output_file: &Path,
+) -> LinderaResult<()> {
+    let rows = words_by_id(&user_dict.dict)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(word_id, word)| {
+            let (surface, word_entry) = word?;
+            if let Some(parts) = user_dict.word_parts(word_id) {
+                return Some(builder.segmentation_record(&surface, parts));
+            }
+            let mut row = entry_fields(surface, word_entry);
+            row.extend(
+                user_dict
+                    .word_details(word_id)
+                    .into_iter()
+                    .map(str::to_string),
+            );
+            Some(row)
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(parent_dir) = output_file.parent() {
+        fs::create_dir_all(parent_dir)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+    }
+    fs::write(output_file, csv_text(&rows)?)
+        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))
+}
+
+/// Returns the surface and the entry of each word, by word id.
+fn words_by_id(prefix_dictionary: &PrefixDictionary) -> Vec<Option<(String, WordEntry)>> {
+    let mut words = vec![None; prefix_dictionary.words_idx_data.len() / 4];
+    for (surface, word_entry) in prefix_dictionary.predict("") {
+        if let Some(word) = words.get_mut(word_entry.word_id.id as usize) {
+            *word = Some((surface, word_entry));
+        }
+    }
+    words
+}
+
+fn entry_fields(surface: String, word_entry: WordEntry) -> Vec<String> {
+    vec![
+        surface,
+        word_entry.left_id.to_string(),
+        word_entry.right_id.to_string(),
+        word_entry.word_cost.to_string(),
+    ]
+}
+
+fn csv_text(rows: &[Vec<String>]) -> LinderaResult<String> {
+    let mut wtr = csv::WriterBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_writer(Vec::new());
+    for row in rows {
+        wtr.write_record(row)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+    }
+    let bytes = wtr
+        .into_inner()
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+    String::from_utf8(bytes)
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))
+}
+
+/// Reverses the normalization of the prefix dictionary builder.
+fn denormalize_text(text: &str) -> String {
+    text.replace('—', "―").replace('〜', "～")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    #[test]
+    fn test_dump_round_trip() {
+        use encoding_rs::EUC_JP;
+
+        use crate::decompress::Algorithm;
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::dictionary_dumper::DictionaryDumper;
+        use crate::test_utils::{load_dictionary, mini_ipadic_dir, temp_dir};
+
+        // The dash and the wave dash are normalized by the builder.
+        let src_dir = temp_dir("dump-src");
+        std::fs::create_dir_all(&src_dir).unwrap();
+        for name in [
+            "mini.csv",
+            "matrix.def",
+            "char.def",
+            "unk.def",
+            "left-id.def",
+            "right-id.def",
+        ] {
+            std::fs::copy(mini_ipadic_dir().join(name), src_dir.join(name)).unwrap();
+        }
+        let (extra, _, _) = EUC_JP.encode(
+            "―,5,5,3000,記号,一般,*,*,*,*,―,―,―\n\
+             ～,5,5,3000,記号,一般,*,*,*,*,～,～,～\n",
+        );
+        std::fs::write(src_dir.join("extra.csv"), extra).unwrap();
+
+        let build = |input_dir: &Path, name: &str| {
+            let output_dir = temp_dir(name);
+            let mut builder = IpadicBuilder::new();
+            builder.set_compress_algorithm(Algorithm::Raw);
+            builder.build_dictionary(input_dir, &output_dir).unwrap();
+            output_dir
+        };
+        let built_dir = build(&src_dir, "dump-built");
+        let dictionary = load_dictionary(&built_dir);
+
+        let dump_dir = temp_dir("dump-dumped");
+        DictionaryDumper::from_metadata(dictionary.metadata().unwrap())
+            .dump(&dictionary, &dump_dir)
+            .unwrap();
+        assert!(dump_dir.join("ipadic.csv").exists());
+
+        let rebuilt_dir = build(&dump_dir, "dump-rebuilt");
+        for name in [
+            "dict.da",
+            "dict.vals",
+            "dict.words",
+            "dict.wordsidx",
+            "matrix.mtx",
+            "char_def.bin",
+            "unk.bin",
+            "context_ids.bin",
+        ] {
+            assert_eq!(
+                std::fs::read(built_dir.join(name)).unwrap(),
+                std::fs::read(rebuilt_dir.join(name)).unwrap(),
+                "{} differs",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_dump_user_dictionary() {
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::dictionary_dumper::dump_user_dictionary;
+        use crate::test_utils::temp_dir;
+
+        let dir = temp_dir("dump-userdic");
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv_path = dir.join("userdic.csv");
+        std::fs::write(
+            &csv_path,
+            "東京スカイツリー,カスタム名詞,トウキョウスカイツリー\n\
+             とうきょうスカイツリー駅,1288,1288,4143,名詞,固有名詞,一般,*,*,*,とうきょうスカイツリー駅,トウキョウスカイツリーエキ,トウキョウスカイツリーエキ\n\
+             \"a,b\",カスタム名詞,エービー\n\
+             関西国際空港,関西 国際 空港,カンサイ コクサイ クウコウ,\"名詞,固有名詞\"\n",
+        )
+        .unwrap();
+
+        let builder = IpadicBuilder::new();
+        let user_dict = builder.build_user_dict(&csv_path, None).unwrap();
+        assert_eq!(user_dict.segmentations.len(), 1);
+        let dump_path = dir.join("dumped.csv");
+        dump_user_dictionary(&user_dict, &builder.user_dictionary_builder(), &dump_path).unwrap();
+        let rebuilt = builder.build_user_dict(&dump_path, None).unwrap();
+        assert!(std::fs::read_to_string(&dump_path).unwrap().contains(
+            "関西国際空港,関西 国際 空港,カンサイ コクサイ クウコウ,\"名詞,固有名詞\"\n"
+        ));
+
+        assert_eq!(
+            bincode::serialize(&user_dict).unwrap(),
+            bincode::serialize(&rebuilt).unwrap()
+        );
+        assert_eq!(
+            rebuilt.lookup("東京スカイツリー")[0].details,
+            user_dict.lookup("東京スカイツリー")[0].details
+        );
+    }
+}
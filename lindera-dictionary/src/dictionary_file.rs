@@ -0,0 +1,673 @@
+//! A compiled dictionary in a single `.linderadic` file.
+//!
+//! The file starts with a header, followed by a table of sections and the sections themselves.
+//! All integers are little-endian.
+//!
+//! ```text
+//! magic           10 bytes  "LINDERADIC"
+//! version          2 bytes  FORMAT_VERSION
+//! section count    4 bytes
+//! section table   48 bytes per section
+//!     name            16 bytes, NUL padded
+//!     compression      1 byte, followed by 3 bytes of padding
+//!     checksum         4 bytes, CRC-32 of the stored bytes
+//!     offset           8 bytes, from the start of the file
+//!     stored length    8 bytes
+//!     length           8 bytes, once decompressed
+//! table checksum   4 bytes, CRC-32 of everything before it
+//! sections, each aligned to 8 bytes
+//! ```
+//!
+//! The sections hold the files of a dictionary directory (`dict.da`, `matrix.mtx`, ...) without
+//! their compression, plus a `metadata` section holding the metadata of the dictionary as JSON.
+//! The `reading.*` sections of the reading index are only present if the dictionary has one, and
+//! the `context_ids.bin` section is only present if the dictionary was built with it.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{ByteOrder, LittleEndian};
+use yada::unit::{Unit, UNIT_SIZE};
+
+use crate::compress::compress;
+use crate::decompress::{decompress, Algorithm, CompressedData};
+use crate::dictionary::character_definition::CharacterDefinition;
+use crate::dictionary::connection_cost_matrix::ConnectionCostMatrix;
+use crate::dictionary::context_id::ContextIds;
+use crate::dictionary::metadata::Metadata;
+use crate::dictionary::prefix_dictionary::PrefixDictionary;
+use crate::dictionary::reading_index::ReadingIndex;
+use crate::dictionary::unknown_dictionary::UnknownDictionary;
+use crate::dictionary::Dictionary;
+use crate::dictionary_loader::metadata::MetadataLoader;
+use crate::error::LinderaErrorKind;
+use crate::util::{read_built_file, read_file, Data, Deferred};
+use crate::viterbi::WordEntry;
+use crate::LinderaResult;
+
+/// The extension of dictionary files.
+pub const EXTENSION: &str = "linderadic";
+
+/// The version of the file format written by this build.
+pub const FORMAT_VERSION: u16 = 1;
+
+const MAGIC: &[u8; 10] = b"LINDERADIC";
+const HEADER_LEN: usize = 16;
+const NAME_LEN: usize = 16;
+const ENTRY_LEN: usize = 48;
+const ALIGNMENT: usize = 8;
+
+const METADATA: &str = "metadata";
+const SECTIONS: [&str; 7] = [
+    "dict.da",
+    "dict.vals",
+    "dict.wordsidx",
+    "dict.words",
+    "matrix.mtx",
+    "char_def.bin",
+    "unk.bin",
+];
+// The sections of the reading index, which only dictionaries built with one have.
+const READING_INDEX_SECTIONS: [&str; 3] = ["reading.da", "reading.idx", "reading.vals"];
+// The section of the context ids, which dictionaries built before they were kept do not have.
+const CONTEXT_IDS_SECTION: &str = "context_ids.bin";
+
+#[derive(Debug, Clone)]
+struct Section {
+    name: String,
+    algorithm: Algorithm,
+    offset: usize,
+    stored_len: usize,
+    len: usize,
+}
+
+fn algorithm_code(algorithm: Algorithm) -> u8 {
+    match algorithm {
+        Algorithm::Raw => 0,
+        Algorithm::Deflate => 1,
+        Algorithm::Zlib => 2,
+        Algorithm::Gzip => 3,
+        Algorithm::Zstd => 4,
+        Algorithm::Lz4 => 5,
+    }
+}
+
+fn algorithm_from_code(code: u8) -> Option<Algorithm> {
+    match code {
+        0 => Some(Algorithm::Raw),
+        1 => Some(Algorithm::Deflate),
+        2 => Some(Algorithm::Zlib),
+        3 => Some(Algorithm::Gzip),
+        4 => Some(Algorithm::Zstd),
+        5 => Some(Algorithm::Lz4),
+        _ => None,
+    }
+}
+
+fn corrupted(message: String) -> crate::error::LinderaError {
+    LinderaErrorKind::Dictionary.with_error(anyhow::anyhow!(message))
+}
+
+/// A dictionary file whose header and checksums have been verified.
+pub struct DictionaryFile {
+    data: Data,
+    sections: Vec<Section>,
+}
+
+impl DictionaryFile {
+    /// Reads a dictionary file into memory.
+    pub fn read(path: &Path) -> LinderaResult<DictionaryFile> {
+        Self::from_data(Data::Vec(read_file(path)?))
+    }
+
+    /// Memory-maps a dictionary file. Sections that are not compressed are read in place.
+    pub fn map(path: &Path) -> LinderaResult<DictionaryFile> {
+        Self::from_data(Data::map(path)?)
+    }
+
+    pub fn from_data(data: Data) -> LinderaResult<DictionaryFile> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(corrupted(
+                "not a Lindera dictionary file: the magic bytes are missing".to_string(),
+            ));
+        }
+
+        let version = LittleEndian::read_u16(&data[10..12]);
+        if version != FORMAT_VERSION {
+            return Err(corrupted(format!(
+                "unsupported dictionary file format version {}, expected {}",
+                version, FORMAT_VERSION
+            )));
+        }
+
+        let count = LittleEndian::read_u32(&data[12..16]) as usize;
+        let table_end = count
+            .checked_mul(ENTRY_LEN)
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            .filter(|&end| end + 4 <= data.len())
+            .ok_or_else(|| corrupted("the section table is truncated".to_string()))?;
+        if crc32fast::hash(&data[..table_end]) != LittleEndian::read_u32(&data[table_end..]) {
+            return Err(corrupted(
+                "checksum mismatch in the section table: the file is corrupted".to_string(),
+            ));
+        }
+
+        let mut sections = Vec::with_capacity(count);
+        for entry in data[HEADER_LEN..table_end].chunks_exact(ENTRY_LEN) {
+            let name_len = entry[..NAME_LEN]
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(NAME_LEN);
+            let name = String::from_utf8_lossy(&entry[..name_len]).into_owned();
+            let algorithm = algorithm_from_code(entry[16]).ok_or_else(|| {
+                corrupted(format!(
+                    "unknown compression {} in section {}",
+                    entry[16], name
+                ))
+            })?;
+            let checksum = LittleEndian::read_u32(&entry[20..24]);
+            let offset = LittleEndian::read_u64(&entry[24..32]) as usize;
+            let stored_len = LittleEndian::read_u64(&entry[32..40]) as usize;
+            let len = LittleEndian::read_u64(&entry[40..48]) as usize;
+
+            let end = offset
+                .checked_add(stored_len)
+                .filter(|&end| end <= data.len())
+                .ok_or_else(|| {
+                    corrupted(format!(
+                        "section {} is out of bounds: the file is truncated",
+                        name
+                    ))
+                })?;
+            if crc32fast::hash(&data[offset..end]) != checksum {
+                return Err(corrupted(format!(
+                    "checksum mismatch in section {}: the file is corrupted",
+                    name
+                )));
+            }
+
+            sections.push(Section {
+                name,
+                algorithm,
+                offset,
+                stored_len,
+                len,
+            });
+        }
+
+        Ok(DictionaryFile { data, sections })
+    }
+
+    /// Returns the names of the sections.
+    pub fn section_names(&self) -> Vec<&str> {
+        self.sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect()
+    }
+
+    /// Returns the decompressed content of a section.
+    pub fn section(&self, name: &str) -> LinderaResult<Data> {
+        let section = self
+            .sections
+            .iter()
+            .find(|section| section.name == name)
+            .ok_or_else(|| corrupted(format!("missing section {}", name)))?;
+        let stored = self
+            .data
+            .slice(section.offset..section.offset + section.stored_len);
+
+        let data = match section.algorithm {
+            Algorithm::Raw => stored,
+            algorithm => Data::Vec(
+                decompress(CompressedData::new(algorithm, stored.to_vec())).map_err(|err| {
+                    LinderaErrorKind::Decompress.with_error(anyhow::anyhow!(
+                        "failed to decompress section {}: {}",
+                        name,
+                        err
+                    ))
+                })?,
+            ),
+        };
+        if data.len() != section.len {
+            return Err(corrupted(format!(
+                "section {} has {} bytes, expected {}",
+                name,
+                data.len(),
+                section.len
+            )));
+        }
+
+        Ok(data)
+    }
+
+    /// Returns the metadata block.
+    pub fn metadata(&self) -> LinderaResult<Metadata> {
+        Metadata::load(&self.section(METADATA)?)
+    }
+
+    /// Loads the dictionary, checking that its sections are consistent with each other so that
+    /// lookups cannot go out of bounds, even if the sections come from different builds.
+    pub fn load_dictionary(&self) -> LinderaResult<Dictionary> {
+        let connection_data = self.section("matrix.mtx")?;
+        let matrix_size = check_matrix(&connection_data)?;
+
+        let dictionary = Dictionary {
+            prefix_dictionary: PrefixDictionary::from_data(
+                self.section("dict.da")?,
+                self.section("dict.vals")?,
+                self.section("dict.wordsidx")?,
+                self.section("dict.words")?,
+            ),
+            connection_cost_matrix: ConnectionCostMatrix::from_data(connection_data),
+            character_definition: CharacterDefinition::from_data(self.section("char_def.bin")?)
+                .map_err(|err| corrupted(format!("section char_def.bin: {}", err)))?,
+            unknown_dictionary: UnknownDictionary::from_data(self.section("unk.bin")?)
+                .map_err(|err| corrupted(format!("section unk.bin: {}", err)))?,
+            metadata: Some(Deferred::new(self.section(METADATA)?, Metadata::load)),
+            reading_index: self.reading_index()?,
+            context_ids: self.context_ids()?,
+        };
+        check_prefix_dictionary(&dictionary.prefix_dictionary, matrix_size)?;
+        check_unknown_dictionary(&dictionary, matrix_size)?;
+
+        Ok(dictionary)
+    }
+
+    fn context_ids(&self) -> LinderaResult<Option<Deferred<ContextIds>>> {
+        if !self.section_names().contains(&CONTEXT_IDS_SECTION) {
+            return Ok(None);
+        }
+        Ok(Some(Deferred::new(
+            self.section(CONTEXT_IDS_SECTION)?,
+            ContextIds::load,
+        )))
+    }
+
+    fn reading_index(&self) -> LinderaResult<Option<ReadingIndex>> {
+        if !self.section_names().contains(&READING_INDEX_SECTIONS[0]) {
+            return Ok(None);
+        }
+        Ok(Some(ReadingIndex::from_data(
+            self.section(READING_INDEX_SECTIONS[0])?,
+            self.section(READING_INDEX_SECTIONS[1])?,
+            self.section(READING_INDEX_SECTIONS[2])?,
+        )))
+    }
+
+    /// Packs a dictionary directory, as written by `DictionaryBuilder::build_dictionary`, into a
+    /// dictionary file whose sections are compressed with the given algorithm.
+    pub fn write(input_dir: &Path, output_file: &Path, algorithm: Algorithm) -> LinderaResult<()> {
+        let mut sections = Vec::new();
+        for name in SECTIONS {
+            sections.push((name, read_built_file(&input_dir.join(name))?));
+        }
+        if input_dir.join(READING_INDEX_SECTIONS[0]).exists() {
+            for name in READING_INDEX_SECTIONS {
+                sections.push((name, read_built_file(&input_dir.join(name))?));
+            }
+        }
+        if input_dir.join(CONTEXT_IDS_SECTION).exists() {
+            sections.push((
+                CONTEXT_IDS_SECTION,
+                read_built_file(&input_dir.join(CONTEXT_IDS_SECTION))?,
+            ));
+        }
+        // Directories built before metadata was written only record the version packing them.
+        let metadata = match MetadataLoader::load(input_dir)? {
+            Some(metadata) => metadata,
+            None => Metadata {
+                lindera_version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Default::default()
+            },
+        };
+        let metadata = serde_json::to_vec(&metadata)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))?;
+        sections.push((METADATA, metadata));
+
+        let mut table = Vec::with_capacity(HEADER_LEN + sections.len() * ENTRY_LEN + 4);
+        table.extend_from_slice(MAGIC);
+        table.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        table.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+
+        let mut stored_sections = Vec::with_capacity(sections.len());
+        let mut offset = align(HEADER_LEN + sections.len() * ENTRY_LEN + 4);
+        for (name, data) in sections.iter() {
+            let stored = match algorithm {
+                Algorithm::Raw => data.clone(),
+                algorithm => compress(data, algorithm)
+                    .map_err(|err| LinderaErrorKind::Compress.with_error(err))?
+                    .into_data(),
+            };
+
+            let mut entry = [0u8; ENTRY_LEN];
+            entry[..name.len()].copy_from_slice(name.as_bytes());
+            entry[16] = algorithm_code(algorithm);
+            LittleEndian::write_u32(&mut entry[20..24], crc32fast::hash(&stored));
+            LittleEndian::write_u64(&mut entry[24..32], offset as u64);
+            LittleEndian::write_u64(&mut entry[32..40], stored.len() as u64);
+            LittleEndian::write_u64(&mut entry[40..48], data.len() as u64);
+            table.extend_from_slice(&entry);
+
+            offset = align(offset + stored.len());
+            stored_sections.push(stored);
+        }
+        let checksum = crc32fast::hash(&table);
+        table.extend_from_slice(&checksum.to_le_bytes());
+
+        // Write to a temporary file first, so that a failure does not leave a broken dictionary.
+        let mut tmp_file = PathBuf::from(output_file);
+        tmp_file.set_extension(format!("{}.tmp", EXTENSION));
+        let write = || -> std::io::Result<()> {
+            let mut writer = BufWriter::new(File::create(&tmp_file)?);
+            let mut written = table.len();
+            writer.write_all(&table)?;
+            for stored in stored_sections.iter() {
+                let padding = align(written) - written;
+                writer.write_all(&[0u8; ALIGNMENT][..padding])?;
+                writer.write_all(stored)?;
+                written += padding + stored.len();
+            }
+            writer.flush()?;
+            fs::rename(&tmp_file, output_file)
+        };
+        write().map_err(|err| {
+            let _ = fs::remove_file(&tmp_file);
+            LinderaErrorKind::Io.with_error(anyhow::anyhow!(err))
+        })
+    }
+}
+
+/// Returns the forward and backward sizes of a connection cost matrix, checking that it has a cost
+/// for each pair of ids.
+fn check_matrix(data: &[u8]) -> LinderaResult<(usize, usize)> {
+    if data.len() < 4 {
+        return Err(corrupted("section matrix.mtx is too short".to_string()));
+    }
+    let forward_size = LittleEndian::read_i16(&data[0..2]);
+    let backward_size = LittleEndian::read_i16(&data[2..4]);
+    if forward_size < 0 || backward_size < 0 {
+        return Err(corrupted(format!(
+            "section matrix.mtx has a negative size {}x{}",
+            forward_size, backward_size
+        )));
+    }
+    let (forward_size, backward_size) = (forward_size as usize, backward_size as usize);
+    if data.len() - 4 != 2 * forward_size * backward_size {
+        return Err(corrupted(format!(
+            "section matrix.mtx has {} bytes of costs, expected {} for a {}x{} matrix",
+            data.len() - 4,
+            2 * forward_size * backward_size,
+            forward_size,
+            backward_size
+        )));
+    }
+    Ok((forward_size, backward_size))
+}
+
+fn check_entry(
+    word_entry: &WordEntry,
+    (forward_size, backward_size): (usize, usize),
+    section: &str,
+) -> LinderaResult<()> {
+    if word_entry.right_id as usize >= forward_size || word_entry.left_id as usize >= backward_size
+    {
+        return Err(corrupted(format!(
+            "section {} has an entry with context ids ({}, {}) outside of the {}x{} matrix",
+            section, word_entry.left_id, word_entry.right_id, forward_size, backward_size
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that the trie, the entries and the details of the words refer to each other in bounds.
+fn check_prefix_dictionary(
+    prefix_dictionary: &PrefixDictionary,
+    matrix_size: (usize, usize),
+) -> LinderaResult<()> {
+    let words_idx_data = &prefix_dictionary.words_idx_data[..];
+    let words_data = &prefix_dictionary.words_data[..];
+    if !words_idx_data.len().is_multiple_of(4) {
+        return Err(corrupted(format!(
+            "section dict.wordsidx has {} bytes, which is not a multiple of 4",
+            words_idx_data.len()
+        )));
+    }
+    for idx in words_idx_data.chunks_exact(4) {
+        let idx = LittleEndian::read_u32(idx) as usize;
+        let details_len = words_data
+            .get(idx..idx + 4)
+            .map(|len| LittleEndian::read_u32(len) as usize);
+        if !matches!(details_len, Some(len) if idx + 4 + len <= words_data.len()) {
+            return Err(corrupted(format!(
+                "section dict.wordsidx refers to offset {} beyond the {} bytes of dict.words",
+                idx,
+                words_data.len()
+            )));
+        }
+    }
+    let word_count = words_idx_data.len() / 4;
+
+    let vals_data = &prefix_dictionary.vals_data[..];
+    if !vals_data.len().is_multiple_of(WordEntry::SERIALIZED_LEN) {
+        return Err(corrupted(format!(
+            "section dict.vals has {} bytes, which is not a multiple of {}",
+            vals_data.len(),
+            WordEntry::SERIALIZED_LEN
+        )));
+    }
+    for data in vals_data.chunks_exact(WordEntry::SERIALIZED_LEN) {
+        let word_entry = WordEntry::deserialize(data, true);
+        if word_entry.word_id.id as usize >= word_count {
+            return Err(corrupted(format!(
+                "section dict.vals refers to word {} beyond the {} words of dict.wordsidx",
+                word_entry.word_id.id, word_count
+            )));
+        }
+        check_entry(&word_entry, matrix_size, "dict.vals")?;
+    }
+    let entry_count = vals_data.len() / WordEntry::SERIALIZED_LEN;
+
+    // The leaves of the trie hold the offset and the number of the entries of their surface.
+    let da_data = &prefix_dictionary.da.0[..];
+    if !da_data.len().is_multiple_of(UNIT_SIZE) {
+        return Err(corrupted(format!(
+            "section dict.da has {} bytes, which is not a multiple of {}",
+            da_data.len(),
+            UNIT_SIZE
+        )));
+    }
+    for unit in da_data.chunks_exact(UNIT_SIZE) {
+        let unit = Unit::from_u32(LittleEndian::read_u32(unit));
+        if !unit.is_leaf() {
+            continue;
+        }
+        let offset_len = unit.value();
+        let end = (offset_len >> 5) as usize + (offset_len & ((1 << 5) - 1)) as usize;
+        if end > entry_count {
+            return Err(corrupted(format!(
+                "section dict.da refers to entry {} beyond the {} entries of dict.vals",
+                end, entry_count
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the character categories and the unknown words refer to each other in bounds.
+fn check_unknown_dictionary(
+    dictionary: &Dictionary,
+    matrix_size: (usize, usize),
+) -> LinderaResult<()> {
+    let character_definition = &dictionary.character_definition;
+    let unknown_dictionary = &dictionary.unknown_dictionary;
+    // Each section is checked on its own when it is loaded.
+    let category_count = character_definition.categories().len();
+    if unknown_dictionary.category_count() < category_count {
+        return Err(corrupted(format!(
+            "section unk.bin has {} categories, but char_def.bin has {}",
+            unknown_dictionary.category_count(),
+            category_count
+        )));
+    }
+    for word_id in 0..unknown_dictionary.len() {
+        check_entry(
+            &unknown_dictionary.word_entry(word_id as u32),
+            matrix_size,
+            "unk.bin",
+        )?;
+    }
+
+    Ok(())
+}
+
+fn align(offset: usize) -> usize {
+    offset.div_ceil(ALIGNMENT) * ALIGNMENT
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::decompress::Algorithm;
+    use crate::dictionary_builder::ipadic::IpadicBuilder;
+    use crate::dictionary_builder::DictionaryBuilder;
+    use crate::dictionary_file::DictionaryFile;
+    use crate::error::LinderaErrorKind;
+    use crate::test_utils::{load_dictionary, mini_ipadic_dir, temp_dir, TempDir};
+
+    fn build(algorithm: Algorithm) -> (TempDir, PathBuf, PathBuf) {
+        let dir = temp_dir("dictionary-file");
+        let output_dir = dir.join("mini-ipadic");
+        IpadicBuilder::new()
+            .build_dictionary(&mini_ipadic_dir(), &output_dir)
+            .unwrap();
+        let output_file = dir.join("mini-ipadic.linderadic");
+        DictionaryFile::write(&output_dir, &output_file, algorithm).unwrap();
+        (dir, output_dir, output_file)
+    }
+
+    #[test]
+    fn test_write_and_load() {
+        use crate::util::Data;
+
+        for algorithm in [Algorithm::Raw, Algorithm::Deflate] {
+            let (_dir, output_dir, output_file) = build(algorithm);
+            let expected = load_dictionary(&output_dir);
+
+            for file in [
+                DictionaryFile::read(&output_file).unwrap(),
+                DictionaryFile::map(&output_file).unwrap(),
+            ] {
+                let dictionary = file.load_dictionary().unwrap();
+                assert_eq!(
+                    &dictionary.prefix_dictionary.words_data[..],
+                    &expected.prefix_dictionary.words_data[..]
+                );
+                assert_eq!(
+                    dictionary.prefix_dictionary.find_surface("東京"),
+                    expected.prefix_dictionary.find_surface("東京")
+                );
+                assert_eq!(
+                    &dictionary.connection_cost_matrix.costs_data[..],
+                    &expected.connection_cost_matrix.costs_data[..]
+                );
+                assert_eq!(
+                    dictionary.character_definition.categories(),
+                    expected.character_definition.categories()
+                );
+                assert_eq!(dictionary.metadata().unwrap(), expected.metadata().unwrap());
+                assert_eq!(dictionary.metadata().unwrap().unwrap().name, "ipadic");
+                assert!(dictionary.context_ids().unwrap().is_some());
+                assert_eq!(
+                    dictionary.context_ids().unwrap(),
+                    expected.context_ids().unwrap()
+                );
+            }
+
+            // Uncompressed sections of a mapped file are read in place.
+            let file = DictionaryFile::map(&output_file).unwrap();
+            let words_data = file.section("dict.words").unwrap();
+            assert_eq!(
+                matches!(words_data, Data::Map(..)),
+                matches!(algorithm, Algorithm::Raw)
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_invalid_file() {
+        use byteorder::{ByteOrder, LittleEndian};
+
+        let (_dir, output_dir, output_file) = build(Algorithm::Raw);
+        let bytes = std::fs::read(&output_file).unwrap();
+        let error = |bytes: Vec<u8>| {
+            let path = output_file.with_extension("broken");
+            std::fs::write(&path, bytes).unwrap();
+            let err = DictionaryFile::read(&path).err().unwrap();
+            assert_eq!(err.kind(), LinderaErrorKind::Dictionary);
+            err.to_string()
+        };
+
+        // The files of a dictionary directory are not dictionary files.
+        assert!(error(std::fs::read(output_dir.join("dict.da")).unwrap())
+            .contains("not a Lindera dictionary file"));
+
+        let mut newer = bytes.clone();
+        LittleEndian::write_u16(&mut newer[10..12], 2);
+        assert!(error(newer).contains("unsupported dictionary file format version 2"));
+
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0xff;
+        assert!(error(corrupted).contains("checksum mismatch in section metadata"));
+
+        let mut corrupted_table = bytes.clone();
+        corrupted_table[20] ^= 0xff;
+        assert!(error(corrupted_table).contains("checksum mismatch in the section table"));
+
+        assert!(error(bytes[..bytes.len() / 2].to_vec()).contains("truncated"));
+        assert!(error(bytes[..20].to_vec()).contains("truncated"));
+    }
+
+    #[test]
+    fn test_load_mismatched_sections() {
+        use crate::util::read_built_file;
+
+        let (_dir, output_dir, _) = build(Algorithm::Raw);
+        let error = |name: &str, data: &[u8]| {
+            let dir = temp_dir("dictionary-file-mismatched");
+            std::fs::create_dir_all(&dir).unwrap();
+            for entry in std::fs::read_dir(&output_dir).unwrap() {
+                let path = entry.unwrap().path();
+                std::fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+            }
+            std::fs::write(dir.join(name), data).unwrap();
+            let output_file = dir.join("mismatched.linderadic");
+            DictionaryFile::write(&dir, &output_file, Algorithm::Raw).unwrap();
+
+            // The checksums are valid, so only loading the dictionary finds the mismatch.
+            let file = DictionaryFile::read(&output_file).unwrap();
+            let err = file.load_dictionary().err().unwrap();
+            assert_eq!(err.kind(), LinderaErrorKind::Dictionary);
+            err.to_string()
+        };
+
+        // A 2x2 matrix, smaller than the one the entries refer to.
+        let matrix = [2u8, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(error("matrix.mtx", &matrix).contains("outside of the 2x2 matrix"));
+        assert!(error("matrix.mtx", &matrix[..10]).contains("expected 8 for a 2x2 matrix"));
+
+        let words = read_built_file(&output_dir.join("dict.words")).unwrap();
+        assert!(error("dict.words", &words[..words.len() / 2]).contains("beyond the"));
+        let vals = read_built_file(&output_dir.join("dict.vals")).unwrap();
+        assert!(error("dict.vals", &vals[..vals.len() / 20 * 10])
+            .contains("section dict.da refers to entry"));
+        let char_def = read_built_file(&output_dir.join("char_def.bin")).unwrap();
+        assert!(
+            error("char_def.bin", &char_def[..char_def.len() - 4]).contains("section char_def.bin")
+        );
+    }
+}
@@ -1,6 +1,9 @@
 pub mod character_definition;
 pub mod connection_cost_matrix;
+pub mod context_id;
+pub mod metadata;
 pub mod prefix_dictionary;
+pub mod reading_index;
 pub mod unknown_dictionary;
 
 use std::path::Path;
@@ -12,3 +15,77 @@ pub trait DictionaryLoader {
     fn load_dictionary(&self, input_dir: &Path) -> LinderaResult<Dictionary>;
     fn load_user_dictionary(&self, input_file: &Path) -> LinderaResult<UserDictionary>;
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_load_compressed_and_raw() {
+        use crate::decompress::Algorithm;
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::dictionary_loader::connection_cost_matrix::ConnectionCostMatrixLoader;
+        use crate::dictionary_loader::prefix_dictionary::PrefixDictionaryLoader;
+        use crate::test_utils::{load_dictionary, mini_ipadic_dir, temp_dir};
+        use crate::util::Data;
+
+        let build = |algorithm: Algorithm| {
+            let output_dir = temp_dir(&format!("load-{}", algorithm));
+            let mut builder = IpadicBuilder::new();
+            builder.set_compress_algorithm(algorithm);
+            builder
+                .build_dictionary(&mini_ipadic_dir(), &output_dir)
+                .unwrap();
+            output_dir
+        };
+        let raw_dir = build(Algorithm::Raw);
+        let raw = load_dictionary(&raw_dir);
+        assert_eq!(
+            raw.metadata().unwrap().unwrap().build_options["compress_algorithm"],
+            "raw"
+        );
+
+        #[allow(unused_mut)]
+        let mut algorithms = vec![Algorithm::Deflate, Algorithm::Zlib, Algorithm::Gzip];
+        #[cfg(feature = "zstd")]
+        algorithms.push(Algorithm::Zstd);
+        #[cfg(feature = "lz4")]
+        algorithms.push(Algorithm::Lz4);
+        for algorithm in algorithms {
+            let output_dir = build(algorithm);
+            let compressed = load_dictionary(&output_dir);
+            assert_ne!(
+                std::fs::read(output_dir.join("dict.words")).unwrap(),
+                std::fs::read(raw_dir.join("dict.words")).unwrap()
+            );
+            assert_eq!(
+                &compressed.prefix_dictionary.words_data[..],
+                &raw.prefix_dictionary.words_data[..]
+            );
+            assert_eq!(
+                compressed.prefix_dictionary.find_surface("東京"),
+                raw.prefix_dictionary.find_surface("東京")
+            );
+            assert_eq!(
+                &compressed.connection_cost_matrix.costs_data[..],
+                &raw.connection_cost_matrix.costs_data[..]
+            );
+            assert_eq!(
+                &compressed.unknown_dictionary.data[..],
+                &raw.unknown_dictionary.data[..]
+            );
+
+            // Compressed files are decompressed into memory when they are mapped.
+            let mapped = PrefixDictionaryLoader::load_mmap(&output_dir).unwrap();
+            assert!(matches!(mapped.words_data, Data::Vec(_)));
+            assert_eq!(
+                &mapped.words_data[..],
+                &raw.prefix_dictionary.words_data[..]
+            );
+        }
+
+        let mapped = PrefixDictionaryLoader::load_mmap(&raw_dir).unwrap();
+        assert!(matches!(mapped.words_data, Data::Map(..)));
+        let mapped = ConnectionCostMatrixLoader::load_mmap(&raw_dir).unwrap();
+        assert!(matches!(mapped.costs_data, Data::Map(..)));
+    }
+}
@@ -1,28 +1,23 @@
 use std::path::Path;
 
-#[cfg(feature = "compress")]
-use crate::decompress::decompress;
 use crate::dictionary::connection_cost_matrix::ConnectionCostMatrix;
-#[cfg(feature = "compress")]
-use crate::error::LinderaErrorKind;
-use crate::util::read_file;
+use crate::util::{decompress_data, read_built_file, Data};
 use crate::LinderaResult;
 
 pub struct ConnectionCostMatrixLoader {}
 
 impl ConnectionCostMatrixLoader {
-    #[allow(unused_mut)]
     pub fn load(input_dir: &Path) -> LinderaResult<ConnectionCostMatrix> {
-        let mut data = read_file(input_dir.join("matrix.mtx").as_path())?;
-
-        #[cfg(feature = "compress")]
-        {
-            let compressed_data = bincode::deserialize_from(data.as_slice())
-                .map_err(|err| LinderaErrorKind::Deserialize.with_error(err))?;
-            data = decompress(compressed_data)
-                .map_err(|err| LinderaErrorKind::Decompress.with_error(err))?;
-        }
+        let data = read_built_file(input_dir.join("matrix.mtx").as_path())?;
 
         Ok(ConnectionCostMatrix::load(data.as_slice()))
     }
+    /// Loads the matrix by memory-mapping its file, so that it reads the costs in place.
+    ///
+    /// A compressed file can not be read in place, so it is decompressed into memory.
+    pub fn load_mmap(input_dir: &Path) -> LinderaResult<ConnectionCostMatrix> {
+        Ok(ConnectionCostMatrix::from_data(decompress_data(
+            Data::map(input_dir.join("matrix.mtx").as_path())?,
+        )?))
+    }
 }
@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use crate::dictionary::context_id::ContextIds;
+use crate::util::{read_built_file, Deferred};
+use crate::LinderaResult;
+
+pub struct ContextIdsLoader {}
+
+impl ContextIdsLoader {
+    /// Loads the context ids of a dictionary directory, if it was built with them. They are
+    /// deserialized the first time they are used.
+    pub fn load(input_dir: &Path) -> LinderaResult<Option<Deferred<ContextIds>>> {
+        let path = input_dir.join("context_ids.bin");
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = read_built_file(path.as_path())?;
+        Ok(Some(Deferred::new(data.into(), ContextIds::load)))
+    }
+}
@@ -0,0 +1,19 @@
+use std::path::Path;
+
+use crate::dictionary::metadata::Metadata;
+use crate::util::read_file;
+use crate::LinderaResult;
+
+pub struct MetadataLoader {}
+
+impl MetadataLoader {
+    /// Loads the metadata of a dictionary, if it was built with some.
+    pub fn load(input_dir: &Path) -> LinderaResult<Option<Metadata>> {
+        let path = input_dir.join("metadata.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Metadata::load(&read_file(&path)?).map(Some)
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use crate::dictionary::reading_index::ReadingIndex;
+use crate::util::{decompress_data, read_built_file, Data};
+use crate::LinderaResult;
+
+pub struct ReadingIndexLoader {}
+
+impl ReadingIndexLoader {
+    /// Loads the reading index of a dictionary directory, if it was built with one.
+    pub fn load(input_dir: &Path) -> LinderaResult<Option<ReadingIndex>> {
+        if !input_dir.join("reading.da").exists() {
+            return Ok(None);
+        }
+
+        let da_data = read_built_file(input_dir.join("reading.da").as_path())?;
+        let offsets_data = read_built_file(input_dir.join("reading.idx").as_path())?;
+        let entries_data = read_built_file(input_dir.join("reading.vals").as_path())?;
+
+        Ok(Some(ReadingIndex::load(
+            da_data.as_slice(),
+            offsets_data.as_slice(),
+            entries_data.as_slice(),
+        )))
+    }
+
+    /// Loads the reading index by memory-mapping its files, so that it reads them in place.
+    pub fn load_mmap(input_dir: &Path) -> LinderaResult<Option<ReadingIndex>> {
+        if !input_dir.join("reading.da").exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(ReadingIndex::from_data(
+            decompress_data(Data::map(input_dir.join("reading.da").as_path())?)?,
+            decompress_data(Data::map(input_dir.join("reading.idx").as_path())?)?,
+            decompress_data(Data::map(input_dir.join("reading.vals").as_path())?)?,
+        )))
+    }
+}
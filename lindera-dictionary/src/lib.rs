@@ -3,9 +3,14 @@ pub mod compress;
 pub mod decompress;
 pub mod dictionary;
 pub mod dictionary_builder;
+pub mod dictionary_dumper;
+pub mod dictionary_file;
 pub mod dictionary_loader;
 pub mod error;
 pub mod mode;
+#[cfg(any(test, feature = "test-utils"))]
+#[doc(hidden)]
+pub mod test_utils;
 pub mod util;
 pub mod viterbi;
 
@@ -1,16 +1,40 @@
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
+use crate::dictionary::character_definition::CharacterDefinition;
 use crate::error::{LinderaError, LinderaErrorKind};
 use crate::viterbi::Edge;
 
+/// The penalty for words longer than a threshold, in characters.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CategoryPenalty {
+    pub length_threshold: usize,
+    pub length_penalty: i32,
+}
+
+impl CategoryPenalty {
+    pub fn penalty(&self, num_chars: usize) -> i32 {
+        if num_chars > self.length_threshold {
+            ((num_chars - self.length_threshold) as i32) * self.length_penalty
+        } else {
+            0
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Penalty {
     pub kanji_penalty_length_threshold: usize,
     pub kanji_penalty_length_penalty: i32,
     pub other_penalty_length_threshold: usize,
     pub other_penalty_length_penalty: i32,
+    /// Penalties keyed by `char.def` category name (e.g. `KANJI`, `KATAKANA`, `HANGUL`). They
+    /// apply to words whose characters all belong to the category, and take precedence over the
+    /// kanji and other penalties.
+    #[serde(default)]
+    pub category_penalties: BTreeMap<String, CategoryPenalty>,
 }
 
 impl Default for Penalty {
@@ -20,6 +44,7 @@ impl Default for Penalty {
             kanji_penalty_length_penalty: 3000,
             other_penalty_length_threshold: 7,
             other_penalty_length_penalty: 1700,
+            category_penalties: BTreeMap::new(),
         }
     }
 }
@@ -40,6 +65,22 @@ impl Penalty {
             0
         }
     }
+
+    /// Computes the penalty of an edge like [`Penalty::penalty`], unless all its characters
+    /// belong to a `char.def` category with a penalty in `category_penalties`.
+    pub fn penalty_with_categories(
+        &self,
+        edge: &Edge,
+        char_definitions: &CharacterDefinition,
+    ) -> i32 {
+        match edge.category.and_then(|category| {
+            self.category_penalties
+                .get(char_definitions.category_name(category))
+        }) {
+            Some(category_penalty) => category_penalty.penalty(edge.num_chars()),
+            None => self.penalty(edge),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -58,10 +99,12 @@ impl Mode {
         }
     }
 
+    /// Returns the penalty for connecting `edge` to the next edge. In decompose mode, the penalty
+    /// is computed by `Lattice::set_text`, which must have been called with the same mode.
     pub fn penalty_cost(&self, edge: &Edge) -> i32 {
         match self {
             Mode::Normal => 0i32,
-            Mode::Decompose(penalty) => penalty.penalty(edge),
+            Mode::Decompose(_penalty) => edge.penalty_cost,
         }
     }
 
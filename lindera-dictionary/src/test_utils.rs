@@ -0,0 +1,93 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::dictionary::Dictionary;
+use crate::dictionary_builder::ipadic::IpadicBuilder;
+use crate::dictionary_builder::DictionaryBuilder;
+use crate::dictionary_loader::character_definition::CharacterDefinitionLoader;
+use crate::dictionary_loader::connection_cost_matrix::ConnectionCostMatrixLoader;
+use crate::dictionary_loader::context_id::ContextIdsLoader;
+use crate::dictionary_loader::metadata::MetadataLoader;
+use crate::dictionary_loader::prefix_dictionary::PrefixDictionaryLoader;
+use crate::dictionary_loader::reading_index::ReadingIndexLoader;
+use crate::dictionary_loader::unknown_dictionary::UnknownDictionaryLoader;
+use crate::util::Deferred;
+
+static TEMP_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A path under the system temporary directory that is removed when dropped, with everything in
+/// it if it is a directory.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = if self.0.is_dir() {
+            std::fs::remove_dir_all(&self.0)
+        } else {
+            std::fs::remove_file(&self.0)
+        };
+    }
+}
+
+/// Returns a fresh path under the system temporary directory, for a directory or a file. Nothing
+/// is created there, and whatever the test puts there is removed when the returned guard is
+/// dropped.
+pub fn temp_dir(name: &str) -> TempDir {
+    let dir = std::env::temp_dir().join(format!(
+        "lindera-test-{}-{}-{}",
+        std::process::id(),
+        TEMP_DIR_COUNTER.fetch_add(1, Ordering::SeqCst),
+        name
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    TempDir(dir)
+}
+
+/// Source directory of the miniature IPADIC used by unit tests.
+pub fn mini_ipadic_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../resources")
+        .join("mini-ipadic")
+}
+
+pub fn load_dictionary(dir: &Path) -> Dictionary {
+    Dictionary {
+        prefix_dictionary: PrefixDictionaryLoader::load(dir).unwrap(),
+        connection_cost_matrix: ConnectionCostMatrixLoader::load(dir).unwrap(),
+        character_definition: CharacterDefinitionLoader::load(dir).unwrap(),
+        unknown_dictionary: UnknownDictionaryLoader::load(dir).unwrap(),
+        metadata: MetadataLoader::load(dir).unwrap().map(Deferred::from_value),
+        reading_index: ReadingIndexLoader::load(dir).unwrap(),
+        context_ids: ContextIdsLoader::load(dir).unwrap(),
+    }
+}
+
+/// Builds the miniature IPADIC and returns the directory it was built in.
+pub fn mini_ipadic_path() -> TempDir {
+    let output_dir = temp_dir("mini-ipadic");
+    IpadicBuilder::new()
+        .build_dictionary(&mini_ipadic_dir(), &output_dir)
+        .unwrap();
+    output_dir
+}
+
+/// Builds the miniature IPADIC and loads it.
+pub fn mini_ipadic() -> Dictionary {
+    load_dictionary(&mini_ipadic_path())
+}
@@ -1,40 +1,317 @@
+use std::fmt;
 use std::fs::File;
 use std::io::{Read, Write};
+use std::ops::{Deref, Range};
 use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::anyhow;
+use byteorder::{ByteOrder, LittleEndian};
 use encoding_rs::Encoding;
+use memmap2::Mmap;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[cfg(feature = "compress")]
 use crate::compress::compress;
-use crate::decompress::Algorithm;
+use crate::decompress::{decompress, Algorithm, CompressedData};
 use crate::error::LinderaErrorKind;
 use crate::LinderaResult;
 
-#[cfg(feature = "compress")]
+/// Writes a dictionary file, as raw bytes with [`Algorithm::Raw`] or as
+/// [`COMPRESSED_MAGIC`](crate::decompress::COMPRESSED_MAGIC) followed by a bincode-serialized
+/// [`CompressedData`] otherwise.
 pub fn compress_write<W: Write>(
     buffer: &[u8],
     algorithm: Algorithm,
     writer: &mut W,
 ) -> LinderaResult<()> {
+    if algorithm == Algorithm::Raw {
+        return writer
+            .write_all(buffer)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)));
+    }
+
     let compressed = compress(buffer, algorithm)
         .map_err(|err| LinderaErrorKind::Compress.with_error(anyhow::anyhow!(err)))?;
-    bincode::serialize_into(writer, &compressed)
+    let bytes = compressed
+        .to_bytes()
+        .map_err(|err| LinderaErrorKind::Serialize.with_error(err))?;
+    writer
+        .write_all(&bytes)
         .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
 
     Ok(())
 }
 
-#[cfg(not(feature = "compress"))]
-pub fn compress_write<W: Write>(
-    buffer: &[u8],
-    _algorithm: Algorithm,
-    writer: &mut W,
-) -> LinderaResult<()> {
-    writer
-        .write_all(buffer)
-        .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+/// Returns the decompressed contents of data written by [`compress_write`], or `None` if the data
+/// is raw.
+fn decompress_envelope(data: &[u8]) -> LinderaResult<Option<Vec<u8>>> {
+    CompressedData::from_bytes(data)?
+        .map(|compressed_data| {
+            decompress(compressed_data).map_err(|err| LinderaErrorKind::Decompress.with_error(err))
+        })
+        .transpose()
+}
+
+/// Returns the contents of data written by [`compress_write`], whether it is compressed or not.
+/// Raw data is returned as it is, without being copied.
+pub fn decompress_data(data: Data) -> LinderaResult<Data> {
+    Ok(match decompress_envelope(&data)? {
+        Some(decompressed) => Data::Vec(decompressed),
+        None => data,
+    })
+}
+
+/// Returns the contents of bytes written by [`compress_write`], such as the files of a dictionary
+/// embedded in the binary, decompressing them if they have been compressed.
+pub fn decompress_bytes(data: &[u8]) -> LinderaResult<Vec<u8>> {
+    Ok(decompress_envelope(data)?.unwrap_or_else(|| data.to_vec()))
+}
+
+/// Reads a file of a dictionary directory, decompressing it if it has been compressed.
+pub fn read_built_file(path: &Path) -> LinderaResult<Vec<u8>> {
+    let data = read_file(path)?;
+    Ok(decompress_envelope(&data)?.unwrap_or(data))
+}
+
+/// Bytes of dictionary data, either owned, embedded in the binary or memory-mapped from a file.
+///
+/// It serializes like a `Vec<u8>`, and always deserializes into owned bytes.
+#[derive(Clone)]
+pub enum Data {
+    Static(&'static [u8]),
+    Vec(Vec<u8>),
+    /// A range of a mapped file.
+    Map(Arc<Mmap>, Range<usize>),
+}
+
+impl Data {
+    /// Maps a file into memory.
+    pub fn map(filename: &Path) -> LinderaResult<Data> {
+        let file = File::open(filename)
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        // SAFETY: Dictionary files are not expected to be modified while they are in use. If one
+        // is, the dictionary may read inconsistent data, as it would with a partially written file.
+        let mmap = unsafe { Mmap::map(&file) }
+            .map_err(|err| LinderaErrorKind::Io.with_error(anyhow::anyhow!(err)))?;
+        let len = mmap.len();
+        Ok(Data::Map(Arc::new(mmap), 0..len))
+    }
+
+    /// Returns the data without its first `offset` bytes.
+    pub fn skip(self, offset: usize) -> Data {
+        match self {
+            Data::Static(data) => Data::Static(&data[offset..]),
+            Data::Vec(mut data) => {
+                data.drain(..offset);
+                Data::Vec(data)
+            }
+            Data::Map(mmap, range) => {
+                assert!(offset <= range.len());
+                Data::Map(mmap, range.start + offset..range.end)
+            }
+        }
+    }
+
+    /// Returns a part of the data. Owned data is copied, while borrowed and mapped data is not.
+    pub fn slice(&self, range: Range<usize>) -> Data {
+        match self {
+            Data::Static(data) => Data::Static(&data[range]),
+            Data::Vec(data) => Data::Vec(data[range].to_vec()),
+            Data::Map(mmap, mapped) => {
+                assert!(range.start <= range.end && range.end <= mapped.len());
+                Data::Map(
+                    Arc::clone(mmap),
+                    mapped.start + range.start..mapped.start + range.end,
+                )
+            }
+        }
+    }
+}
+
+impl Default for Data {
+    fn default() -> Self {
+        Data::Static(&[])
+    }
+}
+
+impl Deref for Data {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Data::Static(data) => data,
+            Data::Vec(data) => data,
+            Data::Map(mmap, range) => &mmap[range.clone()],
+        }
+    }
+}
+
+impl AsRef<[u8]> for Data {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Vec<u8>> for Data {
+    fn from(data: Vec<u8>) -> Self {
+        Data::Vec(data)
+    }
+}
+
+impl From<&'static [u8]> for Data {
+    fn from(data: &'static [u8]) -> Self {
+        Data::Static(data)
+    }
+}
+
+impl fmt::Debug for Data {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Data::Static(_) => "Static",
+            Data::Vec(_) => "Vec",
+            Data::Map(..) => "Map",
+        };
+        write!(f, "Data::{}({} bytes)", kind, self.len())
+    }
+}
+
+impl Serialize for Data {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Data {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<u8>::deserialize(deserializer).map(Data::Vec)
+    }
+}
+
+/// A part of a dictionary that is deserialized the first time it is used instead of when the
+/// dictionary is loaded, for the parts that tokenizing does not need.
+///
+/// It serializes like the deserialized value.
+pub struct Deferred<T> {
+    data: Data,
+    load: fn(&[u8]) -> LinderaResult<T>,
+    value: OnceCell<T>,
+}
+
+impl<T> Deferred<T> {
+    /// Keeps the data, to deserialize it with `load` the first time it is used.
+    pub fn new(data: Data, load: fn(&[u8]) -> LinderaResult<T>) -> Self {
+        Deferred {
+            data,
+            load,
+            value: OnceCell::new(),
+        }
+    }
+
+    /// Wraps a value that has already been deserialized.
+    pub fn from_value(value: T) -> Self {
+        Deferred {
+            data: Data::default(),
+            load: |_| {
+                Err(LinderaErrorKind::Deserialize
+                    .with_error(anyhow!("the value has already been deserialized")))
+            },
+            value: OnceCell::with_value(value),
+        }
+    }
+
+    /// Returns the value, deserializing it if it is the first time.
+    pub fn get(&self) -> LinderaResult<&T> {
+        self.value.get_or_try_init(|| (self.load)(&self.data))
+    }
+}
+
+impl<T: Clone> Clone for Deferred<T> {
+    fn clone(&self) -> Self {
+        Deferred {
+            data: self.data.clone(),
+            load: self.load,
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Deferred<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Deferred<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Deferred::from_value)
+    }
+}
+
+/// Writes sections of bytes after a magic number and the offsets of the sections, so that they
+/// can be read in place with [`section`].
+pub(crate) fn write_sections(magic: &[u8; 4], sections: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = magic.to_vec();
+    let mut offset = magic.len() + 4 * (sections.len() + 1);
+    for section in sections.iter() {
+        data.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += section.len();
+    }
+    data.extend_from_slice(&(offset as u32).to_le_bytes());
+    for section in sections.iter() {
+        data.extend_from_slice(section);
+    }
+    data
+}
+
+/// Returns a section of data written by [`write_sections`].
+pub(crate) fn section(data: &[u8], index: usize) -> &[u8] {
+    &data[offset_range(&data[4..], index)]
+}
+
+/// Checks that data has the magic number and the sections written by [`write_sections`].
+pub(crate) fn check_sections(data: &[u8], magic: &[u8; 4], count: usize) -> Result<(), String> {
+    if !data.starts_with(magic) {
+        return Err("unexpected magic number".to_string());
+    }
+    let offsets_end = magic.len() + 4 * (count + 1);
+    if data.len() < offsets_end {
+        return Err("truncated section offsets".to_string());
+    }
+    check_offsets(&data[4..offsets_end], count, data.len())?;
+    if offset_range(&data[4..], 0).start != offsets_end {
+        return Err("unexpected first section offset".to_string());
+    }
+    Ok(())
+}
+
+/// Returns the range between the `index`th and the next of little-endian `u32` offsets.
+pub(crate) fn offset_range(offsets: &[u8], index: usize) -> Range<usize> {
+    let start = LittleEndian::read_u32(&offsets[4 * index..]) as usize;
+    let end = LittleEndian::read_u32(&offsets[4 * (index + 1)..]) as usize;
+    start..end
+}
 
+/// Checks that `offsets` holds the `count + 1` little-endian `u32` offsets of `count` ranges that
+/// follow each other without going beyond `end`.
+pub(crate) fn check_offsets(offsets: &[u8], count: usize, end: usize) -> Result<(), String> {
+    if offsets.len() != 4 * (count + 1) {
+        return Err(format!(
+            "expected {} offsets, got {} bytes",
+            count + 1,
+            offsets.len()
+        ));
+    }
+    let mut previous = 0;
+    for offset in offsets.chunks_exact(4).map(LittleEndian::read_u32) {
+        let offset = offset as usize;
+        if offset < previous || offset > end {
+            return Err(format!("offset {} out of order or beyond {}", offset, end));
+        }
+        previous = offset;
+    }
     Ok(())
 }
 
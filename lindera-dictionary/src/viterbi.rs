@@ -1,14 +1,22 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Write as _;
 use std::io;
 
 use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::dictionary::character_definition::{CategoryId, CharacterDefinition};
 use crate::dictionary::connection_cost_matrix::ConnectionCostMatrix;
 use crate::dictionary::prefix_dictionary::PrefixDictionary;
 use crate::dictionary::unknown_dictionary::UnknownDictionary;
+use crate::error::LinderaErrorKind;
 use crate::mode::Mode;
+use crate::LinderaResult;
 
+const BOS_NODE: EdgeId = EdgeId(0u32);
 const EOS_NODE: EdgeId = EdgeId(1u32);
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -17,9 +25,71 @@ pub struct WordId {
     pub is_system: bool,
 }
 
+// The bit set in the ids of unknown words. The other bits are the id of the entry of the unknown
+// dictionary, or all set if the word has no entry.
+const UNKNOWN_WORD_ID_FLAG: u32 = 1 << 31;
+
+// The bit set in the ids of the words of the user dictionaries layered after the first one. The
+// ids of the words of the first user dictionary are the ids of their entries, as when there is a
+// single user dictionary.
+const LAYERED_USER_WORD_ID_FLAG: u32 = 1 << 31;
+
+// The bits of the ids of the words of a layered user dictionary that hold its index among the
+// layered ones. The lower bits are the id of the entry in its dictionary.
+const USER_DICTIONARY_SHIFT: u32 = 24;
+
+/// The maximum number of user dictionaries layered in a lattice.
+pub const MAX_USER_DICTIONARIES: usize = 1 + (1 << (31 - USER_DICTIONARY_SHIFT));
+
+/// The maximum number of entries of each user dictionary layered after the first one, whose words
+/// keep the ids of their entries.
+pub const MAX_USER_DICTIONARY_ENTRIES: usize = 1 << USER_DICTIONARY_SHIFT;
+
 impl WordId {
+    /// Returns the id of an unknown word of the given entry of the unknown dictionary.
+    pub fn unknown(entry_id: u32) -> Self {
+        WordId {
+            id: UNKNOWN_WORD_ID_FLAG | entry_id,
+            is_system: true,
+        }
+    }
+    /// Returns the id of a word of the user dictionary at `dictionary_index` among the layered
+    /// ones, given the id of its entry in that dictionary.
+    pub fn user(dictionary_index: usize, entry_id: u32) -> Self {
+        let id = match dictionary_index {
+            0 => entry_id,
+            _ => {
+                LAYERED_USER_WORD_ID_FLAG
+                    | (((dictionary_index - 1) as u32) << USER_DICTIONARY_SHIFT)
+                    | entry_id
+            }
+        };
+        WordId {
+            id,
+            is_system: false,
+        }
+    }
+    /// Returns the index of the user dictionary of a user dictionary word and the id of its entry
+    /// in that dictionary.
+    pub fn user_entry(&self) -> Option<(usize, u32)> {
+        if self.is_system {
+            None
+        } else if self.id & LAYERED_USER_WORD_ID_FLAG == 0 {
+            Some((0, self.id))
+        } else {
+            let id = self.id & !LAYERED_USER_WORD_ID_FLAG;
+            Some((
+                (id >> USER_DICTIONARY_SHIFT) as usize + 1,
+                id & ((1 << USER_DICTIONARY_SHIFT) - 1),
+            ))
+        }
+    }
     pub fn is_unknown(&self) -> bool {
-        self.id == u32::MAX
+        self.is_system && self.id & UNKNOWN_WORD_ID_FLAG != 0
+    }
+    /// Returns the id of the entry of the unknown dictionary of an unknown word, if any.
+    pub fn unknown_entry_id(&self) -> Option<u32> {
+        (self.is_unknown() && self.id != u32::MAX).then_some(self.id & !UNKNOWN_WORD_ID_FLAG)
     }
     pub fn is_system(&self) -> bool {
         self.is_system
@@ -79,7 +149,15 @@ impl WordEntry {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+/// A user dictionary consulted by `Lattice::set_text`, along with the system dictionary.
+#[derive(Clone, Copy)]
+pub struct UserDictionaryLayer<'a> {
+    pub dict: &'a PrefixDictionary,
+    /// The bias added to the costs of the words of the dictionary.
+    pub cost_bias: i16,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
 pub enum EdgeType {
     #[default]
     KNOWN,
@@ -102,13 +180,143 @@ pub struct Edge {
     pub start_index: u32,
     pub stop_index: u32,
 
+    /// Index of the first character of the edge, counted in extended grapheme clusters.
+    pub start_char: u32,
+    /// Index of the character following the edge, counted in extended grapheme clusters.
+    pub stop_char: u32,
+
     pub kanji_only: bool,
+
+    /// The `char.def` category shared by all the characters of the edge, if any. Only set in
+    /// decompose mode.
+    pub category: Option<CategoryId>,
+
+    /// The penalty for connecting this edge to the next one. Only set in decompose mode.
+    pub penalty_cost: i32,
 }
 
 impl Edge {
-    // TODO fix em
+    /// Returns the length of the edge in characters, where a character is an extended grapheme
+    /// cluster (so that a Hangul syllable written with conjoining jamo or an emoji sequence
+    /// counts as one character). An edge that starts inside a cluster, such as an unknown word
+    /// made of the trailing jamo of a syllable, still counts as one character.
     pub fn num_chars(&self) -> usize {
-        (self.stop_index - self.start_index) as usize / 3
+        let num_chars = (self.stop_char - self.start_char) as usize;
+        if self.stop_index > self.start_index {
+            num_chars.max(1)
+        } else {
+            num_chars
+        }
+    }
+}
+
+/// A span of text that must be a single token.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatticeSpan {
+    /// Byte offset of the start of the span.
+    pub start: usize,
+    /// Byte offset of the end of the span.
+    pub end: usize,
+    /// If set, only these words may be used for the span.
+    pub word_ids: Option<Vec<WordId>>,
+}
+
+/// Constraints on the edges of a lattice, given as byte offsets in the text.
+///
+/// Spans must not overlap each other, forced boundaries must not be inside a span, and forbidden
+/// boundaries must not be at the edge of a span or be forced. Boundaries at the start and the end
+/// of the text are always allowed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatticeConstraints {
+    /// Spans that must be a single token.
+    pub spans: Vec<LatticeSpan>,
+    /// Positions where a token must start or end.
+    pub forced_boundaries: Vec<usize>,
+    /// Positions where a token must not start or end.
+    pub forbidden_boundaries: Vec<usize>,
+}
+
+impl LatticeConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+            && self.forced_boundaries.is_empty()
+            && self.forbidden_boundaries.is_empty()
+    }
+
+    /// Returns whether an edge from `start` to `stop` for the word `word_id` (`None` for an
+    /// unknown word) is allowed in a text of `text_len` bytes.
+    pub fn allows(
+        &self,
+        start: usize,
+        stop: usize,
+        text_len: usize,
+        word_id: Option<WordId>,
+    ) -> bool {
+        if self
+            .forced_boundaries
+            .iter()
+            .any(|&boundary| start < boundary && boundary < stop)
+        {
+            return false;
+        }
+        if self.forbidden_boundaries.iter().any(|&boundary| {
+            0 < boundary && boundary < text_len && (boundary == start || boundary == stop)
+        }) {
+            return false;
+        }
+        self.spans.iter().all(|span| {
+            if span.start == start && span.end == stop {
+                match (&span.word_ids, word_id) {
+                    (Some(word_ids), Some(word_id)) => word_ids.contains(&word_id),
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                }
+            } else {
+                stop <= span.start || span.end <= start
+            }
+        })
+    }
+
+    fn span_starting_at(&self, start: usize) -> Option<&LatticeSpan> {
+        self.spans.iter().find(|span| span.start == start)
+    }
+
+    /// Adjusts the byte length of an unknown word starting at `start` so that it satisfies the
+    /// constraints, or returns 0 if it cannot.
+    fn fit_unknown_word(
+        &self,
+        start: usize,
+        word_len: usize,
+        suffix: &str,
+        text_len: usize,
+    ) -> usize {
+        // A span starting here is covered by its own edges.
+        if self.span_starting_at(start).is_some() {
+            return 0;
+        }
+
+        // Cut the word short before the next forced boundary or span.
+        let limit = self
+            .forced_boundaries
+            .iter()
+            .copied()
+            .chain(self.spans.iter().map(|span| span.start))
+            .filter(|&boundary| boundary > start)
+            .min()
+            .map_or(suffix.len(), |boundary| boundary - start);
+        let mut word_len = word_len.min(limit);
+        while word_len > 0 && !suffix.is_char_boundary(word_len) {
+            word_len -= 1;
+        }
+
+        // Extend the word past forbidden boundaries.
+        while word_len > 0 && !self.allows(start, start + word_len, text_len, None) {
+            match suffix[word_len..].chars().next() {
+                Some(c) if word_len + c.len_utf8() <= limit => word_len += c.len_utf8(),
+                _ => return 0,
+            }
+        }
+        word_len
     }
 }
 
@@ -118,17 +326,65 @@ pub struct Lattice {
     edges: Vec<Edge>,
     starts_at: Vec<Vec<EdgeId>>,
     ends_at: Vec<Vec<EdgeId>>,
+    // Number of characters before each byte offset of the text.
+    char_index: Vec<u32>,
+    pruning: Pruning,
+    unknown_word_mode: UnknownWordMode,
+}
+
+/// Limits on the size of a lattice, to bound the memory and time spent on very long texts.
+/// Pruning may make the result differ from the exact Viterbi path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pruning {
+    /// The maximum number of edges ending at a position that are kept as candidates for the edges
+    /// starting there. The edges with the lowest path costs are kept.
+    pub beam_width: Option<usize>,
+    /// The maximum number of edges starting at a position. The edges with the lowest word costs
+    /// are kept.
+    pub max_edges_per_position: Option<usize>,
+}
+
+/// How the candidates for unknown words are generated from the character definitions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownWordMode {
+    /// One character, or the whole run of characters of the category if it is grouped. In normal
+    /// mode, no candidate starts inside a run.
+    #[default]
+    Lindera,
+    /// Like MeCab: the whole run if the category is grouped, and the prefixes of 1 to LENGTH
+    /// characters of the run, at every position.
+    Mecab,
 }
 
 fn is_kanji(c: char) -> bool {
-    let c = c as u32;
-    (19968..=40879).contains(&c)
+    matches!(c as u32,
+        0x3400..=0x4DBF // CJK Unified Ideographs Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // CJK Unified Ideographs Extension B to F, CJK Compatibility Ideographs Supplement
+        | 0x30000..=0x323AF // CJK Unified Ideographs Extension G and H
+    )
 }
 
 fn is_kanji_only(s: &str) -> bool {
     s.chars().all(is_kanji)
 }
 
+/// Returns the first category of the first character of `s` that all its other characters
+/// also belong to.
+fn common_category(char_definitions: &CharacterDefinition, s: &str) -> Option<CategoryId> {
+    let mut chars = s.chars();
+    let mut first_categories = char_definitions.lookup_categories(chars.next()?);
+    first_categories.find(|category| {
+        chars.clone().all(|c| {
+            char_definitions
+                .lookup_categories(c)
+                .any(|other| other == *category)
+        })
+    })
+}
+
 impl Lattice {
     pub fn clear(&mut self) {
         for edge_vec in &mut self.starts_at {
@@ -140,6 +396,16 @@ impl Lattice {
         self.edges.clear()
     }
 
+    /// Sets the limits applied by `set_text` and `calculate_path_costs`.
+    pub fn set_pruning(&mut self, pruning: Pruning) {
+        self.pruning = pruning;
+    }
+
+    /// Sets how `set_text` generates the candidates for unknown words.
+    pub fn set_unknown_word_mode(&mut self, unknown_word_mode: UnknownWordMode) {
+        self.unknown_word_mode = unknown_word_mode;
+    }
+
     fn set_capacity(&mut self, text_len: usize) {
         self.clear();
         if self.capacity < text_len {
@@ -150,22 +416,63 @@ impl Lattice {
         }
     }
 
+    /// Builds the lattice of `text`. The user dictionaries are consulted in order, and the ids of
+    /// their words are made with `WordId::user` from the index of their dictionary.
     #[inline(never)]
     pub fn set_text(
         &mut self,
         dict: &PrefixDictionary,
-        user_dict: &Option<&PrefixDictionary>,
+        user_dicts: &[UserDictionaryLayer],
+        char_definitions: &CharacterDefinition,
+        unknown_dictionary: &UnknownDictionary,
+        text: &str,
+        search_mode: &Mode,
+    ) {
+        self.set_text_with_constraints(
+            dict,
+            user_dicts,
+            char_definitions,
+            unknown_dictionary,
+            text,
+            search_mode,
+            &LatticeConstraints::default(),
+        );
+    }
+
+    /// Same as `set_text`, but edges that break the given constraints are left out of the
+    /// lattice, like MeCab's partial analysis mode.
+    ///
+    /// Unknown words are cut short so as not to cross a forced boundary or run into a span, and
+    /// are extended when they would end on a forbidden boundary. If no allowed edge covers a span,
+    /// an edge of type `EdgeType::INSERTED` is added for it, so that the span is always a token.
+    /// The constraints are assumed to be consistent (see `LatticeConstraints`).
+    #[allow(clippy::too_many_arguments)]
+    #[inline(never)]
+    pub fn set_text_with_constraints(
+        &mut self,
+        dict: &PrefixDictionary,
+        user_dicts: &[UserDictionaryLayer],
         char_definitions: &CharacterDefinition,
         unknown_dictionary: &UnknownDictionary,
         text: &str,
         search_mode: &Mode,
+        constraints: &LatticeConstraints,
     ) {
         let len = text.len();
         self.set_capacity(len);
+        self.set_char_index(text);
 
+        let num_chars = self.char_index[len];
         let start_edge_id = self.add_edge(Edge::default());
-        let end_edge_id = self.add_edge(Edge::default());
+        let end_edge_id = self.add_edge(Edge {
+            start_index: len as u32,
+            stop_index: len as u32,
+            start_char: num_chars,
+            stop_char: num_chars,
+            ..Edge::default()
+        });
 
+        assert_eq!(BOS_NODE, start_edge_id);
         assert_eq!(EOS_NODE, end_edge_id);
         self.ends_at[0].push(start_edge_id);
         self.starts_at[len].push(end_edge_id);
@@ -184,18 +491,24 @@ impl Lattice {
 
             let mut found: bool = false;
 
-            // lookup user dictionary
-            if user_dict.is_some() {
-                let dict = user_dict.as_ref().unwrap();
-                for (prefix_len, word_entry) in dict.prefix(suffix) {
+            // lookup user dictionaries, in order
+            for (dictionary_index, user_dict) in user_dicts.iter().enumerate() {
+                for (prefix_len, mut word_entry) in user_dict.dict.prefix(suffix) {
+                    word_entry.word_id = WordId::user(dictionary_index, word_entry.word_id.id);
+                    word_entry.word_cost = word_entry.word_cost.saturating_add(user_dict.cost_bias);
+                    let stop = start + prefix_len;
+                    if !constraints.allows(start, stop, len, Some(word_entry.word_id)) {
+                        continue;
+                    }
                     let edge = Edge {
-                        edge_type: EdgeType::KNOWN,
+                        edge_type: EdgeType::USER,
                         word_entry,
                         left_edge: None,
                         start_index: start as u32,
-                        stop_index: (start + prefix_len) as u32,
+                        stop_index: stop as u32,
                         path_cost: i32::MAX,
                         kanji_only: is_kanji_only(&suffix[..prefix_len]),
+                        ..Edge::default()
                     };
                     self.add_edge_in_lattice(edge);
                     found = true;
@@ -205,14 +518,19 @@ impl Lattice {
             // we check all word starting at start, using the double array, like we would use
             // a prefix trie, and populate the lattice with as many edges
             for (prefix_len, word_entry) in dict.prefix(suffix) {
+                let stop = start + prefix_len;
+                if !constraints.allows(start, stop, len, Some(word_entry.word_id)) {
+                    continue;
+                }
                 let edge = Edge {
                     edge_type: EdgeType::KNOWN,
                     word_entry,
                     left_edge: None,
                     start_index: start as u32,
-                    stop_index: (start + prefix_len) as u32,
+                    stop_index: stop as u32,
                     path_cost: i32::MAX,
                     kanji_only: is_kanji_only(&suffix[..prefix_len]),
+                    ..Edge::default()
                 };
                 self.add_edge_in_lattice(edge);
                 found = true;
@@ -220,11 +538,12 @@ impl Lattice {
 
             // In the case of normal mode, it doesn't process unknown word greedily.
             if search_mode.is_search()
+                || self.unknown_word_mode == UnknownWordMode::Mecab
                 || unknown_word_end.map(|index| index <= start).unwrap_or(true)
             {
                 if let Some(first_char) = suffix.chars().next() {
                     let categories = char_definitions.lookup_categories(first_char);
-                    for (category_ord, &category) in categories.iter().enumerate() {
+                    for (category_ord, category) in categories.enumerate() {
                         unknown_word_end = self.process_unknown_word(
                             char_definitions,
                             unknown_dictionary,
@@ -234,10 +553,76 @@ impl Lattice {
                             start,
                             suffix,
                             found,
+                            constraints,
+                            len,
                         );
                     }
                 }
             }
+
+            // Make sure that a span is always covered by an edge.
+            if let Some(span) = constraints.span_starting_at(start) {
+                let covered = self.starts_at[start]
+                    .iter()
+                    .any(|&edge_id| self.edge(edge_id).stop_index as usize == span.end);
+                if !covered {
+                    let surface = &text[span.start..span.end];
+                    let word_entry = surface
+                        .chars()
+                        .next()
+                        .and_then(|first_char| {
+                            char_definitions
+                                .lookup_categories(first_char)
+                                .next()
+                                .and_then(|category| {
+                                    unknown_dictionary.lookup_word_ids(category).next()
+                                })
+                        })
+                        .map(|word_id| unknown_dictionary.word_entry(word_id))
+                        .unwrap_or_default();
+                    let edge = Edge {
+                        edge_type: EdgeType::INSERTED,
+                        word_entry,
+                        left_edge: None,
+                        start_index: span.start as u32,
+                        stop_index: span.end as u32,
+                        path_cost: i32::MAX,
+                        kanji_only: is_kanji_only(surface),
+                        ..Edge::default()
+                    };
+                    self.add_edge_in_lattice(edge);
+                }
+            }
+        }
+
+        if let Mode::Decompose(penalty) = search_mode {
+            for edge_id in 2..self.edges.len() {
+                let edge = &self.edges[edge_id];
+                let category = common_category(
+                    char_definitions,
+                    &text[edge.start_index as usize..edge.stop_index as usize],
+                );
+                let edge = &mut self.edges[edge_id];
+                edge.category = category;
+                edge.penalty_cost = penalty.penalty_with_categories(edge, char_definitions);
+            }
+        }
+    }
+
+    fn set_char_index(&mut self, text: &str) {
+        self.char_index.clear();
+        self.char_index.resize(text.len() + 1, 0);
+        let mut num_chars = 0;
+        let mut graphemes = text.grapheme_indices(true).peekable();
+        for (byte_index, char_index) in self.char_index.iter_mut().enumerate() {
+            while graphemes
+                .peek()
+                .is_some_and(|&(start, _)| start < byte_index)
+            {
+                graphemes.next();
+                num_chars += 1;
+            }
+            *char_index = num_chars;
         }
     }
 
@@ -252,29 +637,72 @@ impl Lattice {
         start: usize,
         suffix: &str,
         found: bool,
+        constraints: &LatticeConstraints,
+        text_len: usize,
     ) -> Option<usize> {
-        let mut unknown_word_num_chars: usize = 0;
         let category_data = char_definitions.lookup_definition(category);
-        if category_data.invoke || !found {
-            unknown_word_num_chars = 1;
-            if category_data.group {
-                for c in suffix.chars().skip(1) {
-                    let categories = char_definitions.lookup_categories(c);
-                    if categories.len() > category_ord && categories[category_ord] == category {
-                        unknown_word_num_chars += 1;
-                    } else {
-                        break;
-                    }
-                }
+        if !category_data.invoke && found {
+            return unknown_word_index;
+        }
+
+        let mecab = self.unknown_word_mode == UnknownWordMode::Mecab;
+
+        // Count the characters of the run of the category, as far as needed.
+        let max_num_chars = if category_data.group {
+            usize::MAX
+        } else if mecab {
+            category_data.length as usize
+        } else {
+            1
+        };
+        let mut run_num_chars: usize = 1;
+        for c in suffix.chars().skip(1) {
+            if run_num_chars >= max_num_chars {
+                break;
             }
+            let mut categories = char_definitions.lookup_categories(c);
+            if categories.nth(category_ord) == Some(category) {
+                run_num_chars += 1;
+            } else {
+                break;
+            }
+        }
+
+        // The numbers of characters of the candidates. Duplicates are skipped below.
+        let mut candidates = Vec::new();
+        if category_data.group {
+            candidates.push(run_num_chars);
+        }
+        if mecab {
+            let max_len = run_num_chars.min(category_data.length as usize);
+            candidates.extend(1..=max_len);
         }
-        if unknown_word_num_chars > 0 {
-            // optimize
-            let unknown_word = suffix
+        if candidates.is_empty() {
+            candidates.push(1);
+        }
+
+        let mut unknown_word_end = unknown_word_index;
+        let mut unknown_word_lens = Vec::with_capacity(candidates.len());
+        for num_chars in candidates {
+            let mut unknown_word_len = suffix
                 .chars()
-                .take(unknown_word_num_chars)
-                .collect::<String>();
-            for &word_id in unknown_dictionary.lookup_word_ids(category) {
+                .take(num_chars)
+                .map(char::len_utf8)
+                .sum::<usize>();
+            if !constraints.is_empty() {
+                unknown_word_len =
+                    constraints.fit_unknown_word(start, unknown_word_len, suffix, text_len);
+                if unknown_word_len == 0 {
+                    continue;
+                }
+            }
+            // Fitting may make several candidates the same.
+            if unknown_word_lens.contains(&unknown_word_len) {
+                continue;
+            }
+            unknown_word_lens.push(unknown_word_len);
+            let unknown_word = &suffix[..unknown_word_len];
+            for word_id in unknown_dictionary.lookup_word_ids(category) {
                 let word_entry = unknown_dictionary.word_entry(word_id);
                 let edge = Edge {
                     edge_type: EdgeType::UNKNOWN,
@@ -283,18 +711,45 @@ impl Lattice {
                     start_index: start as u32,
                     stop_index: (start + unknown_word.len()) as u32,
                     path_cost: i32::MAX,
-                    kanji_only: is_kanji_only(&unknown_word[..]),
+                    kanji_only: is_kanji_only(unknown_word),
+                    ..Edge::default()
                 };
                 self.add_edge_in_lattice(edge);
             }
-            return Some(start + unknown_word.len());
+            unknown_word_end = Some(start + unknown_word.len());
         }
-        unknown_word_index
+        unknown_word_end
     }
 
-    fn add_edge_in_lattice(&mut self, edge: Edge) {
+    fn add_edge_in_lattice(&mut self, mut edge: Edge) {
         let start_index = edge.start_index as usize;
         let stop_index = edge.stop_index as usize;
+        edge.start_char = self.char_index[start_index];
+        edge.stop_char = self.char_index[stop_index];
+
+        // Edges inserted for a span are always kept, so that the span stays covered.
+        if let Some(max_edges) = self.pruning.max_edges_per_position {
+            if self.starts_at[start_index].len() >= max_edges
+                && !matches!(edge.edge_type, EdgeType::INSERTED)
+            {
+                // Replace the most expensive edge starting here, if the new edge is cheaper.
+                let worst = self.starts_at[start_index]
+                    .iter()
+                    .copied()
+                    .filter(|&edge_id| !matches!(self.edge(edge_id).edge_type, EdgeType::INSERTED))
+                    .max_by_key(|&edge_id| self.edge(edge_id).word_entry.word_cost);
+                if let Some(worst) = worst {
+                    if edge.word_entry.word_cost < self.edge(worst).word_entry.word_cost {
+                        let worst_stop_index = self.edge(worst).stop_index as usize;
+                        self.ends_at[worst_stop_index].retain(|&edge_id| edge_id != worst);
+                        self.ends_at[stop_index].push(worst);
+                        self.edges[worst.0 as usize] = edge;
+                    }
+                }
+                return;
+            }
+        }
+
         let edge_id = self.add_edge(edge);
         self.starts_at[start_index].push(edge_id);
         self.ends_at[stop_index].push(edge_id);
@@ -314,6 +769,16 @@ impl Lattice {
     pub fn calculate_path_costs(&mut self, cost_matrix: &ConnectionCostMatrix, mode: &Mode) {
         let text_len = self.starts_at.len();
         for i in 0..text_len {
+            // Keep only the best edges ending here as candidates.
+            if let Some(beam_width) = self.pruning.beam_width {
+                if self.ends_at[i].len() > beam_width {
+                    let mut left_edge_ids = std::mem::take(&mut self.ends_at[i]);
+                    left_edge_ids.sort_by_key(|&edge_id| self.edge(edge_id).path_cost);
+                    left_edge_ids.truncate(beam_width);
+                    self.ends_at[i] = left_edge_ids;
+                }
+            }
+
             let left_edge_ids = &self.ends_at[i];
             let right_edge_ids = &self.starts_at[i];
             for &right_edge_id in right_edge_ids {
@@ -339,6 +804,14 @@ impl Lattice {
         }
     }
 
+    /// Returns whether there is a path from BOS to EOS. It can only be false when the lattice was
+    /// built with constraints that cannot be satisfied.
+    ///
+    /// `calculate_path_costs` must have been called.
+    pub fn has_path(&self) -> bool {
+        !self.best_path().is_empty()
+    }
+
     pub fn tokens_offset(&self) -> Vec<(usize, WordId)> {
         let mut offsets = Vec::new();
         let mut edge_id = EOS_NODE;
@@ -356,11 +829,533 @@ impl Lattice {
         offsets.pop();
         offsets
     }
+
+    /// Returns up to `n` paths through the lattice in ascending order of total cost,
+    /// like MeCab's `-N` option. Each path is returned with its total cost and the
+    /// `(start_index, word_id)` of every edge between BOS and EOS.
+    ///
+    /// `calculate_path_costs` must have been called with the same cost matrix and mode.
+    pub fn nbest_tokens_offset(
+        &self,
+        cost_matrix: &ConnectionCostMatrix,
+        mode: &Mode,
+        n: usize,
+    ) -> Vec<(Vec<(usize, WordId)>, i32)> {
+        self.nbest_paths(cost_matrix, mode, n)
+            .into_iter()
+            .map(|(path, cost)| {
+                let offsets = path
+                    .iter()
+                    .map(|&edge_id| {
+                        let edge = self.edge(edge_id);
+                        (edge.start_index as usize, edge.word_entry.word_id)
+                    })
+                    .collect();
+                (offsets, cost)
+            })
+            .collect()
+    }
+
+    /// Enumerates the `n` best paths with a backward A* search from EOS.
+    ///
+    /// The forward Viterbi costs stored in `path_cost` are the exact cost of the best
+    /// prefix ending at each edge, so they are used as the heuristic and paths come out
+    /// of the queue in ascending order of total cost.
+    fn nbest_paths(
+        &self,
+        cost_matrix: &ConnectionCostMatrix,
+        mode: &Mode,
+        n: usize,
+    ) -> Vec<(Vec<EdgeId>, i32)> {
+        struct Node {
+            edge_id: EdgeId,
+            // Cost of the partial path on the right side of the edge, excluding the edge itself.
+            suffix_cost: i32,
+            next: Option<usize>,
+        }
+
+        let mut paths = Vec::new();
+        if n == 0 || self.edges.len() <= EOS_NODE.0 as usize {
+            return paths;
+        }
+        let eos = self.edge(EOS_NODE);
+        if eos.left_edge.is_none() {
+            return paths;
+        }
+
+        let mut nodes = vec![Node {
+            edge_id: EOS_NODE,
+            suffix_cost: 0,
+            next: None,
+        }];
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((eos.path_cost, 0usize)));
+
+        while let Some(Reverse((cost, node_idx))) = queue.pop() {
+            let edge_id = nodes[node_idx].edge_id;
+            if edge_id == BOS_NODE {
+                let mut path = Vec::new();
+                let mut next = nodes[node_idx].next;
+                while let Some(idx) = next {
+                    if nodes[idx].edge_id != EOS_NODE {
+                        path.push(nodes[idx].edge_id);
+                    }
+                    next = nodes[idx].next;
+                }
+                paths.push((path, cost));
+                if paths.len() >= n {
+                    break;
+                }
+                continue;
+            }
+
+            let edge = self.edge(edge_id);
+            let suffix_cost = nodes[node_idx].suffix_cost + edge.word_entry.word_cost as i32;
+            for &left_edge_id in &self.ends_at[edge.start_index as usize] {
+                let left_edge = self.edge(left_edge_id);
+                // Skip edges that are not reachable from BOS.
+                if left_edge_id != BOS_NODE && left_edge.left_edge.is_none() {
+                    continue;
+                }
+                let left_suffix_cost = suffix_cost
+                    + cost_matrix.cost(left_edge.word_entry.right_id(), edge.word_entry.left_id())
+                    + mode.penalty_cost(left_edge);
+                nodes.push(Node {
+                    edge_id: left_edge_id,
+                    suffix_cost: left_suffix_cost,
+                    next: Some(node_idx),
+                });
+                queue.push(Reverse((
+                    left_edge.path_cost + left_suffix_cost,
+                    nodes.len() - 1,
+                )));
+            }
+        }
+
+        paths
+    }
+
+    /// Returns the byte offset of each boundary between two tokens of the best path, along with
+    /// the connection cost between them. A low connection cost means that the boundary is likely,
+    /// so it is a safe place to split a long text.
+    ///
+    /// `calculate_path_costs` must have been called with the same cost matrix.
+    pub fn best_path_boundaries(&self, cost_matrix: &ConnectionCostMatrix) -> Vec<(usize, i32)> {
+        let best_path = self.best_path();
+        if best_path.len() < 4 {
+            return Vec::new();
+        }
+        best_path[1..best_path.len() - 1]
+            .windows(2)
+            .map(|pair| {
+                let left_edge = self.edge(pair[0]);
+                let right_edge = self.edge(pair[1]);
+                (
+                    right_edge.start_index as usize,
+                    cost_matrix.cost(
+                        left_edge.word_entry.right_id(),
+                        right_edge.word_entry.left_id(),
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the marginal probability of each edge of the best path, in the same order as
+    /// `tokens_offset`.
+    ///
+    /// The probability of a path is proportional to `exp(-cost / temperature)`, where `cost` is
+    /// the sum of its word costs and connection costs. The marginal probability of an edge is the
+    /// total probability of the paths going through it, computed with the forward-backward
+    /// algorithm. A low temperature concentrates the probability mass on the best path, and a
+    /// high one spreads it over the alternatives. Since costs are typically in the hundreds or
+    /// thousands, temperatures of a similar order give meaningful scores.
+    ///
+    /// `calculate_path_costs` must have been called with the same cost matrix and mode.
+    pub fn tokens_confidence(
+        &self,
+        cost_matrix: &ConnectionCostMatrix,
+        mode: &Mode,
+        temperature: f64,
+    ) -> Vec<f64> {
+        let best_path = self.best_path();
+        if best_path.len() < 2 {
+            return Vec::new();
+        }
+
+        let alpha = self.forward(cost_matrix, mode, temperature);
+        let beta = self.backward(cost_matrix, mode, temperature);
+        let log_z = alpha[EOS_NODE.0 as usize];
+
+        best_path[1..best_path.len() - 1]
+            .iter()
+            .map(|edge_id| {
+                let idx = edge_id.0 as usize;
+                (alpha[idx] + beta[idx] - log_z).exp().min(1.0)
+            })
+            .collect()
+    }
+
+    /// Draws a path at random, with the probability of each path proportional to
+    /// `exp(-cost / temperature)` as in `tokens_confidence`, and returns it in the same form as
+    /// `tokens_offset`.
+    ///
+    /// `calculate_path_costs` must have been called with the same cost matrix and mode.
+    pub fn sample_tokens_offset<R: Rng + ?Sized>(
+        &self,
+        cost_matrix: &ConnectionCostMatrix,
+        mode: &Mode,
+        temperature: f64,
+        rng: &mut R,
+    ) -> Vec<(usize, WordId)> {
+        let mut offsets = Vec::new();
+        if self.best_path().is_empty() {
+            return offsets;
+        }
+
+        let alpha = self.forward(cost_matrix, mode, temperature);
+
+        // Sample backward from EOS: the left edge is chosen with a probability proportional to
+        // the total weight of the paths from BOS that reach the current edge through it.
+        let mut edge_id = EOS_NODE;
+        while edge_id != BOS_NODE {
+            let edge = self.edge(edge_id);
+            let candidates = self.ends_at[edge.start_index as usize]
+                .iter()
+                .map(|&left_edge_id| {
+                    let left_edge = self.edge(left_edge_id);
+                    let score = alpha[left_edge_id.0 as usize]
+                        - self.connection_cost(cost_matrix, mode, left_edge, edge) / temperature;
+                    (left_edge_id, score)
+                })
+                .filter(|(_, score)| score.is_finite())
+                .collect::<Vec<_>>();
+            let max = candidates
+                .iter()
+                .map(|&(_, score)| score)
+                .fold(f64::NEG_INFINITY, f64::max);
+            let total: f64 = candidates
+                .iter()
+                .map(|&(_, score)| (score - max).exp())
+                .sum();
+
+            let mut threshold = rng.gen::<f64>() * total;
+            let mut chosen = candidates[candidates.len() - 1].0;
+            for &(left_edge_id, score) in &candidates {
+                threshold -= (score - max).exp();
+                if threshold <= 0.0 {
+                    chosen = left_edge_id;
+                    break;
+                }
+            }
+
+            if edge_id != EOS_NODE {
+                offsets.push((edge.start_index as usize, edge.word_entry.word_id));
+            }
+            edge_id = chosen;
+        }
+
+        offsets.reverse();
+        offsets
+    }
+
+    /// The cost of connecting `left_edge` to `right_edge`, excluding the word costs.
+    fn connection_cost(
+        &self,
+        cost_matrix: &ConnectionCostMatrix,
+        mode: &Mode,
+        left_edge: &Edge,
+        right_edge: &Edge,
+    ) -> f64 {
+        (cost_matrix.cost(
+            left_edge.word_entry.right_id(),
+            right_edge.word_entry.left_id(),
+        ) + mode.penalty_cost(left_edge)) as f64
+    }
+
+    /// Computes, for each edge, the log of the total weight of the paths from BOS up to and
+    /// including the edge.
+    fn forward(
+        &self,
+        cost_matrix: &ConnectionCostMatrix,
+        mode: &Mode,
+        temperature: f64,
+    ) -> Vec<f64> {
+        let mut alpha = vec![f64::NEG_INFINITY; self.edges.len()];
+        alpha[BOS_NODE.0 as usize] = 0.0;
+        for (left_edge_ids, right_edge_ids) in self.ends_at.iter().zip(self.starts_at.iter()) {
+            for &right_edge_id in right_edge_ids {
+                let right_edge = self.edge(right_edge_id);
+                let score = log_sum_exp(left_edge_ids.iter().map(|&left_edge_id| {
+                    alpha[left_edge_id.0 as usize]
+                        - self.connection_cost(
+                            cost_matrix,
+                            mode,
+                            self.edge(left_edge_id),
+                            right_edge,
+                        ) / temperature
+                }));
+                alpha[right_edge_id.0 as usize] =
+                    score - right_edge.word_entry.word_cost as f64 / temperature;
+            }
+        }
+        alpha
+    }
+
+    /// Computes, for each edge, the log of the total weight of the paths from the edge (excluding
+    /// it) to EOS.
+    fn backward(
+        &self,
+        cost_matrix: &ConnectionCostMatrix,
+        mode: &Mode,
+        temperature: f64,
+    ) -> Vec<f64> {
+        let mut beta = vec![f64::NEG_INFINITY; self.edges.len()];
+        beta[EOS_NODE.0 as usize] = 0.0;
+        for (left_edge_ids, right_edge_ids) in self.ends_at.iter().zip(self.starts_at.iter()).rev()
+        {
+            for &left_edge_id in left_edge_ids {
+                let left_edge = self.edge(left_edge_id);
+                beta[left_edge_id.0 as usize] =
+                    log_sum_exp(right_edge_ids.iter().map(|&right_edge_id| {
+                        let right_edge = self.edge(right_edge_id);
+                        beta[right_edge_id.0 as usize]
+                            - (self.connection_cost(cost_matrix, mode, left_edge, right_edge)
+                                + right_edge.word_entry.word_cost as f64)
+                                / temperature
+                    }));
+            }
+        }
+        beta
+    }
+
+    /// Returns a snapshot of every edge and connection in the lattice, together with the
+    /// best path, for debugging. `text` must be the text passed to `set_text`, and
+    /// `calculate_path_costs` must have been called with the same cost matrix and mode.
+    pub fn dump(&self, text: &str, cost_matrix: &ConnectionCostMatrix, mode: &Mode) -> LatticeDump {
+        let best_path = self.best_path();
+        let best_cost = best_path
+            .last()
+            .map(|&edge_id| self.edge(edge_id).path_cost);
+
+        let edges = self
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(id, edge)| {
+                let edge_id = EdgeId(id as u32);
+                let reachable = edge_id == BOS_NODE || edge.left_edge.is_some();
+                let surface = if edge_id == BOS_NODE {
+                    "BOS"
+                } else if edge_id == EOS_NODE {
+                    "EOS"
+                } else {
+                    &text[edge.start_index as usize..edge.stop_index as usize]
+                };
+                EdgeDump {
+                    id: edge_id.0,
+                    surface: surface.to_string(),
+                    edge_type: edge.edge_type,
+                    start: edge.start_index as usize,
+                    end: edge.stop_index as usize,
+                    word_id: edge.word_entry.word_id.id,
+                    is_system: edge.word_entry.word_id.is_system,
+                    left_id: edge.word_entry.left_id,
+                    right_id: edge.word_entry.right_id,
+                    word_cost: edge.word_entry.word_cost as i32,
+                    penalty_cost: mode.penalty_cost(edge),
+                    path_cost: reachable.then_some(edge.path_cost),
+                    left_edge: edge.left_edge.map(|left_edge_id| left_edge_id.0),
+                    best: best_path.contains(&edge_id),
+                }
+            })
+            .collect();
+
+        let mut connections = Vec::new();
+        for (left_edge_ids, right_edge_ids) in self.ends_at.iter().zip(self.starts_at.iter()) {
+            for &right_edge_id in right_edge_ids {
+                let right_edge = self.edge(right_edge_id);
+                for &left_edge_id in left_edge_ids {
+                    let left_edge = self.edge(left_edge_id);
+                    connections.push(ConnectionDump {
+                        from: left_edge_id.0,
+                        to: right_edge_id.0,
+                        cost: cost_matrix.cost(
+                            left_edge.word_entry.right_id(),
+                            right_edge.word_entry.left_id(),
+                        ),
+                        best: right_edge.left_edge == Some(left_edge_id)
+                            && best_path.contains(&right_edge_id),
+                    });
+                }
+            }
+        }
+
+        LatticeDump {
+            text: text.to_string(),
+            edges,
+            connections,
+            best_path: best_path.iter().map(|edge_id| edge_id.0).collect(),
+            best_cost,
+        }
+    }
+
+    /// Returns the edges of the best path from BOS to EOS, or an empty vector if EOS is not
+    /// reachable.
+    fn best_path(&self) -> Vec<EdgeId> {
+        let mut path = Vec::new();
+        if self.edges.len() <= EOS_NODE.0 as usize || self.edge(EOS_NODE).left_edge.is_none() {
+            return path;
+        }
+        let mut edge_id = Some(EOS_NODE);
+        while let Some(id) = edge_id {
+            path.push(id);
+            edge_id = self.edge(id).left_edge;
+        }
+        path.reverse();
+        path
+    }
+}
+
+fn log_sum_exp<I: Iterator<Item = f64>>(values: I) -> f64 {
+    let values = values.collect::<Vec<_>>();
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// A snapshot of a `Lattice` produced by `Lattice::dump`, which can be rendered as Graphviz DOT
+/// or serialized as JSON.
+#[derive(Clone, Debug, Serialize)]
+pub struct LatticeDump {
+    /// The text the lattice was built from.
+    pub text: String,
+    /// All edges, indexed by edge id. Edge 0 is BOS and edge 1 is EOS.
+    pub edges: Vec<EdgeDump>,
+    /// All connections between an edge and the edges that start where it ends.
+    pub connections: Vec<ConnectionDump>,
+    /// The edge ids of the best path, from BOS to EOS.
+    pub best_path: Vec<u32>,
+    /// The total cost of the best path.
+    pub best_cost: Option<i32>,
+}
+
+/// An edge of a `LatticeDump`.
+#[derive(Clone, Debug, Serialize)]
+pub struct EdgeDump {
+    pub id: u32,
+    pub surface: String,
+    pub edge_type: EdgeType,
+    /// Byte offset of the start of the edge in the text.
+    pub start: usize,
+    /// Byte offset of the end of the edge in the text.
+    pub end: usize,
+    pub word_id: u32,
+    pub is_system: bool,
+    pub left_id: u16,
+    pub right_id: u16,
+    pub word_cost: i32,
+    /// The penalty added when this edge is connected to the next one (decompose mode).
+    pub penalty_cost: i32,
+    /// The cost of the best path from BOS up to and including this edge, or `None` if the edge
+    /// is not reachable from BOS.
+    pub path_cost: Option<i32>,
+    /// The id of the edge preceding this one on its best path.
+    pub left_edge: Option<u32>,
+    /// Whether the edge is on the best path.
+    pub best: bool,
+}
+
+/// A connection between two adjacent edges of a `LatticeDump`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConnectionDump {
+    pub from: u32,
+    pub to: u32,
+    /// The connection cost between the right id of `from` and the left id of `to`.
+    pub cost: i32,
+    /// Whether the connection is on the best path.
+    pub best: bool,
+}
+
+impl LatticeDump {
+    /// Serializes the lattice as pretty-printed JSON.
+    pub fn to_json(&self) -> LinderaResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|err| LinderaErrorKind::Serialize.with_error(anyhow::anyhow!(err)))
+    }
+
+    /// Renders the lattice as a Graphviz DOT digraph. Edges of the lattice are drawn as nodes,
+    /// and the best path is highlighted in red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        dot.push_str("digraph lattice {\n");
+        dot.push_str("  graph [rankdir=LR];\n");
+        dot.push_str("  node [shape=box];\n");
+        for edge in &self.edges {
+            let path_cost = edge
+                .path_cost
+                .map_or_else(|| "-".to_string(), |cost| cost.to_string());
+            let _ = write!(
+                dot,
+                "  e{} [label=\"{}\\n{:?} {}-{}\\nword_cost={} path_cost={}\"",
+                edge.id,
+                escape_dot(&edge.surface),
+                edge.edge_type,
+                edge.start,
+                edge.end,
+                edge.word_cost,
+                path_cost,
+            );
+            if edge.best {
+                dot.push_str(", color=red, penwidth=2");
+            }
+            dot.push_str("];\n");
+        }
+        for connection in &self.connections {
+            let _ = write!(
+                dot,
+                "  e{} -> e{} [label=\"{}\"",
+                connection.from, connection.to, connection.cost
+            );
+            if connection.best {
+                dot.push_str(", color=red, penwidth=2");
+            }
+            dot.push_str("];\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::viterbi::{WordEntry, WordId};
+    use std::collections::BTreeMap;
+
+    use crate::dictionary::Dictionary;
+    use crate::mode::{CategoryPenalty, Mode, Penalty};
+    use crate::test_utils::mini_ipadic;
+    use crate::viterbi::{
+        is_kanji, Edge, EdgeId, EdgeType, Lattice, LatticeConstraints, LatticeSpan, Pruning,
+        UnknownWordMode, WordEntry, WordId, EOS_NODE,
+    };
+
+    fn analyze(lattice: &mut Lattice, dictionary: &Dictionary, text: &str, mode: &Mode) {
+        lattice.set_text(
+            &dictionary.prefix_dictionary,
+            &[],
+            &dictionary.character_definition,
+            &dictionary.unknown_dictionary,
+            text,
+            mode,
+        );
+        lattice.calculate_path_costs(&dictionary.connection_cost_matrix, mode);
+    }
 
     #[test]
     fn test_word_entry() {
@@ -379,4 +1374,619 @@ mod tests {
         let word_entry2 = WordEntry::deserialize(&buffer[..], true);
         assert_eq!(word_entry, word_entry2);
     }
+
+    #[test]
+    fn test_nbest_tokens_offset() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        analyze(&mut lattice, &dictionary, "東京都に行く", &Mode::Normal);
+
+        // There are only three ways to segment the text with this dictionary.
+        let nbest =
+            lattice.nbest_tokens_offset(&dictionary.connection_cost_matrix, &Mode::Normal, 5);
+        assert_eq!(nbest.len(), 3);
+
+        // The best path is the Viterbi path.
+        assert_eq!(nbest[0].0, lattice.tokens_offset());
+
+        // Paths come out in ascending order of cost and are all different.
+        for i in 1..nbest.len() {
+            assert!(nbest[i - 1].1 <= nbest[i].1);
+            assert!(nbest[..i].iter().all(|(path, _)| *path != nbest[i].0));
+        }
+
+        // Every segmentation of "東京都" is enumerated.
+        let boundaries = nbest
+            .iter()
+            .map(|(path, _)| path.iter().map(|(start, _)| *start).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            boundaries,
+            vec![vec![0, 9, 12], vec![0, 6, 9, 12], vec![0, 3, 9, 12]]
+        );
+    }
+
+    #[test]
+    fn test_nbest_tokens_offset_cost() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        analyze(&mut lattice, &dictionary, "関西国際空港", &Mode::Normal);
+
+        let nbest =
+            lattice.nbest_tokens_offset(&dictionary.connection_cost_matrix, &Mode::Normal, 1);
+        assert_eq!(nbest.len(), 1);
+
+        // The cost of a path is the sum of its word costs and connection costs.
+        let (offsets, cost) = &nbest[0];
+        let matrix = &dictionary.connection_cost_matrix;
+        let mut expected = 0;
+        let mut right_id = 0;
+        for (start, word_id) in offsets {
+            let entry = dictionary
+                .prefix_dictionary
+                .prefix(&"関西国際空港"[*start..])
+                .map(|(_, entry)| entry)
+                .find(|entry| entry.word_id == *word_id)
+                .unwrap();
+            expected += matrix.cost(right_id, entry.left_id()) + entry.word_cost as i32;
+            right_id = entry.right_id();
+        }
+        expected += matrix.cost(right_id, 0);
+        assert_eq!(*cost, expected);
+    }
+
+    #[test]
+    fn test_nbest_tokens_offset_zero() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        analyze(&mut lattice, &dictionary, "東京都", &Mode::Normal);
+
+        assert!(lattice
+            .nbest_tokens_offset(&dictionary.connection_cost_matrix, &Mode::Normal, 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_dump() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        let text = "東京都に行く";
+        analyze(&mut lattice, &dictionary, text, &Mode::Normal);
+
+        let dump = lattice.dump(text, &dictionary.connection_cost_matrix, &Mode::Normal);
+        assert_eq!(dump.edges.len(), lattice.edges.len());
+        assert_eq!(dump.edges[0].surface, "BOS");
+        assert_eq!(dump.edges[1].surface, "EOS");
+
+        // The best path is the Viterbi path.
+        let best_path = dump
+            .best_path
+            .iter()
+            .map(|&id| dump.edges[id as usize].surface.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(best_path, vec!["BOS", "東京都", "に", "行く", "EOS"]);
+        assert_eq!(dump.best_cost, Some(lattice.edge(EOS_NODE).path_cost));
+        assert_eq!(
+            dump.edges.iter().filter(|edge| edge.best).count(),
+            dump.best_path.len()
+        );
+
+        // The path cost of an edge is the word cost plus the cost of its best incoming connection.
+        for edge in dump.edges.iter().skip(1) {
+            let Some(left_edge) = edge.left_edge else {
+                continue;
+            };
+            let connection = dump
+                .connections
+                .iter()
+                .find(|c| c.from == left_edge && c.to == edge.id)
+                .unwrap();
+            let left = &dump.edges[left_edge as usize];
+            assert_eq!(
+                edge.path_cost.unwrap(),
+                left.path_cost.unwrap() + connection.cost + left.penalty_cost + edge.word_cost
+            );
+        }
+        assert_eq!(
+            dump.connections.iter().filter(|c| c.best).count(),
+            dump.best_path.len() - 1
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&dump.to_json().unwrap()).unwrap();
+        assert_eq!(json["edges"][2]["edge_type"], "KNOWN");
+        assert_eq!(json["best_path"].as_array().unwrap().len(), 5);
+
+        let dot = dump.to_dot();
+        assert!(dot.starts_with("digraph lattice {"));
+        assert!(dot.contains("e0 [label=\"BOS"));
+        assert!(dot.contains("東京都"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn test_tokens_confidence() {
+        let dictionary = mini_ipadic();
+        let matrix = &dictionary.connection_cost_matrix;
+        let mut lattice = Lattice::default();
+        analyze(&mut lattice, &dictionary, "東京都", &Mode::Normal);
+
+        // "東京都" has three segmentations, so the confidence of the best one is its share of
+        // the total weight.
+        let nbest = lattice.nbest_tokens_offset(matrix, &Mode::Normal, 3);
+        assert_eq!(nbest.len(), 3);
+        assert_eq!(nbest[0].0.len(), 1);
+
+        let temperature = 1000.0;
+        let weights = nbest
+            .iter()
+            .map(|(_, cost)| (-*cost as f64 / temperature).exp())
+            .collect::<Vec<_>>();
+        let expected = weights[0] / weights.iter().sum::<f64>();
+
+        let confidence = lattice.tokens_confidence(matrix, &Mode::Normal, temperature);
+        assert_eq!(confidence.len(), 1);
+        assert!((confidence[0] - expected).abs() < 1e-9);
+
+        // A low temperature puts all the probability mass on the best path.
+        let confidence = lattice.tokens_confidence(matrix, &Mode::Normal, 1.0);
+        assert!((confidence[0] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sample_tokens_offset() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let dictionary = mini_ipadic();
+        let matrix = &dictionary.connection_cost_matrix;
+        let mut lattice = Lattice::default();
+        analyze(&mut lattice, &dictionary, "東京都に行く", &Mode::Normal);
+
+        let temperature = 1000.0;
+        let nbest = lattice.nbest_tokens_offset(matrix, &Mode::Normal, 3);
+        let weights = nbest
+            .iter()
+            .map(|(_, cost)| (-*cost as f64 / temperature).exp())
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f64>();
+
+        // Every sample is one of the paths, drawn with a frequency close to its probability.
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut counts = [0usize; 3];
+        let samples = 5000;
+        for _ in 0..samples {
+            let path = lattice.sample_tokens_offset(matrix, &Mode::Normal, temperature, &mut rng);
+            let idx = nbest
+                .iter()
+                .position(|(offsets, _)| *offsets == path)
+                .unwrap();
+            counts[idx] += 1;
+        }
+        for (count, weight) in counts.iter().zip(weights.iter()) {
+            let frequency = *count as f64 / samples as f64;
+            assert!((frequency - weight / total).abs() < 0.03);
+        }
+
+        // A low temperature always draws the best path.
+        let path = lattice.sample_tokens_offset(matrix, &Mode::Normal, 1.0, &mut rng);
+        assert_eq!(path, lattice.tokens_offset());
+    }
+
+    fn analyze_with_constraints(
+        lattice: &mut Lattice,
+        dictionary: &Dictionary,
+        text: &str,
+        constraints: &LatticeConstraints,
+    ) -> Vec<usize> {
+        lattice.set_text_with_constraints(
+            &dictionary.prefix_dictionary,
+            &[],
+            &dictionary.character_definition,
+            &dictionary.unknown_dictionary,
+            text,
+            &Mode::Normal,
+            constraints,
+        );
+        lattice.calculate_path_costs(&dictionary.connection_cost_matrix, &Mode::Normal);
+        assert!(lattice.has_path());
+        lattice
+            .tokens_offset()
+            .iter()
+            .map(|(start, _)| *start)
+            .collect()
+    }
+
+    #[test]
+    fn test_constraints_span() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        let text = "東京都に行く";
+
+        // Without constraints "東京都" is a single token.
+        let starts = analyze_with_constraints(
+            &mut lattice,
+            &dictionary,
+            text,
+            &LatticeConstraints::default(),
+        );
+        assert_eq!(starts, vec![0, 9, 12]);
+
+        let constraints = LatticeConstraints {
+            spans: vec![LatticeSpan {
+                start: 0,
+                end: 6,
+                word_ids: None,
+            }],
+            ..LatticeConstraints::default()
+        };
+        let starts = analyze_with_constraints(&mut lattice, &dictionary, text, &constraints);
+        assert_eq!(starts, vec![0, 6, 9, 12]);
+    }
+
+    #[test]
+    fn test_constraints_span_word_ids() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        let text = "東京都に行く";
+
+        // The span can only be covered by one of the given words.
+        let word_id = dictionary.prefix_dictionary.find_surface("東京")[0].word_id;
+        let constraints = LatticeConstraints {
+            spans: vec![LatticeSpan {
+                start: 0,
+                end: 6,
+                word_ids: Some(vec![word_id]),
+            }],
+            ..LatticeConstraints::default()
+        };
+        analyze_with_constraints(&mut lattice, &dictionary, text, &constraints);
+        assert_eq!(lattice.tokens_offset()[0], (0, word_id));
+
+        // If none of the words match, an edge is inserted for the span.
+        let constraints = LatticeConstraints {
+            spans: vec![LatticeSpan {
+                start: 0,
+                end: 6,
+                word_ids: Some(vec![WordId {
+                    id: 12345,
+                    is_system: true,
+                }]),
+            }],
+            ..LatticeConstraints::default()
+        };
+        let starts = analyze_with_constraints(&mut lattice, &dictionary, text, &constraints);
+        assert_eq!(starts, vec![0, 6, 9, 12]);
+        let first = lattice.edge(lattice.best_path()[1]);
+        assert!(matches!(first.edge_type, EdgeType::INSERTED));
+        assert_eq!((first.start_index, first.stop_index), (0, 6));
+    }
+
+    #[test]
+    fn test_constraints_boundaries() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        let text = "東京都に行く";
+
+        // A forced boundary splits "東京都" into "東" and "京都".
+        let constraints = LatticeConstraints {
+            forced_boundaries: vec![3],
+            ..LatticeConstraints::default()
+        };
+        let starts = analyze_with_constraints(&mut lattice, &dictionary, text, &constraints);
+        assert_eq!(starts, vec![0, 3, 9, 12]);
+
+        // A forbidden boundary has to be crossed by an unknown word.
+        let constraints = LatticeConstraints {
+            forbidden_boundaries: vec![9],
+            ..LatticeConstraints::default()
+        };
+        let starts = analyze_with_constraints(&mut lattice, &dictionary, text, &constraints);
+        assert!(!starts.contains(&9));
+        for (start, _) in lattice.tokens_offset() {
+            assert!(text.is_char_boundary(start));
+        }
+    }
+
+    #[test]
+    fn test_constraints_allows() {
+        let constraints = LatticeConstraints {
+            spans: vec![LatticeSpan {
+                start: 3,
+                end: 9,
+                word_ids: None,
+            }],
+            forced_boundaries: vec![12],
+            forbidden_boundaries: vec![0, 15],
+        };
+        assert!(constraints.allows(0, 3, 18, None));
+        assert!(constraints.allows(3, 9, 18, None));
+        assert!(!constraints.allows(0, 6, 18, None));
+        assert!(!constraints.allows(6, 12, 18, None));
+        assert!(constraints.allows(9, 12, 18, None));
+        assert!(!constraints.allows(9, 15, 18, None));
+        assert!(!constraints.allows(12, 15, 18, None));
+        assert!(constraints.allows(12, 18, 18, None));
+    }
+
+    #[test]
+    fn test_is_kanji() {
+        assert!(is_kanji('漢'));
+        assert!(is_kanji('㐂')); // Extension A
+        assert!(is_kanji('𠮷')); // Extension B
+        assert!(is_kanji('鿯'));
+        assert!(!is_kanji('あ'));
+        assert!(!is_kanji('a'));
+        assert!(!is_kanji('한'));
+    }
+
+    #[test]
+    fn test_num_chars() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+
+        // ASCII, a Hangul syllable written with conjoining jamo, and an emoji ZWJ sequence.
+        for (text, num_chars) in [
+            ("abcdefghij", 10),
+            ("\u{1112}\u{1161}\u{11AB}", 1),
+            ("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}", 1),
+            ("東京都", 3),
+        ] {
+            analyze(&mut lattice, &dictionary, text, &Mode::Normal);
+            let eos = lattice.edge(EOS_NODE);
+            assert_eq!(eos.start_char as usize, num_chars, "{}", text);
+
+            let longest = lattice.starts_at[0]
+                .iter()
+                .map(|&edge_id| lattice.edge(edge_id))
+                .find(|edge| edge.stop_index as usize == text.len())
+                .unwrap();
+            assert_eq!(longest.num_chars(), num_chars, "{}", text);
+        }
+
+        // An edge made of the trailing jamo of the syllable starts inside its cluster.
+        let text = "\u{1112}\u{1161}\u{11AB}";
+        analyze(&mut lattice, &dictionary, text, &Mode::Normal);
+        let edge = Edge {
+            start_index: 3,
+            stop_index: text.len() as u32,
+            start_char: lattice.char_index[3],
+            stop_char: lattice.char_index[text.len()],
+            ..Edge::default()
+        };
+        assert_eq!(edge.stop_char, edge.start_char);
+        assert_eq!(edge.num_chars(), 1);
+    }
+
+    #[test]
+    fn test_penalty() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        let mode = Mode::Decompose(Penalty::default());
+
+        // Words that are not kanji only are penalized beyond 7 characters, whatever their byte length.
+        analyze(&mut lattice, &dictionary, "abcdefghij", &mode);
+        let edge = lattice.edge(EdgeId(2));
+        assert_eq!(edge.num_chars(), 10);
+        assert_eq!(edge.penalty_cost, 3 * 1700);
+        assert_eq!(mode.penalty_cost(edge), 3 * 1700);
+        assert_eq!(Mode::Normal.penalty_cost(edge), 0);
+
+        // The penalty of kanji words splits "関西国際空港" into "関西", "国際" and "空港".
+        analyze(&mut lattice, &dictionary, "関西国際空港", &mode);
+        assert_eq!(lattice.tokens_offset().len(), 3);
+
+        // A penalty for the KANJI category of char.def takes precedence.
+        let mode = Mode::Decompose(Penalty {
+            category_penalties: BTreeMap::from([(
+                "KANJI".to_string(),
+                CategoryPenalty {
+                    length_threshold: 6,
+                    length_penalty: 3000,
+                },
+            )]),
+            ..Penalty::default()
+        });
+        analyze(&mut lattice, &dictionary, "関西国際空港", &mode);
+        assert_eq!(lattice.tokens_offset().len(), 1);
+        let edge = lattice.edge(lattice.best_path()[1]);
+        assert_eq!(
+            dictionary
+                .character_definition
+                .category_name(edge.category.unwrap()),
+            "KANJI"
+        );
+    }
+
+    #[test]
+    fn test_pruning() {
+        let dictionary = mini_ipadic();
+        let text = "関西国際空港限定バッグ";
+
+        let mut lattice = Lattice::default();
+        analyze(&mut lattice, &dictionary, text, &Mode::Normal);
+        let expected = lattice.tokens_offset();
+        let num_edges = lattice.edges.len();
+
+        // A wide enough beam gives the same result.
+        let mut pruned = Lattice::default();
+        pruned.set_pruning(Pruning {
+            beam_width: Some(4),
+            max_edges_per_position: Some(4),
+        });
+        analyze(&mut pruned, &dictionary, text, &Mode::Normal);
+        assert_eq!(pruned.tokens_offset(), expected);
+
+        // At most one edge starts at each position, and at most one ending edge is a candidate.
+        pruned.set_pruning(Pruning {
+            beam_width: Some(1),
+            max_edges_per_position: Some(1),
+        });
+        analyze(&mut pruned, &dictionary, text, &Mode::Normal);
+        assert!(pruned.edges.len() < num_edges);
+        assert!(pruned.starts_at.iter().all(|edge_ids| edge_ids.len() <= 1));
+        assert!(pruned.ends_at.iter().all(|edge_ids| edge_ids.len() <= 1));
+        assert!(pruned.has_path());
+
+        // The cheapest word starting at a position is kept.
+        let first = pruned.edge(pruned.starts_at[0][0]);
+        let cheapest = dictionary
+            .prefix_dictionary
+            .prefix(text)
+            .map(|(_, word_entry)| word_entry.word_cost)
+            .min()
+            .unwrap();
+        assert_eq!(first.word_entry.word_cost, cheapest);
+    }
+
+    #[test]
+    fn test_best_path_boundaries() {
+        let dictionary = mini_ipadic();
+        let mut lattice = Lattice::default();
+        analyze(&mut lattice, &dictionary, "東京都に行く", &Mode::Normal);
+
+        let boundaries = lattice.best_path_boundaries(&dictionary.connection_cost_matrix);
+        assert_eq!(
+            boundaries.iter().map(|(pos, _)| *pos).collect::<Vec<_>>(),
+            vec![9, 12]
+        );
+    }
+
+    fn unknown_surfaces_starting_at<'a>(
+        lattice: &Lattice,
+        text: &'a str,
+        start: usize,
+    ) -> Vec<&'a str> {
+        let mut surfaces = lattice.starts_at[start]
+            .iter()
+            .map(|&edge_id| lattice.edge(edge_id))
+            .filter(|edge| matches!(edge.edge_type, EdgeType::UNKNOWN))
+            .map(|edge| &text[edge.start_index as usize..edge.stop_index as usize])
+            .collect::<Vec<_>>();
+        surfaces.sort_by_key(|surface| surface.len());
+        surfaces
+    }
+
+    fn surfaces<'a>(lattice: &Lattice, text: &'a str) -> Vec<&'a str> {
+        let mut offsets = lattice
+            .tokens_offset()
+            .iter()
+            .map(|(start, _)| *start)
+            .collect::<Vec<_>>();
+        offsets.push(text.len());
+        offsets.windows(2).map(|w| &text[w[0]..w[1]]).collect()
+    }
+
+    #[test]
+    fn test_unknown_word_mode() {
+        let dictionary = mini_ipadic();
+
+        // KATAKANA is grouped and has LENGTH 2.
+        let text = "ロンドンに行く";
+        let mut lindera = Lattice::default();
+        analyze(&mut lindera, &dictionary, text, &Mode::Normal);
+        assert_eq!(
+            unknown_surfaces_starting_at(&lindera, text, 0),
+            vec!["ロンドン"]
+        );
+        assert!(unknown_surfaces_starting_at(&lindera, text, 3).is_empty());
+
+        let mut mecab = Lattice::default();
+        mecab.set_unknown_word_mode(UnknownWordMode::Mecab);
+        analyze(&mut mecab, &dictionary, text, &Mode::Normal);
+        assert_eq!(
+            unknown_surfaces_starting_at(&mecab, text, 0),
+            vec!["ロ", "ロン", "ロンドン"]
+        );
+        assert_eq!(
+            unknown_surfaces_starting_at(&mecab, text, 3),
+            vec!["ン", "ンド", "ンドン"]
+        );
+        assert_eq!(surfaces(&lindera, text), surfaces(&mecab, text));
+
+        // KANJI is not grouped and has LENGTH 2: an unknown word of two kanji is a candidate.
+        let text = "大阪に行く";
+        analyze(&mut lindera, &dictionary, text, &Mode::Normal);
+        assert_eq!(surfaces(&lindera, text), vec!["大", "阪", "に", "行く"]);
+        analyze(&mut mecab, &dictionary, text, &Mode::Normal);
+        assert_eq!(
+            unknown_surfaces_starting_at(&mecab, text, 0),
+            vec!["大", "大阪"]
+        );
+        assert_eq!(surfaces(&mecab, text), vec!["大阪", "に", "行く"]);
+    }
+
+    #[test]
+    fn test_user_dictionary_layers() {
+        use crate::dictionary_builder::ipadic::IpadicBuilder;
+        use crate::dictionary_builder::DictionaryBuilder;
+        use crate::test_utils::temp_dir;
+        use crate::viterbi::{
+            UserDictionaryLayer, MAX_USER_DICTIONARIES, MAX_USER_DICTIONARY_ENTRIES,
+        };
+
+        let dictionary = mini_ipadic();
+        let dir = temp_dir("user-dictionary-layers");
+        std::fs::create_dir_all(&dir).unwrap();
+        let build = |name: &str, rows: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, rows).unwrap();
+            IpadicBuilder::new().build_user_dict(&path, None).unwrap()
+        };
+        let company = build(
+            "company.csv",
+            "東京都庁,4,4,1000,名詞,固有名詞,組織,*,*,*,東京都庁,トウキョウトチョウ,トーキョートチョー\n",
+        );
+        let tenant = build(
+            "tenant.csv",
+            "ロンドン,4,4,2000,名詞,固有名詞,地域,一般,*,*,ロンドン,ロンドン,ロンドン\n\
+             東京都庁,4,4,1000,名詞,固有名詞,組織,*,*,*,東京都庁,トチョウ,トチョー\n",
+        );
+
+        let best_word_id = |cost_biases: [i16; 2]| {
+            let mut lattice = Lattice::default();
+            lattice.set_text(
+                &dictionary.prefix_dictionary,
+                &[
+                    UserDictionaryLayer {
+                        dict: &company.dict,
+                        cost_bias: cost_biases[0],
+                    },
+                    UserDictionaryLayer {
+                        dict: &tenant.dict,
+                        cost_bias: cost_biases[1],
+                    },
+                ],
+                &dictionary.character_definition,
+                &dictionary.unknown_dictionary,
+                "東京都庁",
+                &Mode::Normal,
+            );
+            lattice.calculate_path_costs(&dictionary.connection_cost_matrix, &Mode::Normal);
+            lattice.tokens_offset()[0].1
+        };
+
+        // The words of each dictionary get ids of their own, and the bias decides between them.
+        let word_id = best_word_id([0, -500]);
+        assert_eq!(word_id, WordId::user(1, 1));
+        assert_eq!(word_id.user_entry(), Some((1, 1)));
+        assert_eq!(tenant.word_details(1)[7], "トチョウ");
+
+        let word_id = best_word_id([-500, 0]);
+        assert_eq!(word_id, WordId::user(0, 0));
+        assert_eq!(word_id.user_entry(), Some((0, 0)));
+        assert_eq!(WordId::default().user_entry(), None);
+
+        // The words of the first dictionary keep the ids of their entries, whatever its size.
+        let word_id = WordId::user(0, 1 << 30);
+        assert_eq!(word_id.id, 1 << 30);
+        assert_eq!(word_id.user_entry(), Some((0, 1 << 30)));
+        assert!(!word_id.is_unknown());
+        let last = MAX_USER_DICTIONARY_ENTRIES as u32 - 1;
+        let word_id = WordId::user(MAX_USER_DICTIONARIES - 1, last);
+        assert_eq!(
+            word_id.user_entry(),
+            Some((MAX_USER_DICTIONARIES - 1, last))
+        );
+        assert!(!word_id.is_unknown());
+    }
 }
@@ -1,22 +1,21 @@
 #[cfg(feature = "ko-dic")]
 use std::env;
 
-#[cfg(feature = "compress")]
-use lindera_dictionary::decompress::decompress;
 use lindera_dictionary::dictionary::character_definition::CharacterDefinition;
 use lindera_dictionary::dictionary::connection_cost_matrix::ConnectionCostMatrix;
+use lindera_dictionary::dictionary::context_id::ContextIds;
+use lindera_dictionary::dictionary::metadata::Metadata;
 use lindera_dictionary::dictionary::prefix_dictionary::PrefixDictionary;
 use lindera_dictionary::dictionary::unknown_dictionary::UnknownDictionary;
 use lindera_dictionary::dictionary::Dictionary;
+use lindera_dictionary::util::{decompress_bytes, Deferred};
 use lindera_dictionary::LinderaResult;
 
 macro_rules! decompress_data {
     ($name: ident, $bytes: expr, $filename: literal) => {
         #[cfg(feature = "compress")]
         static $name: once_cell::sync::Lazy<Vec<u8>> = once_cell::sync::Lazy::new(|| {
-            let compressed_data = bincode::deserialize_from(&$bytes[..])
-                .expect(concat!("invalid file format ", $filename));
-            decompress(compressed_data).expect(concat!("invalid file format ", $filename))
+            decompress_bytes(&$bytes[..]).expect(concat!("invalid file format ", $filename))
         });
         #[cfg(not(feature = "compress"))]
         const $name: &'static [u8] = $bytes;
@@ -101,6 +100,28 @@ decompress_data!(
 #[cfg(not(feature = "ko-dic"))]
 decompress_data!(WORDS_DATA, &[], "dict.words");
 
+#[cfg(feature = "ko-dic")]
+const CONTEXT_IDS_DATA: &[u8] = include_bytes!(concat!(
+    env!("LINDERA_WORKDIR"),
+    "/lindera-ko-dic/context_ids.bin"
+));
+#[cfg(not(feature = "ko-dic"))]
+const CONTEXT_IDS_DATA: &[u8] = &[];
+
+#[cfg(feature = "ko-dic")]
+const METADATA_DATA: Option<&[u8]> = Some(include_bytes!(concat!(
+    env!("LINDERA_WORKDIR"),
+    "/lindera-ko-dic/metadata.json"
+)));
+#[cfg(not(feature = "ko-dic"))]
+const METADATA_DATA: Option<&[u8]> = None;
+
+/// Loads the embedded dictionary.
+///
+/// The prefix dictionary, the connection cost matrix, the character definition and the unknown
+/// dictionary are read in place, while the metadata and the context ids are deserialized the
+/// first time they are used. With the `compress` feature, the embedded files are decompressed
+/// once per process.
 pub fn load() -> LinderaResult<Dictionary> {
     let da_data = &DA_DATA;
     let vals_data = &VALS_DATA;
@@ -111,9 +132,22 @@ pub fn load() -> LinderaResult<Dictionary> {
     let unknown_data = &UNKNOWN_DATA;
 
     Ok(Dictionary {
-        prefix_dictionary: PrefixDictionary::load(da_data, vals_data, words_idx_data, words_data),
+        prefix_dictionary: PrefixDictionary::load_static(
+            da_data,
+            vals_data,
+            words_idx_data,
+            words_data,
+        ),
         connection_cost_matrix: ConnectionCostMatrix::load_static(connection_data),
-        character_definition: CharacterDefinition::load(char_definition)?,
-        unknown_dictionary: UnknownDictionary::load(unknown_data)?,
+        character_definition: CharacterDefinition::load_static(char_definition)?,
+        unknown_dictionary: UnknownDictionary::load_static(unknown_data)?,
+        metadata: METADATA_DATA.map(|data| Deferred::new(data.into(), Metadata::load)),
+        reading_index: None,
+        context_ids: Some(Deferred::new(CONTEXT_IDS_DATA.into(), load_context_ids)),
     })
 }
+
+/// Deserializes the embedded context ids, decompressing them if they have been compressed.
+fn load_context_ids(data: &[u8]) -> LinderaResult<ContextIds> {
+    ContextIds::load(&decompress_bytes(data)?)
+}
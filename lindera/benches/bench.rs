@@ -102,7 +102,7 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -115,9 +115,12 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
                     .join("ipadic_simple_userdic.csv");
 
                 let dictionary = load_dictionary_from_kind(DictionaryKind::IPADIC).unwrap();
-                let user_dictionary =
-                    load_user_dictionary_from_csv(DictionaryKind::IPADIC, userdic_file.as_path())
-                        .unwrap();
+                let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+                    DictionaryKind::IPADIC,
+                    userdic_file.as_path(),
+                    &dictionary,
+                )
+                .unwrap();
                 let segmenter = Segmenter::new(
                     Mode::Normal,
                     dictionary,
@@ -135,7 +138,7 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -148,9 +151,12 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
                     .join("unidic_simple_userdic.csv");
 
                 let dictionary = load_dictionary_from_kind(DictionaryKind::UniDic).unwrap();
-                let user_dictionary =
-                    load_user_dictionary_from_csv(DictionaryKind::UniDic, userdic_file.as_path())
-                        .unwrap();
+                let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+                    DictionaryKind::UniDic,
+                    userdic_file.as_path(),
+                    &dictionary,
+                )
+                .unwrap();
                 let segmenter = Segmenter::new(
                     Mode::Normal,
                     dictionary,
@@ -168,7 +174,7 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -181,9 +187,12 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
                     .join("ko-dic_simple_userdic.csv");
 
                 let dictionary = load_dictionary_from_kind(DictionaryKind::KoDic).unwrap();
-                let user_dictionary =
-                    load_user_dictionary_from_csv(DictionaryKind::KoDic, userdic_file.as_path())
-                        .unwrap();
+                let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+                    DictionaryKind::KoDic,
+                    userdic_file.as_path(),
+                    &dictionary,
+                )
+                .unwrap();
                 let segmenter = Segmenter::new(
                     Mode::Normal,
                     dictionary,
@@ -201,7 +210,7 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -214,9 +223,12 @@ fn bench_constructor_with_simple_userdic(c: &mut Criterion) {
                     .join("cc-cedict_simple_userdic.csv");
 
                 let dictionary = load_dictionary_from_kind(DictionaryKind::CcCedict).unwrap();
-                let user_dictionary =
-                    load_user_dictionary_from_csv(DictionaryKind::CcCedict, userdic_file.as_path())
-                        .unwrap();
+                let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+                    DictionaryKind::CcCedict,
+                    userdic_file.as_path(),
+                    &dictionary,
+                )
+                .unwrap();
                 let segmenter = Segmenter::new(
                     Mode::Normal,
                     dictionary,
@@ -328,7 +340,7 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -339,8 +351,12 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
             .join("ipadic_simple_userdic.csv");
 
         let dictionary = load_dictionary_from_kind(DictionaryKind::IPADIC).unwrap();
-        let user_dictionary =
-            load_user_dictionary_from_csv(DictionaryKind::IPADIC, userdic_file.as_path()).unwrap();
+        let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+            DictionaryKind::IPADIC,
+            userdic_file.as_path(),
+            &dictionary,
+        )
+        .unwrap();
         let segmenter = Segmenter::new(
             Mode::Normal,
             dictionary,
@@ -362,7 +378,7 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -373,8 +389,12 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
             .join("unidic_simple_userdic.csv");
 
         let dictionary = load_dictionary_from_kind(DictionaryKind::UniDic).unwrap();
-        let user_dictionary =
-            load_user_dictionary_from_csv(DictionaryKind::UniDic, userdic_file.as_path()).unwrap();
+        let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+            DictionaryKind::UniDic,
+            userdic_file.as_path(),
+            &dictionary,
+        )
+        .unwrap();
         let segmenter = Segmenter::new(
             Mode::Normal,
             dictionary,
@@ -396,7 +416,7 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -407,8 +427,12 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
             .join("ko-dic_simple_userdic.csv");
 
         let dictionary = load_dictionary_from_kind(DictionaryKind::KoDic).unwrap();
-        let user_dictionary =
-            load_user_dictionary_from_csv(DictionaryKind::KoDic, userdic_file.as_path()).unwrap();
+        let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+            DictionaryKind::KoDic,
+            userdic_file.as_path(),
+            &dictionary,
+        )
+        .unwrap();
         let segmenter = Segmenter::new(
             Mode::Normal,
             dictionary,
@@ -428,7 +452,7 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -439,9 +463,12 @@ fn bench_tokenize_with_simple_userdic(c: &mut Criterion) {
             .join("cc-cedict_simple_userdic.csv");
 
         let dictionary = load_dictionary_from_kind(DictionaryKind::CcCedict).unwrap();
-        let user_dictionary =
-            load_user_dictionary_from_csv(DictionaryKind::CcCedict, userdic_file.as_path())
-                .unwrap();
+        let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+            DictionaryKind::CcCedict,
+            userdic_file.as_path(),
+            &dictionary,
+        )
+        .unwrap();
         let segmenter = Segmenter::new(
             Mode::Normal,
             dictionary,
@@ -641,6 +668,88 @@ fn bench_tokenize_details_long_text(c: &mut Criterion) {
     }
 }
 
+/// Compares the size of the dictionary data compressed with each algorithm, and the time it takes
+/// to decompress it when the dictionary is loaded.
+#[cfg(any(feature = "ipadic", feature = "unidic"))]
+fn bench_compression_of(
+    c: &mut Criterion,
+    name: &str,
+    dictionary: &lindera::dictionary::Dictionary,
+) {
+    use criterion::BatchSize;
+    use lindera_dictionary::compress::compress;
+    use lindera_dictionary::decompress::{decompress, Algorithm};
+
+    let sections: Vec<&[u8]> = vec![
+        &dictionary.prefix_dictionary.da.0,
+        &dictionary.prefix_dictionary.vals_data,
+        &dictionary.prefix_dictionary.words_idx_data,
+        &dictionary.prefix_dictionary.words_data,
+        &dictionary.connection_cost_matrix.costs_data,
+    ];
+    let raw_size: usize = sections.iter().map(|section| section.len()).sum();
+
+    #[allow(unused_mut)]
+    let mut algorithms = vec![Algorithm::Deflate, Algorithm::Zlib, Algorithm::Gzip];
+    #[cfg(feature = "zstd")]
+    algorithms.push(Algorithm::Zstd);
+    #[cfg(feature = "lz4")]
+    algorithms.push(Algorithm::Lz4);
+
+    let mut group = c.benchmark_group(format!("decompress-{}", name));
+    group.sample_size(10);
+    for algorithm in algorithms {
+        let compressed = sections
+            .iter()
+            .map(|section| compress(section, algorithm).unwrap())
+            .collect::<Vec<_>>();
+        let size: usize = compressed
+            .iter()
+            .map(|data| bincode::serialized_size(data).unwrap() as usize)
+            .sum();
+        println!(
+            "{} {}: {} bytes ({:.1}% of {} bytes)",
+            name,
+            algorithm,
+            size,
+            size as f64 * 100.0 / raw_size as f64,
+            raw_size
+        );
+
+        group.bench_function(algorithm.to_string(), |b| {
+            b.iter_batched(
+                || compressed.clone(),
+                |compressed| {
+                    for data in compressed {
+                        decompress(data).unwrap();
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+#[allow(unused_variables)]
+fn bench_compression(c: &mut Criterion) {
+    #[cfg(feature = "ipadic")]
+    {
+        use lindera::dictionary::{load_dictionary_from_kind, DictionaryKind};
+
+        let dictionary = load_dictionary_from_kind(DictionaryKind::IPADIC).unwrap();
+        bench_compression_of(c, "ipadic", &dictionary);
+    }
+
+    #[cfg(feature = "unidic")]
+    {
+        use lindera::dictionary::{load_dictionary_from_kind, DictionaryKind};
+
+        let dictionary = load_dictionary_from_kind(DictionaryKind::UniDic).unwrap();
+        bench_compression_of(c, "unidic", &dictionary);
+    }
+}
+
 criterion_group!(
     benches,
     bench_constructor,
@@ -649,5 +758,6 @@ criterion_group!(
     bench_tokenize_with_simple_userdic,
     bench_tokenize_long_text,
     bench_tokenize_details_long_text,
+    bench_compression,
 );
 criterion_main!(benches);
@@ -6,7 +6,7 @@ fn main() -> LinderaResult<()> {
         use std::path::PathBuf;
 
         use lindera::dictionary::{
-            load_dictionary_from_kind, load_user_dictionary_from_csv, DictionaryKind,
+            load_dictionary_from_kind, load_user_dictionary_from_csv_for_dictionary, DictionaryKind,
         };
         use lindera::mode::Mode;
         use lindera::segmenter::Segmenter;
@@ -17,8 +17,11 @@ fn main() -> LinderaResult<()> {
             .join("ipadic_simple_userdic.csv");
 
         let dictionary = load_dictionary_from_kind(DictionaryKind::IPADIC)?;
-        let user_dictionary =
-            load_user_dictionary_from_csv(DictionaryKind::IPADIC, user_dict_path.as_path())?;
+        let user_dictionary = load_user_dictionary_from_csv_for_dictionary(
+            DictionaryKind::IPADIC,
+            user_dict_path.as_path(),
+            &dictionary,
+        )?;
         let segmenter = Segmenter::new(
             Mode::Normal,
             dictionary,
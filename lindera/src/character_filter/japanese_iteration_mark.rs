@@ -21,8 +21,12 @@ fn hiragana_add_dakuon(c: &char) -> char {
     // Unsafe code is okay, because we know that all the characters within these ranges exist.
     match codepoint {
         0x304b..=0x3062 if codepoint % 2 == 1 => unsafe { char::from_u32_unchecked(codepoint + 1) },
-        0x3064..=0x3069 if codepoint % 2 == 0 => unsafe { char::from_u32_unchecked(codepoint + 1) },
-        0x306f..=0x307d if codepoint % 3 == 0 => unsafe { char::from_u32_unchecked(codepoint + 1) },
+        0x3064..=0x3069 if codepoint.is_multiple_of(2) => unsafe {
+            char::from_u32_unchecked(codepoint + 1)
+        },
+        0x306f..=0x307d if codepoint.is_multiple_of(3) => unsafe {
+            char::from_u32_unchecked(codepoint + 1)
+        },
         _ => *c,
     }
 }
@@ -31,7 +35,9 @@ fn hiragana_remove_dakuon(c: &char) -> char {
     let codepoint = *c as u32;
     // Unsafe code is okay, because we know that all the characters within these ranges exist.
     match codepoint {
-        0x304b..=0x3062 if codepoint % 2 == 0 => unsafe { char::from_u32_unchecked(codepoint - 1) },
+        0x304b..=0x3062 if codepoint.is_multiple_of(2) => unsafe {
+            char::from_u32_unchecked(codepoint - 1)
+        },
         0x3064..=0x3069 if codepoint % 2 == 1 => unsafe { char::from_u32_unchecked(codepoint - 1) },
         0x306f..=0x307d if codepoint % 3 == 1 => unsafe { char::from_u32_unchecked(codepoint - 1) },
         _ => *c,
@@ -42,8 +48,12 @@ fn katakana_add_dakuon(c: &char) -> char {
     let codepoint = *c as u32;
     match codepoint {
         0x30ab..=0x30c2 if codepoint % 2 == 1 => unsafe { char::from_u32_unchecked(codepoint + 1) },
-        0x30c4..=0x30c9 if codepoint % 2 == 0 => unsafe { char::from_u32_unchecked(codepoint + 1) },
-        0x30cf..=0x30dd if codepoint % 3 == 0 => unsafe { char::from_u32_unchecked(codepoint + 1) },
+        0x30c4..=0x30c9 if codepoint.is_multiple_of(2) => unsafe {
+            char::from_u32_unchecked(codepoint + 1)
+        },
+        0x30cf..=0x30dd if codepoint.is_multiple_of(3) => unsafe {
+            char::from_u32_unchecked(codepoint + 1)
+        },
         _ => *c,
     }
 }
@@ -51,7 +61,9 @@ fn katakana_add_dakuon(c: &char) -> char {
 fn katakana_remove_dakuon(c: &char) -> char {
     let codepoint = *c as u32;
     match codepoint {
-        0x30ab..=0x30c2 if codepoint % 2 == 0 => unsafe { char::from_u32_unchecked(codepoint - 1) },
+        0x30ab..=0x30c2 if codepoint.is_multiple_of(2) => unsafe {
+            char::from_u32_unchecked(codepoint - 1)
+        },
         0x30c4..=0x30c9 if codepoint % 2 == 1 => unsafe { char::from_u32_unchecked(codepoint - 1) },
         0x30cf..=0x30dd if codepoint % 3 == 1 => unsafe { char::from_u32_unchecked(codepoint - 1) },
         _ => *c,
@@ -258,21 +270,21 @@ mod tests {
     fn hiragana_has_dakuon(c: &char) -> bool {
         let codepoint = *c as u32;
         // か…ぢ
-        (codepoint >= 0x304b && codepoint <= 0x3062 && codepoint % 2 == 0) ||
+        ((0x304b..=0x3062).contains(&codepoint) && codepoint.is_multiple_of(2)) ||
         // つ…ど
-        (codepoint >= 0x3064 && codepoint <= 0x3069 && codepoint % 2 == 1) ||
+        ((0x3064..=0x3069).contains(&codepoint) && codepoint % 2 == 1) ||
         // は…ぽ
-        (codepoint >= 0x306f && codepoint <= 0x307d && codepoint % 3 == 1)
+        ((0x306f..=0x307d).contains(&codepoint) && codepoint % 3 == 1)
     }
 
     fn katakana_has_dakuon(c: &char) -> bool {
         let codepoint = *c as u32;
         // カ…ヂ
-        (codepoint >= 0x30ab && codepoint <= 0x30c2 && codepoint % 2 == 0) ||
+        ((0x30ab..=0x30c2).contains(&codepoint) && codepoint.is_multiple_of(2)) ||
         // ツ…ド
-        (codepoint >= 0x30c4 && codepoint <= 0x30c9 && codepoint % 2 == 1) ||
+        ((0x30c4..=0x30c9).contains(&codepoint) && codepoint % 2 == 1) ||
         // ハ…ポ
-        (codepoint >= 0x30cf && codepoint <= 0x30dd && codepoint % 3 == 1)
+        ((0x30cf..=0x30dd).contains(&codepoint) && codepoint % 3 == 1)
     }
 
     static HIRAGANA_DAKUON_MAP: Lazy<HashMap<char, char>> = Lazy::new(|| {
@@ -375,7 +387,7 @@ mod tests {
         "#;
         let result: Result<JapaneseIterationMarkCharacterFilterConfig, _> =
             serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
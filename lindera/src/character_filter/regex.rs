@@ -161,7 +161,7 @@ mod tests {
         }
         "#;
         let result: Result<RegexCharacterFilterConfig, _> = serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -175,7 +175,7 @@ mod tests {
         let config: RegexCharacterFilterConfig = serde_json::from_str(config_str).unwrap();
 
         let result = RegexCharacterFilter::from_config(&config);
-        assert_eq!(true, result.is_ok());
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -186,7 +186,7 @@ mod tests {
         "#;
         let result: Result<UnicodeNormalizeCharacterFilterConfig, _> =
             serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -200,7 +200,7 @@ mod tests {
             serde_json::from_str::<UnicodeNormalizeCharacterFilterConfig>(config_str).unwrap();
 
         let result = UnicodeNormalizeCharacterFilter::from_config(&config);
-        assert_eq!(true, result.is_ok());
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -1,5 +1,9 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -11,21 +15,33 @@ use lindera_dictionary::dictionary_builder::ipadic::IpadicBuilder;
 use lindera_dictionary::dictionary_builder::ipadic_neologd::IpadicNeologdBuilder;
 use lindera_dictionary::dictionary_builder::ko_dic::KoDicBuilder;
 use lindera_dictionary::dictionary_builder::unidic::UnidicBuilder;
-use lindera_dictionary::dictionary_builder::DictionaryBuilder;
+use lindera_dictionary::dictionary_builder::{build_user_dictionary, DictionaryBuilder};
+use lindera_dictionary::dictionary_dumper::DictionaryDumper;
 use lindera_dictionary::dictionary_loader::character_definition::CharacterDefinitionLoader;
 use lindera_dictionary::dictionary_loader::connection_cost_matrix::ConnectionCostMatrixLoader;
+use lindera_dictionary::dictionary_loader::context_id::ContextIdsLoader;
+use lindera_dictionary::dictionary_loader::metadata::MetadataLoader;
 use lindera_dictionary::dictionary_loader::prefix_dictionary::PrefixDictionaryLoader;
+use lindera_dictionary::dictionary_loader::reading_index::ReadingIndexLoader;
 use lindera_dictionary::dictionary_loader::unknown_dictionary::UnknownDictionaryLoader;
-use lindera_dictionary::util::read_file;
+use lindera_dictionary::util::{read_file, Deferred};
 
 use crate::error::{LinderaError, LinderaErrorKind};
 use crate::LinderaResult;
 
 pub type Dictionary = lindera_dictionary::dictionary::Dictionary;
 pub type UserDictionary = lindera_dictionary::dictionary::UserDictionary;
+pub type UserDictionaryEntries = lindera_dictionary::dictionary_builder::UserDictionaryEntries;
 pub type WordId = lindera_dictionary::viterbi::WordId;
+pub type LookupEntry<'a> = lindera_dictionary::dictionary::LookupEntry<'a>;
+pub type Metadata = lindera_dictionary::dictionary::metadata::Metadata;
+pub type ContextIds = lindera_dictionary::dictionary::context_id::ContextIds;
+pub type DictionaryFile = lindera_dictionary::dictionary_file::DictionaryFile;
+pub type CompressAlgorithm = lindera_dictionary::decompress::Algorithm;
+
+pub const DICTIONARY_FILE_EXTENSION: &str = lindera_dictionary::dictionary_file::EXTENSION;
 
-#[derive(Debug, Clone, EnumIter, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, EnumIter, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub enum DictionaryKind {
     #[serde(rename = "ipadic")]
     IPADIC,
@@ -98,16 +114,69 @@ pub fn resolve_builder(
     }
 }
 
+/// Loads an external dictionary from a dictionary directory or a `.linderadic` dictionary file.
 pub fn load_dictionary_from_path(path: &Path) -> LinderaResult<Dictionary> {
+    if path.is_file() {
+        return DictionaryFile::read(path)?.load_dictionary();
+    }
+
     Ok(Dictionary {
         prefix_dictionary: PrefixDictionaryLoader::load(path)?,
         connection_cost_matrix: ConnectionCostMatrixLoader::load(path)?,
         character_definition: CharacterDefinitionLoader::load(path)?,
         unknown_dictionary: UnknownDictionaryLoader::load(path)?,
+        metadata: MetadataLoader::load(path)?.map(Deferred::from_value),
+        reading_index: ReadingIndexLoader::load(path)?,
+        context_ids: ContextIdsLoader::load(path)?,
     })
 }
 
-pub fn load_dictionary_from_kind(kind: DictionaryKind) -> LinderaResult<Dictionary> {
+/// Loads an external dictionary, memory-mapping the prefix dictionary and the connection cost
+/// matrix instead of reading them into memory.
+pub fn load_dictionary_from_path_mmap(path: &Path) -> LinderaResult<Dictionary> {
+    if path.is_file() {
+        return DictionaryFile::map(path)?.load_dictionary();
+    }
+
+    Ok(Dictionary {
+        prefix_dictionary: PrefixDictionaryLoader::load_mmap(path)?,
+        connection_cost_matrix: ConnectionCostMatrixLoader::load_mmap(path)?,
+        character_definition: CharacterDefinitionLoader::load(path)?,
+        unknown_dictionary: UnknownDictionaryLoader::load(path)?,
+        metadata: MetadataLoader::load(path)?.map(Deferred::from_value),
+        reading_index: ReadingIndexLoader::load_mmap(path)?,
+        context_ids: ContextIdsLoader::load(path)?,
+    })
+}
+
+// The contained dictionaries loaded so far, shared by everything that uses them.
+static DICTIONARY_CACHE: Lazy<Mutex<HashMap<DictionaryKind, Arc<Dictionary>>>> =
+    Lazy::new(Default::default);
+
+/// Loads a contained dictionary.
+///
+/// Each dictionary is loaded once per process; later calls return the same shared instance.
+pub fn load_dictionary_from_kind(kind: DictionaryKind) -> LinderaResult<Arc<Dictionary>> {
+    load_cached_dictionary(&DICTIONARY_CACHE, kind, load_contained_dictionary)
+}
+
+fn load_cached_dictionary(
+    cache: &Mutex<HashMap<DictionaryKind, Arc<Dictionary>>>,
+    kind: DictionaryKind,
+    load: impl FnOnce(&DictionaryKind) -> LinderaResult<Dictionary>,
+) -> LinderaResult<Arc<Dictionary>> {
+    // A poisoned cache still holds fully loaded dictionaries only.
+    let mut cache = cache.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(dictionary) = cache.get(&kind) {
+        return Ok(Arc::clone(dictionary));
+    }
+
+    let dictionary = Arc::new(load(&kind)?);
+    cache.insert(kind, Arc::clone(&dictionary));
+    Ok(dictionary)
+}
+
+fn load_contained_dictionary(kind: &DictionaryKind) -> LinderaResult<Dictionary> {
     // The dictionary specified by the feature flag will be loaded.
     match kind {
         #[cfg(feature = "ipadic")]
@@ -136,7 +205,7 @@ pub fn load_dictionary_from_kind(kind: DictionaryKind) -> LinderaResult<Dictiona
 
 pub fn load_dictionary_from_config(
     dictionary_config: &DictionaryConfig,
-) -> LinderaResult<Dictionary> {
+) -> LinderaResult<Arc<Dictionary>> {
     match dictionary_config.get("kind") {
         Some(kind_value) => {
             let kind = DictionaryKind::from_str(kind_value.as_str().ok_or_else(|| {
@@ -154,8 +223,21 @@ pub fn load_dictionary_from_config(
                             .with_error(anyhow::anyhow!("path field must be a string"))
                     })?);
 
+                    let mmap = match dictionary_config.get("mmap") {
+                        Some(mmap_value) => mmap_value.as_bool().ok_or_else(|| {
+                            LinderaErrorKind::Parse
+                                .with_error(anyhow::anyhow!("mmap field must be a boolean"))
+                        })?,
+                        None => false,
+                    };
+
                     // load external dictionary from path
-                    load_dictionary_from_path(path.as_path())
+                    let dictionary = if mmap {
+                        load_dictionary_from_path_mmap(path.as_path())?
+                    } else {
+                        load_dictionary_from_path(path.as_path())?
+                    };
+                    Ok(Arc::new(dictionary))
                 }
                 None => Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
                     "kind field or path field must be specified"
@@ -165,13 +247,41 @@ pub fn load_dictionary_from_config(
     }
 }
 
+/// Loads a user dictionary from a CSV file, without the system dictionary it is for.
+///
+/// The simple entries get the fixed context id 0 and cost -10000 of the legacy behavior, which
+/// connects them poorly to the words around them.
+#[deprecated(
+    note = "use `load_user_dictionary_from_csv_for_dictionary`, which gives the simple entries the context ids of their part of speech"
+)]
 pub fn load_user_dictionary_from_csv(
     kind: DictionaryKind,
     path: &Path,
+) -> LinderaResult<UserDictionary> {
+    load_user_dictionary_from_csv_with_context_ids(kind, path, None)
+}
+
+/// Loads a user dictionary from a CSV file for the given system dictionary. If it was built with
+/// context ids, the simple entries get the context ids of their part of speech and a cost
+/// estimated from its words.
+pub fn load_user_dictionary_from_csv_for_dictionary(
+    kind: DictionaryKind,
+    path: &Path,
+    dictionary: &Dictionary,
+) -> LinderaResult<UserDictionary> {
+    load_user_dictionary_from_csv_with_context_ids(kind, path, dictionary.context_ids()?.cloned())
+}
+
+fn load_user_dictionary_from_csv_with_context_ids(
+    kind: DictionaryKind,
+    path: &Path,
+    context_ids: Option<ContextIds>,
 ) -> LinderaResult<UserDictionary> {
     let builder = resolve_builder(kind)?;
     builder
-        .build_user_dict(path)
+        .user_dictionary_builder()
+        .with_context_ids(context_ids)
+        .build(path)
         .map_err(|err| LinderaErrorKind::Build.with_error(err))
 }
 
@@ -179,8 +289,69 @@ pub fn load_user_dictionary_from_bin(path: &Path) -> LinderaResult<UserDictionar
     UserDictionary::load(&read_file(path)?)
 }
 
+/// Returns a builder of a user dictionary of the given kind, whose entries are added in memory or
+/// read from any reader instead of a CSV file. Give it the context ids of the system dictionary
+/// with `with_context_ids`, or its simple entries get the fixed context id and cost.
+pub fn user_dictionary_builder(kind: DictionaryKind) -> LinderaResult<UserDictionaryEntries> {
+    Ok(UserDictionary::builder(resolve_builder(kind)?.as_ref()))
+}
+
+/// Writes a user dictionary to a `.bin` file, which `load_user_dictionary_from_bin` loads.
+pub fn save_user_dictionary_to_bin(
+    user_dictionary: UserDictionary,
+    path: &Path,
+) -> LinderaResult<()> {
+    build_user_dictionary(user_dictionary, path)
+}
+
+/// Writes the source files of a dictionary to `output_dir`, in the encoding it was built from, so
+/// that building them again yields the same dictionary.
+pub fn dump_dictionary(dictionary: &Dictionary, output_dir: &Path) -> LinderaResult<()> {
+    DictionaryDumper::from_metadata(dictionary.metadata()?).dump(dictionary, output_dir)
+}
+
+/// Writes the entries of a user dictionary of the given kind to a CSV file of detailed entries,
+/// and its words with a custom segmentation as segmentation entries.
+pub fn dump_user_dictionary(
+    user_dictionary: &UserDictionary,
+    kind: DictionaryKind,
+    output_file: &Path,
+) -> LinderaResult<()> {
+    lindera_dictionary::dictionary_dumper::dump_user_dictionary(
+        user_dictionary,
+        &resolve_builder(kind)?.user_dictionary_builder(),
+        output_file,
+    )
+}
+
+/// Loads a user dictionary from a config, without the system dictionary it is for.
+///
+/// The simple entries of a CSV file get the fixed context id 0 and cost -10000 of the legacy
+/// behavior, which connects them poorly to the words around them.
+#[deprecated(
+    note = "use `load_user_dictionary_from_config_for_dictionary`, which gives the simple entries the context ids of their part of speech"
+)]
 pub fn load_user_dictionary_from_config(
     dictionary_config: &UserDictionaryConfig,
+) -> LinderaResult<UserDictionary> {
+    load_user_dictionary_from_config_with_context_ids(dictionary_config, None)
+}
+
+/// Loads a user dictionary from a config for the given system dictionary. The simple entries of a
+/// CSV file get context ids and costs like with `load_user_dictionary_from_csv_for_dictionary`.
+pub fn load_user_dictionary_from_config_for_dictionary(
+    dictionary_config: &UserDictionaryConfig,
+    dictionary: &Dictionary,
+) -> LinderaResult<UserDictionary> {
+    load_user_dictionary_from_config_with_context_ids(
+        dictionary_config,
+        dictionary.context_ids()?.cloned(),
+    )
+}
+
+fn load_user_dictionary_from_config_with_context_ids(
+    dictionary_config: &UserDictionaryConfig,
+    context_ids: Option<ContextIds>,
 ) -> LinderaResult<UserDictionary> {
     match dictionary_config.get("path") {
         Some(path_value) => {
@@ -199,7 +370,11 @@ pub fn load_user_dictionary_from_config(
                                 },
                             )?)?;
 
-                            load_user_dictionary_from_csv(kind, path.as_path())
+                            load_user_dictionary_from_csv_with_context_ids(
+                                kind,
+                                path.as_path(),
+                                context_ids,
+                            )
                         }
                         None => Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
                             "kind field must be specified if CSV file specified"
@@ -219,3 +394,67 @@ pub fn load_user_dictionary_from_config(
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_load_cached_dictionary() {
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+
+        use crate::dictionary::{load_cached_dictionary, DictionaryKind};
+        use crate::error::LinderaErrorKind;
+        use crate::test_utils::mini_ipadic;
+
+        let cache = Mutex::new(HashMap::new());
+        let mut loads = 0;
+        let mut load = |_: &DictionaryKind| {
+            loads += 1;
+            Ok(mini_ipadic())
+        };
+
+        let first = load_cached_dictionary(&cache, DictionaryKind::IPADIC, &mut load).unwrap();
+        let second = load_cached_dictionary(&cache, DictionaryKind::IPADIC, &mut load).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(loads, 1);
+
+        // Failed loads are not cached.
+        let result = load_cached_dictionary(&cache, DictionaryKind::KoDic, |_| {
+            Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!("not contained")))
+        });
+        assert!(result.is_err());
+        assert!(!cache.lock().unwrap().contains_key(&DictionaryKind::KoDic));
+    }
+
+    #[test]
+    fn test_user_dictionary_builder() {
+        use crate::dictionary::{
+            load_user_dictionary_from_bin, save_user_dictionary_to_bin, user_dictionary_builder,
+            DictionaryKind,
+        };
+        use crate::test_utils::temp_dir;
+
+        let user_dictionary = user_dictionary_builder(DictionaryKind::IPADIC)
+            .unwrap()
+            .add_simple("東京スカイツリー", "カスタム名詞", "トウキョウスカイツリー")
+            .read_csv(
+                "とうきょうスカイツリー駅,カスタム名詞,トウキョウスカイツリーエキ\n".as_bytes(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let dir = temp_dir("user-dictionary-builder");
+        let path = dir.join("userdic.bin");
+        save_user_dictionary_to_bin(user_dictionary, &path).unwrap();
+        let user_dictionary = load_user_dictionary_from_bin(&path).unwrap();
+        assert_eq!(
+            user_dictionary.lookup("東京スカイツリー")[0].details[7],
+            "トウキョウスカイツリー"
+        );
+        assert_eq!(
+            user_dictionary.lookup("とうきょうスカイツリー駅")[0].details[7],
+            "トウキョウスカイツリーエキ"
+        );
+    }
+}
@@ -2,7 +2,10 @@ pub mod character_filter;
 pub mod dictionary;
 pub mod error;
 pub mod mode;
+pub mod reload;
 pub mod segmenter;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod token;
 pub mod token_filter;
 pub mod tokenizer;
@@ -0,0 +1,279 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+
+use crate::tokenizer::Tokenizer;
+use crate::LinderaResult;
+
+/// A tokenizer shared between threads whose user dictionaries can be reloaded while it is in use.
+///
+/// Tokenizing goes through a snapshot returned by `load`: a reload swaps in a new tokenizer
+/// atomically, and the snapshots taken before keep the user dictionaries they were taken with, so
+/// that in-flight tokenizations stay consistent. The system dictionary is shared between the
+/// snapshots rather than reloaded.
+pub struct ReloadableTokenizer {
+    tokenizer: ArcSwap<Tokenizer>,
+    /// Serializes the updates, so that concurrent reloads of different files are not lost.
+    update_lock: Mutex<()>,
+}
+
+impl ReloadableTokenizer {
+    pub fn new(tokenizer: Tokenizer) -> Self {
+        Self {
+            tokenizer: ArcSwap::from_pointee(tokenizer),
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns a snapshot of the current tokenizer. It is not affected by later reloads.
+    pub fn load(&self) -> Arc<Tokenizer> {
+        self.tokenizer.load_full()
+    }
+
+    /// Replaces the tokenizer.
+    pub fn store(&self, tokenizer: Tokenizer) {
+        let _guard = self
+            .update_lock
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        self.tokenizer.store(Arc::new(tokenizer));
+    }
+
+    /// Reloads the user dictionaries that were loaded from the file at `path`, and swaps in a
+    /// tokenizer using them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no user dictionary was loaded from `path`, or if it cannot be loaded.
+    /// The current tokenizer is kept in that case.
+    pub fn reload_user_dictionary(&self, path: &Path) -> LinderaResult<()> {
+        let _guard = self
+            .update_lock
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let mut tokenizer = Tokenizer::clone(&self.tokenizer.load());
+        tokenizer.segmenter.reload_user_dictionary(path)?;
+        self.tokenizer.store(Arc::new(tokenizer));
+        Ok(())
+    }
+
+    /// Watches the files the user dictionaries were loaded from, and reloads a dictionary when its
+    /// file is written or replaced.
+    ///
+    /// `on_reload` is called from the watching thread with the path and the result of each reload.
+    /// A file may be seen while it is being written, in which case the reload fails and the next
+    /// change of the file reloads it again. The watching stops when the returned watcher is dropped.
+    #[cfg(feature = "watch")]
+    pub fn watch_user_dictionaries<F>(self: &Arc<Self>, on_reload: F) -> LinderaResult<Watcher>
+    where
+        F: Fn(&Path, LinderaResult<()>) + Send + 'static,
+    {
+        use std::collections::{BTreeMap, BTreeSet};
+
+        use notify::{EventKind, RecursiveMode, Watcher as _};
+
+        use crate::error::LinderaErrorKind;
+
+        // The directories are watched rather than the files, so that files replaced by a rename
+        // are still seen. The events name the files under the watched directory.
+        let mut paths = BTreeMap::new();
+        for user_dictionary in self.load().segmenter.user_dictionaries.iter() {
+            let Some(path) = user_dictionary.source_path() else {
+                continue;
+            };
+            let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+                continue;
+            };
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            let dir = dir
+                .canonicalize()
+                .map_err(|err| LinderaErrorKind::Io.with_error(err))?;
+            paths.insert(dir.join(file_name), path);
+        }
+        let dirs = paths
+            .keys()
+            .filter_map(|path| path.parent())
+            .map(Path::to_path_buf)
+            .collect::<BTreeSet<_>>();
+
+        let tokenizer = Arc::downgrade(self);
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    return;
+                }
+                let Some(tokenizer) = tokenizer.upgrade() else {
+                    return;
+                };
+                let reloaded = event
+                    .paths
+                    .iter()
+                    .filter_map(|path| paths.get(path))
+                    .collect::<BTreeSet<_>>();
+                for path in reloaded {
+                    on_reload(path, tokenizer.reload_user_dictionary(path));
+                }
+            })
+            .map_err(|err| LinderaErrorKind::Io.with_error(err))?;
+        for dir in dirs {
+            watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(|err| LinderaErrorKind::Io.with_error(err))?;
+        }
+
+        Ok(Watcher { _watcher: watcher })
+    }
+}
+
+impl From<Tokenizer> for ReloadableTokenizer {
+    fn from(tokenizer: Tokenizer) -> Self {
+        Self::new(tokenizer)
+    }
+}
+
+/// Watches the files of the user dictionaries of a `ReloadableTokenizer`, until it is dropped.
+#[cfg(feature = "watch")]
+pub struct Watcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_reload_user_dictionary() {
+        use std::borrow::Cow;
+
+        use crate::error::LinderaErrorKind;
+        use crate::reload::ReloadableTokenizer;
+        use crate::test_utils::{mini_ipadic_path, temp_dir};
+        use crate::tokenizer::{Tokenizer, TokenizerConfig};
+
+        let dictionary_path = mini_ipadic_path();
+
+        let dir = temp_dir("reload-user-dictionary");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("userdic.csv");
+        std::fs::write(
+            &path,
+            "東京都庁,4,4,1000,名詞,固有名詞,組織,*,*,*,東京都庁,トウキョウトチョウ,トーキョートチョー\n",
+        )
+        .unwrap();
+
+        let config: TokenizerConfig = serde_json::json!({
+            "segmenter": {
+                "dictionary": { "path": &*dictionary_path },
+                "user_dictionaries": [{ "path": path, "kind": "ipadic", "cost_bias": -100 }]
+            }
+        });
+        let tokenizer = ReloadableTokenizer::new(Tokenizer::from_config(&config).unwrap());
+
+        let before = tokenizer.load();
+        let mut tokens = before.tokenize("東京都庁に行く").unwrap();
+        assert_eq!(tokens[0].details()[7], "トウキョウトチョウ");
+
+        std::fs::write(
+            &path,
+            "東京都庁,4,4,1000,名詞,固有名詞,組織,*,*,*,東京都庁,トチョウ,トチョー\n",
+        )
+        .unwrap();
+        tokenizer.reload_user_dictionary(&path).unwrap();
+
+        // New snapshots use the reloaded dictionary, with the same cost bias.
+        let after = tokenizer.load();
+        let mut tokens = after.tokenize("東京都庁に行く").unwrap();
+        assert_eq!(tokens[0].details()[7], "トチョウ");
+        assert_eq!(after.segmenter.user_dictionaries[0].cost_bias, -100);
+        assert!(std::sync::Arc::ptr_eq(
+            &before.segmenter.dictionary,
+            &after.segmenter.dictionary
+        ));
+
+        // The earlier snapshot is left as it was.
+        let mut tokens = before
+            .segmenter
+            .segment(Cow::Borrowed("東京都庁に行く"))
+            .unwrap();
+        assert_eq!(tokens[0].details()[7], "トウキョウトチョウ");
+
+        // A failed reload keeps the current tokenizer.
+        std::fs::write(&path, "東京都庁,4,4,cost\n").unwrap();
+        assert!(tokenizer.reload_user_dictionary(&path).is_err());
+        let current = tokenizer.load();
+        let mut tokens = current.tokenize("東京都庁に行く").unwrap();
+        assert_eq!(tokens[0].details()[7], "トチョウ");
+
+        assert_eq!(
+            tokenizer
+                .reload_user_dictionary(&dir.join("other.csv"))
+                .unwrap_err()
+                .kind(),
+            LinderaErrorKind::NotFound
+        );
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn test_watch_user_dictionaries() {
+        use std::sync::mpsc;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use crate::reload::ReloadableTokenizer;
+        use crate::test_utils::{mini_ipadic_path, temp_dir};
+        use crate::tokenizer::{Tokenizer, TokenizerConfig};
+
+        let dictionary_path = mini_ipadic_path();
+
+        let dir = temp_dir("watch-user-dictionaries");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("userdic.csv");
+        std::fs::write(
+            &path,
+            "東京都庁,4,4,1000,名詞,固有名詞,組織,*,*,*,東京都庁,トウキョウトチョウ,トーキョートチョー\n",
+        )
+        .unwrap();
+
+        let config: TokenizerConfig = serde_json::json!({
+            "segmenter": {
+                "dictionary": { "path": &*dictionary_path },
+                "user_dictionary": { "path": path, "kind": "ipadic" }
+            }
+        });
+        let tokenizer = Arc::new(ReloadableTokenizer::new(
+            Tokenizer::from_config(&config).unwrap(),
+        ));
+        let (sender, receiver) = mpsc::channel();
+        let _watcher = tokenizer
+            .watch_user_dictionaries(move |path, result| {
+                let _ = sender.send((path.to_path_buf(), result.is_ok()));
+            })
+            .unwrap();
+
+        std::fs::write(
+            &path,
+            "東京都庁,4,4,1000,名詞,固有名詞,組織,*,*,*,東京都庁,トチョウ,トチョー\n",
+        )
+        .unwrap();
+
+        // The file may be seen while it is being written, so wait for a successful reload.
+        loop {
+            let (reloaded_path, ok) = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+            assert_eq!(reloaded_path, path);
+            if ok {
+                let current = tokenizer.load();
+                let mut tokens = current.tokenize("東京都庁に行く").unwrap();
+                if tokens[0].details()[7] == "トチョウ" {
+                    break;
+                }
+            }
+        }
+    }
+}
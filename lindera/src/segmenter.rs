@@ -1,18 +1,154 @@
 use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use lindera_dictionary::mode::Mode;
 
-use lindera_dictionary::dictionary::{Dictionary, UserDictionary};
-use lindera_dictionary::viterbi::Lattice;
+use lindera_dictionary::dictionary::{Dictionary, LookupEntry, UserDictionary};
+use lindera_dictionary::viterbi::{
+    Lattice, LatticeConstraints, LatticeDump, LatticeSpan, Pruning, UnknownWordMode,
+    UserDictionaryLayer, WordId, MAX_USER_DICTIONARIES, MAX_USER_DICTIONARY_ENTRIES,
+};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::dictionary::{load_dictionary_from_config, load_user_dictionary_from_config};
+use crate::dictionary::{
+    load_dictionary_from_config, load_user_dictionary_from_config_for_dictionary,
+    UserDictionaryConfig,
+};
 use crate::error::LinderaErrorKind;
 use crate::token::Token;
 use crate::LinderaResult;
 
 pub type SegmenterConfig = Value;
 
+/// A span of the input text that must be segmented as a single token.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanConstraint {
+    /// Byte offset of the start of the span.
+    pub start: usize,
+    /// Byte offset of the end of the span.
+    pub end: usize,
+    /// The part-of-speech the token must have, as leading comma-separated detail fields (e.g. `名詞,固有名詞`).
+    #[serde(default)]
+    pub pos: Option<String>,
+}
+
+/// Constraints for partial analysis, given as byte offsets in the input text.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Constraints {
+    /// Spans that must be a single token.
+    #[serde(default)]
+    pub spans: Vec<SpanConstraint>,
+    /// Positions where a token must start or end.
+    #[serde(default)]
+    pub forced_boundaries: Vec<usize>,
+    /// Positions where a token must not start or end.
+    #[serde(default)]
+    pub forbidden_boundaries: Vec<usize>,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+            && self.forced_boundaries.is_empty()
+            && self.forbidden_boundaries.is_empty()
+    }
+
+    /// Checks that the constraints are consistent with each other and with the text.
+    fn validate(&self, text: &str) -> LinderaResult<()> {
+        let invalid =
+            |message: String| Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(message)));
+
+        let mut spans = self.spans.iter().collect::<Vec<_>>();
+        spans.sort_by_key(|span| span.start);
+        for (i, span) in spans.iter().enumerate() {
+            if span.start >= span.end
+                || span.end > text.len()
+                || !text.is_char_boundary(span.start)
+                || !text.is_char_boundary(span.end)
+            {
+                return invalid(format!("invalid span: {}..{}", span.start, span.end));
+            }
+            if i > 0 && spans[i - 1].end > span.start {
+                return invalid(format!(
+                    "overlapping spans: {}..{} and {}..{}",
+                    spans[i - 1].start,
+                    spans[i - 1].end,
+                    span.start,
+                    span.end
+                ));
+            }
+        }
+
+        for &boundary in self
+            .forced_boundaries
+            .iter()
+            .chain(self.forbidden_boundaries.iter())
+        {
+            if boundary > text.len() || !text.is_char_boundary(boundary) {
+                return invalid(format!("invalid boundary: {}", boundary));
+            }
+        }
+        for &boundary in &self.forced_boundaries {
+            if spans
+                .iter()
+                .any(|span| span.start < boundary && boundary < span.end)
+            {
+                return invalid(format!("forced boundary inside a span: {}", boundary));
+            }
+        }
+        for &boundary in &self.forbidden_boundaries {
+            if 0 < boundary
+                && boundary < text.len()
+                && (self.forced_boundaries.contains(&boundary)
+                    || spans
+                        .iter()
+                        .any(|span| span.start == boundary || span.end == boundary))
+            {
+                return invalid(format!("forbidden boundary is also required: {}", boundary));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the text may be split into sentences at `position`.
+    fn can_split_at(&self, position: usize) -> bool {
+        !self.forbidden_boundaries.contains(&position)
+            && !self
+                .spans
+                .iter()
+                .any(|span| span.start < position && position < span.end)
+    }
+}
+
+/// A user dictionary of a segmenter, with the bias added to the costs of its words.
+#[derive(Clone)]
+pub struct LayeredUserDictionary {
+    /// The user dictionary. It is shared between clones of the segmenter.
+    pub dictionary: Arc<UserDictionary>,
+    /// The bias added to the costs of the words of the dictionary. A negative bias makes its words
+    /// win over those of the other dictionaries.
+    pub cost_bias: i16,
+    /// The config the dictionary was loaded from, used to reload it. `None` if it was not loaded
+    /// from a config.
+    pub source: Option<UserDictionaryConfig>,
+}
+
+impl LayeredUserDictionary {
+    /// Returns the path of the file the dictionary was loaded from, if any.
+    pub fn source_path(&self) -> Option<PathBuf> {
+        self.source
+            .as_ref()?
+            .get("path")?
+            .as_str()
+            .map(PathBuf::from)
+    }
+}
+
 /// Segmenter
 #[derive(Clone)]
 pub struct Segmenter {
@@ -22,12 +158,29 @@ pub struct Segmenter {
 
     /// The dictionary used for segmenting text. This dictionary contains the necessary
     /// data structures and algorithms to perform morphological analysis and tokenization.
-    pub dictionary: Dictionary,
+    /// It is shared between clones of the segmenter.
+    pub dictionary: Arc<Dictionary>,
+
+    /// The user-defined dictionaries that can be used to customize the segmentation process.
+    /// They are used in addition to the default dictionary to improve the accuracy of segmentation
+    /// for specific words or phrases, and are consulted in order, so that the entries of the first
+    /// ones come first in lookups. Tokens record the index of the dictionary of their word.
+    pub user_dictionaries: Vec<LayeredUserDictionary>,
+
+    /// The temperature used to compute the confidence of each token with the forward-backward
+    /// algorithm. If `None`, the confidence is not computed.
+    pub confidence_temperature: Option<f64>,
+
+    /// Limits on the size of the lattice, to bound the memory and time spent on very long
+    /// sentences.
+    pub pruning: Pruning,
+
+    /// The maximum length of a sentence in characters. Longer sentences are analyzed in chunks,
+    /// split at boundaries the analysis is confident about.
+    pub max_sentence_length: Option<usize>,
 
-    /// An optional user-defined dictionary that can be used to customize the segmentation process.
-    /// If provided, this dictionary will be used in addition to the default dictionary to improve
-    /// the accuracy of segmentation for specific words or phrases.
-    pub user_dictionary: Option<UserDictionary>,
+    /// How the candidates for unknown words are generated from the character definitions.
+    pub unknown_word_mode: UnknownWordMode,
 }
 
 impl Segmenter {
@@ -46,17 +199,28 @@ impl Segmenter {
     /// # Details
     ///
     /// - `mode`: This defines the behavior of the instance, such as whether to process text in normal or aggressive mode.
-    /// - `dictionary`: The main dictionary containing tokenization or processing rules.
+    /// - `dictionary`: The main dictionary containing tokenization or processing rules. It can be a `Dictionary` or an `Arc<Dictionary>` shared with other segmenters.
     /// - `user_dictionary`: This is optional. If provided, it allows the user to extend or override the rules of the main dictionary with custom tokens.
     pub fn new(
         mode: Mode,
-        dictionary: Dictionary,
+        dictionary: impl Into<Arc<Dictionary>>,
         user_dictionary: Option<UserDictionary>,
     ) -> Self {
         Self {
             mode,
-            dictionary,
-            user_dictionary,
+            dictionary: dictionary.into(),
+            user_dictionaries: user_dictionary
+                .map(|user_dictionary| LayeredUserDictionary {
+                    dictionary: Arc::new(user_dictionary),
+                    cost_bias: 0,
+                    source: None,
+                })
+                .into_iter()
+                .collect(),
+            confidence_temperature: None,
+            pruning: Pruning::default(),
+            max_sentence_length: None,
+            unknown_word_mode: UnknownWordMode::default(),
         }
     }
 
@@ -82,11 +246,18 @@ impl Segmenter {
                 LinderaErrorKind::Parse.with_error(anyhow::anyhow!("dictionary field is missing"))
             })?)?;
 
-        // Load the user dictionary from the config
-        let user_dictionary = config
+        // Load the user dictionaries from the config: `user_dictionary` first, then the ones of
+        // `user_dictionaries` in order.
+        let mut user_dictionary_configs = config
             .get("user_dictionary")
-            .map(load_user_dictionary_from_config)
-            .transpose()?;
+            .into_iter()
+            .collect::<Vec<_>>();
+        if let Some(value) = config.get("user_dictionaries") {
+            user_dictionary_configs.extend(value.as_array().ok_or_else(|| {
+                LinderaErrorKind::Parse
+                    .with_error(anyhow::anyhow!("user_dictionaries field must be an array"))
+            })?);
+        }
 
         // Load the mode from the config
         let mode: Mode = config.get("mode").map_or_else(
@@ -99,7 +270,197 @@ impl Segmenter {
             },
         )?;
 
-        Ok(Self::new(mode, dictionary, user_dictionary))
+        // Load the confidence temperature from the config
+        let confidence_temperature = config
+            .get("confidence_temperature")
+            .map(|v| {
+                v.as_f64().ok_or_else(|| {
+                    LinderaErrorKind::Parse.with_error(anyhow::anyhow!(
+                        "confidence_temperature field must be a number"
+                    ))
+                })
+            })
+            .transpose()?;
+
+        // Load the pruning settings from the config
+        let pruning = Pruning {
+            beam_width: get_usize(config, "beam_width")?,
+            max_edges_per_position: get_usize(config, "max_edges_per_position")?,
+        };
+        let max_sentence_length = get_usize(config, "max_sentence_length")?;
+
+        // Load the unknown word mode from the config
+        let unknown_word_mode: UnknownWordMode = config.get("unknown_word_mode").map_or_else(
+            || Ok(UnknownWordMode::default()),
+            |v| {
+                serde_json::from_value(v.clone()).map_err(|e| {
+                    LinderaErrorKind::Parse
+                        .with_error(anyhow::anyhow!("unknown_word_mode field is invalid: {}", e))
+                })
+            },
+        )?;
+
+        let mut segmenter = Self::new(mode, dictionary, None);
+        for user_dictionary_config in user_dictionary_configs {
+            let user_dictionary = load_user_dictionary_from_config_for_dictionary(
+                user_dictionary_config,
+                &segmenter.dictionary,
+            )?;
+            segmenter = segmenter.push_user_dictionary(
+                Arc::new(user_dictionary),
+                get_cost_bias(user_dictionary_config)?,
+                Some(user_dictionary_config.clone()),
+            )?;
+        }
+
+        segmenter
+            .with_unknown_word_mode(unknown_word_mode)
+            .with_confidence_temperature(confidence_temperature)?
+            .with_pruning(pruning)?
+            .with_max_sentence_length(max_sentence_length)
+    }
+
+    /// Adds a user dictionary, consulted after the ones already added.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_dictionary` - The user dictionary. It can be a `UserDictionary` or an `Arc<UserDictionary>` shared with other segmenters.
+    /// * `cost_bias` - The bias added to the costs of the words of the dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the segmenter already has `MAX_USER_DICTIONARIES` user dictionaries, or if the dictionary is not the first one and has more than `MAX_USER_DICTIONARY_ENTRIES` entries.
+    pub fn with_user_dictionary(
+        self,
+        user_dictionary: impl Into<Arc<UserDictionary>>,
+        cost_bias: i16,
+    ) -> LinderaResult<Self> {
+        self.push_user_dictionary(user_dictionary.into(), cost_bias, None)
+    }
+
+    fn push_user_dictionary(
+        mut self,
+        user_dictionary: Arc<UserDictionary>,
+        cost_bias: i16,
+        source: Option<UserDictionaryConfig>,
+    ) -> LinderaResult<Self> {
+        if self.user_dictionaries.len() >= MAX_USER_DICTIONARIES {
+            return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                "too many user dictionaries: at most {} are supported",
+                MAX_USER_DICTIONARIES
+            )));
+        }
+        check_user_dictionary_size(self.user_dictionaries.len(), &user_dictionary)?;
+        self.user_dictionaries.push(LayeredUserDictionary {
+            dictionary: user_dictionary,
+            cost_bias,
+            source,
+        });
+        Ok(self)
+    }
+
+    /// Reloads the user dictionaries that were loaded from the file at `path`, keeping their cost
+    /// bias and their place among the user dictionaries.
+    ///
+    /// The path is compared with the one of the config the dictionaries were loaded from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no user dictionary was loaded from `path`, or if it cannot be loaded.
+    /// The user dictionaries are left unchanged in that case.
+    pub fn reload_user_dictionary(&mut self, path: &Path) -> LinderaResult<()> {
+        let loaded_from_path = |user_dictionary: &LayeredUserDictionary| {
+            user_dictionary.source_path().as_deref() == Some(path)
+        };
+        let source = self
+            .user_dictionaries
+            .iter()
+            .find(|user_dictionary| loaded_from_path(user_dictionary))
+            .and_then(|user_dictionary| user_dictionary.source.as_ref())
+            .ok_or_else(|| {
+                LinderaErrorKind::NotFound.with_error(anyhow::anyhow!(
+                    "no user dictionary was loaded from {:?}",
+                    path
+                ))
+            })?;
+        let dictionary = Arc::new(load_user_dictionary_from_config_for_dictionary(
+            source,
+            &self.dictionary,
+        )?);
+        for (dictionary_index, user_dictionary) in self.user_dictionaries.iter().enumerate() {
+            if loaded_from_path(user_dictionary) {
+                check_user_dictionary_size(dictionary_index, &dictionary)?;
+            }
+        }
+
+        for user_dictionary in self.user_dictionaries.iter_mut() {
+            if loaded_from_path(user_dictionary) {
+                user_dictionary.dictionary = Arc::clone(&dictionary);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets how the candidates for unknown words are generated.
+    ///
+    /// `UnknownWordMode::Mecab` also honors the LENGTH column of char.def, like MeCab.
+    pub fn with_unknown_word_mode(mut self, unknown_word_mode: UnknownWordMode) -> Self {
+        self.unknown_word_mode = unknown_word_mode;
+        self
+    }
+
+    /// Sets the limits on the size of the lattice.
+    ///
+    /// # Arguments
+    ///
+    /// * `pruning` - The beam width and the maximum number of edges per position. See `Pruning` for their meaning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a limit is 0.
+    pub fn with_pruning(mut self, pruning: Pruning) -> LinderaResult<Self> {
+        if pruning.beam_width == Some(0) || pruning.max_edges_per_position == Some(0) {
+            return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                "pruning limits must be positive: {:?}",
+                pruning
+            )));
+        }
+        self.pruning = pruning;
+        Ok(self)
+    }
+
+    /// Sets the maximum length of a sentence, in characters, beyond which it is analyzed in chunks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the length is 0.
+    pub fn with_max_sentence_length(
+        mut self,
+        max_sentence_length: Option<usize>,
+    ) -> LinderaResult<Self> {
+        if max_sentence_length == Some(0) {
+            return Err(LinderaErrorKind::Args
+                .with_error(anyhow::anyhow!("max_sentence_length must be positive")));
+        }
+        self.max_sentence_length = max_sentence_length;
+        Ok(self)
+    }
+
+    /// Sets the temperature used to compute the confidence of each token.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - The temperature, or `None` to disable the confidence. See `Lattice::tokens_confidence` for its meaning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temperature is not a positive finite number.
+    pub fn with_confidence_temperature(mut self, temperature: Option<f64>) -> LinderaResult<Self> {
+        if let Some(temperature) = temperature {
+            check_temperature(temperature)?;
+        }
+        self.confidence_temperature = temperature;
+        Ok(self)
     }
 
     /// Segments the input text into tokens based on the dictionary and user-defined rules.
@@ -139,64 +500,676 @@ impl Segmenter {
     ///
     /// - If the lattice fails to be processed or if there is an issue with the segmentation process, the function returns an error.
     pub fn segment<'a>(&'a self, text: Cow<'a, str>) -> LinderaResult<Vec<Token<'a>>> {
+        self.segment_with_constraints(text, &Constraints::default())
+    }
+
+    /// Segments the input text like `segment`, while respecting the given constraints (MeCab's partial analysis).
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A `Cow<'a, str>` representing the input text.
+    /// * `constraints` - Spans that must be a single token (optionally with a required part-of-speech), and positions where a boundary is forced or forbidden, as byte offsets in `text`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `LinderaResult<Vec<Token<'a>>>` in the same form as `segment`.
+    ///
+    /// # Notes
+    ///
+    /// - Edges that break a constraint are left out of the lattice.
+    /// - A span for which the dictionaries have no word with the required part-of-speech becomes an unknown token whose details are the required part-of-speech.
+    /// - The text is not split into sentences inside a span or at a forbidden boundary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the constraints are inconsistent, out of the text, not on character boundaries, or cannot be satisfied.
+    pub fn segment_with_constraints<'a>(
+        &'a self,
+        text: Cow<'a, str>,
+        constraints: &Constraints,
+    ) -> LinderaResult<Vec<Token<'a>>> {
+        constraints.validate(&text)?;
+
         let mut tokens: Vec<Token> = Vec::new();
-        let mut lattice = Lattice::default();
 
         let mut position = 0_usize;
         let mut byte_position = 0_usize;
 
-        // Split text into sentences using Japanese punctuation.
-        for sentence in text.split_inclusive(&['。', '、', '\n', '\t']) {
-            if sentence.is_empty() {
-                continue;
+        self.for_each_chunk(&text, constraints, |_chunk_start, chunk, lattice| {
+            if !constraints.is_empty() && !lattice.has_path() {
+                return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+                    "constraints cannot be satisfied: {}",
+                    chunk
+                )));
             }
 
-            lattice.set_text(
-                &self.dictionary.prefix_dictionary,
-                &self.user_dictionary.as_ref().map(|d| &d.dict),
-                &self.dictionary.character_definition,
-                &self.dictionary.unknown_dictionary,
-                sentence,
-                &self.mode,
+            let offsets = lattice.tokens_offset();
+
+            let confidence = self.confidence_temperature.map(|temperature| {
+                lattice.tokens_confidence(
+                    &self.dictionary.connection_cost_matrix,
+                    &self.mode,
+                    temperature,
+                )
+            });
+
+            let first = tokens.len();
+            self.push_tokens(
+                &mut tokens,
+                chunk,
+                &offsets,
+                confidence.as_deref(),
+                &mut position,
+                &mut byte_position,
             );
-            lattice.calculate_path_costs(&self.dictionary.connection_cost_matrix, &self.mode);
 
-            let offsets = lattice.tokens_offset();
+            // Tokens inserted for a span take the required part-of-speech as details.
+            for token in tokens[first..].iter_mut() {
+                if !token.word_id.is_unknown() {
+                    continue;
+                }
+                if let Some(pos) = constraints
+                    .spans
+                    .iter()
+                    .find(|span| span.start == token.byte_start && span.end == token.byte_end)
+                    .and_then(|span| span.pos.as_ref())
+                {
+                    token.details = Some(
+                        pos.split(',')
+                            .map(|field| Cow::Owned(field.to_string()))
+                            .collect(),
+                    );
+                }
+            }
 
-            for i in 0..offsets.len() {
-                let (byte_start, word_id) = offsets[i];
-                let byte_end = if i == offsets.len() - 1 {
-                    sentence.len()
-                } else {
-                    let (next_start, _word_id) = offsets[i + 1];
-                    next_start
-                };
-
-                // retrieve token from its sentence byte positions
-                let surface = &sentence[byte_start..byte_end];
-
-                // compute the token's absolute byte positions
-                let token_start = byte_position;
-                byte_position += surface.len();
-                let token_end = byte_position;
-
-                // Use Cow::Owned to ensure the token data can be returned safely
-                tokens.push(Token::new(
-                    Cow::Owned(surface.to_string()), // Clone the string here
-                    token_start,
-                    token_end,
-                    position,
-                    word_id,
-                    &self.dictionary,
-                    self.user_dictionary.as_ref(),
-                ));
+            Ok(())
+        })?;
+
+        Ok(tokens)
+    }
+
+    /// Splits the text into the pieces that are analyzed separately, and calls `f` with the byte
+    /// offset of each piece in `text`, the piece itself and its lattice.
+    ///
+    /// The text is split into sentences using Japanese punctuation, except where the constraints
+    /// do not allow it. Sentences longer than `max_sentence_length` are further split into chunks:
+    /// the first `max_sentence_length` characters are analyzed, the chunk ends at a safe boundary
+    /// of their best path, and the chunk is analyzed again on its own. `segment`, `segment_nbest`,
+    /// `sample` and `dump_lattice` all analyze the same pieces.
+    fn for_each_chunk<'t, E>(
+        &self,
+        text: &'t str,
+        constraints: &Constraints,
+        mut f: impl FnMut(usize, &'t str, &Lattice) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut lattice = Lattice::default();
 
-                position += 1;
+        for (sentence_start, sentence) in split_sentences(text, constraints) {
+            let mut chunk_start = 0;
+            while chunk_start < sentence.len() {
+                let rest = &sentence[chunk_start..];
+                let window_start = sentence_start + chunk_start;
+                let window = self.chunk_window(rest, window_start, constraints);
+
+                let lattice_constraints =
+                    self.lattice_constraints(constraints, window_start, window);
+                self.build_lattice(&mut lattice, window, &lattice_constraints);
+
+                // Keep the part up to a safe boundary, and analyze the rest with the next chunk.
+                let mut chunk = window;
+                if window.len() < rest.len() {
+                    let chunk_len = self.chunk_len(&lattice, window.len());
+                    if chunk_len < window.len() {
+                        chunk = &window[..chunk_len];
+                        let lattice_constraints =
+                            self.lattice_constraints(constraints, window_start, chunk);
+                        self.build_lattice(&mut lattice, chunk, &lattice_constraints);
+                    }
+                }
+
+                f(window_start, chunk, &lattice)?;
+
+                chunk_start += chunk.len();
             }
         }
 
+        Ok(())
+    }
+
+    /// Returns the part of `rest` to analyze at once: all of it, or its first `max_sentence_length` characters, extended so as not to end inside a span or at a forbidden boundary.
+    fn chunk_window<'t>(
+        &self,
+        rest: &'t str,
+        rest_start: usize,
+        constraints: &Constraints,
+    ) -> &'t str {
+        let Some((mut end, _)) = self
+            .max_sentence_length
+            .and_then(|max_sentence_length| rest.char_indices().nth(max_sentence_length))
+        else {
+            return rest;
+        };
+        while end < rest.len() && !constraints.can_split_at(rest_start + end) {
+            end += rest[end..].chars().next().map_or(1, char::len_utf8);
+        }
+        &rest[..end]
+    }
+
+    /// Chooses where to end a chunk, given the lattice of a window that ends before the end of the sentence.
+    ///
+    /// The last tokens of the window may be cut off by its end, so the chunk ends at a boundary of the best path in the second half of the window, where the connection cost is the lowest (i.e. where the boundary is the most certain).
+    fn chunk_len(&self, lattice: &Lattice, window_len: usize) -> usize {
+        let boundaries = lattice.best_path_boundaries(&self.dictionary.connection_cost_matrix);
+        // The boundary before the last token is not safe either, since the last token may be longer.
+        let candidates = &boundaries[..boundaries.len().saturating_sub(1)];
+
+        candidates
+            .iter()
+            .filter(|(pos, _)| *pos >= window_len / 2)
+            .min_by_key(|(pos, cost)| (*cost, Reverse(*pos)))
+            .or_else(|| candidates.last())
+            .or_else(|| boundaries.last())
+            .map_or(window_len, |(pos, _)| *pos)
+    }
+
+    /// Converts the constraints that apply to a sentence into lattice constraints relative to the sentence.
+    fn lattice_constraints(
+        &self,
+        constraints: &Constraints,
+        sentence_start: usize,
+        sentence: &str,
+    ) -> LatticeConstraints {
+        let sentence_end = sentence_start + sentence.len();
+        let inner = |boundaries: &[usize]| {
+            boundaries
+                .iter()
+                .filter(|&&boundary| sentence_start < boundary && boundary < sentence_end)
+                .map(|&boundary| boundary - sentence_start)
+                .collect()
+        };
+
+        LatticeConstraints {
+            spans: constraints
+                .spans
+                .iter()
+                .filter(|span| sentence_start <= span.start && span.end <= sentence_end)
+                .map(|span| LatticeSpan {
+                    start: span.start - sentence_start,
+                    end: span.end - sentence_start,
+                    word_ids: span.pos.as_ref().map(|pos| {
+                        self.word_ids_with_pos(
+                            &sentence[span.start - sentence_start..span.end - sentence_start],
+                            pos,
+                        )
+                    }),
+                })
+                .collect(),
+            forced_boundaries: inner(&constraints.forced_boundaries),
+            forbidden_boundaries: inner(&constraints.forbidden_boundaries),
+        }
+    }
+
+    /// Looks up a surface in the dictionaries without segmenting any text.
+    ///
+    /// # Arguments
+    ///
+    /// * `surface` - The surface to look up.
+    ///
+    /// # Returns
+    ///
+    /// Returns the entries of the user dictionaries whose surface is `surface`, followed by those of the system dictionary, with their details decoded. The entries of the user dictionaries have the word ids and the biased costs they have in the lattice.
+    pub fn lookup<'a>(&'a self, surface: &'a str) -> Vec<LookupEntry<'a>> {
+        let mut entries = self.user_entries(|user_dictionary| user_dictionary.lookup(surface));
+        entries.extend(self.dictionary.lookup(surface));
+        entries
+    }
+
+    /// Looks up the dictionary entries that start the given text.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text whose prefixes are looked up.
+    ///
+    /// # Returns
+    ///
+    /// Returns the entries of the user and system dictionaries whose surface is a prefix of `text`, from the shortest surface. Entries of the same surface are ordered as by `lookup`.
+    pub fn common_prefixes<'a>(&'a self, text: &'a str) -> Vec<LookupEntry<'a>> {
+        let mut entries =
+            self.user_entries(|user_dictionary| user_dictionary.common_prefixes(text));
+        entries.extend(self.dictionary.common_prefixes(text));
+        // The sort is stable, so user entries stay before system entries of the same length.
+        entries.sort_by_key(|entry| entry.surface.len());
+        entries
+    }
+
+    /// Looks up the words of the dictionaries that start with the given prefix, for example to suggest completions.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The prefix of the surfaces to look up.
+    /// * `limit` - The maximum number of entries to return.
+    /// * `pos` - If given, only the entries whose part-of-speech starts with these comma-separated fields are returned, e.g. `名詞,固有名詞`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the entries of the user and system dictionaries whose surface starts with `prefix`, from the lowest word cost. Entries of the same cost are ordered as by `lookup`.
+    pub fn predict(&self, prefix: &str, limit: usize, pos: Option<&str>) -> Vec<LookupEntry<'_>> {
+        let mut entries =
+            self.user_entries(|user_dictionary| user_dictionary.predict(prefix, limit, pos));
+        entries.extend(self.dictionary.predict(prefix, limit, pos));
+        entries.sort_by_key(|entry| entry.word_cost);
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Looks up the entries of each user dictionary with `lookup`, and gives them the word ids and the costs they have in the lattice.
+    fn user_entries<'a>(
+        &'a self,
+        lookup: impl Fn(&'a UserDictionary) -> Vec<LookupEntry<'a>>,
+    ) -> Vec<LookupEntry<'a>> {
+        self.user_dictionaries
+            .iter()
+            .enumerate()
+            .flat_map(|(dictionary_index, user_dictionary)| {
+                lookup(&user_dictionary.dictionary)
+                    .into_iter()
+                    .map(move |mut entry| {
+                        entry.word_id = WordId::user(dictionary_index, entry.word_id.id);
+                        entry.word_cost = entry.word_cost.saturating_add(user_dictionary.cost_bias);
+                        entry
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the user dictionary of a word, if it is a word of a user dictionary.
+    fn user_dictionary_of(&self, word_id: WordId) -> Option<&UserDictionary> {
+        let (dictionary_index, _) = word_id.user_entry()?;
+        self.user_dictionaries
+            .get(dictionary_index)
+            .map(|user_dictionary| user_dictionary.dictionary.as_ref())
+    }
+
+    /// Returns the ids of the words of the dictionaries with the given surface and part-of-speech.
+    fn word_ids_with_pos(&self, surface: &str, pos: &str) -> Vec<WordId> {
+        // System words first, as the lattice lists them.
+        let mut entries = self.dictionary.lookup(surface);
+        entries.extend(self.user_entries(|user_dictionary| user_dictionary.lookup(surface)));
+        entries
+            .into_iter()
+            .filter(|entry| entry.has_pos(pos))
+            .map(|entry| entry.word_id)
+            .collect()
+    }
+
+    /// Segments the input text along a path drawn at random from the lattice, for example to augment training data with alternative segmentations.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A `Cow<'a, str>` representing the input text.
+    /// * `temperature` - The temperature of the distribution. The probability of a segmentation is proportional to `exp(-cost / temperature)`, so a low temperature almost always returns the segmentation of `segment`, while a high one favors the alternatives.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `LinderaResult<Vec<Token<'a>>>` in the same form as `segment`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the temperature is not a positive finite number.
+    pub fn sample<'a>(
+        &'a self,
+        text: Cow<'a, str>,
+        temperature: f64,
+    ) -> LinderaResult<Vec<Token<'a>>> {
+        check_temperature(temperature)?;
+
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut rng = rand::thread_rng();
+
+        let mut position = 0_usize;
+        let mut byte_position = 0_usize;
+
+        self.for_each_chunk(
+            &text,
+            &Constraints::default(),
+            |_chunk_start, chunk, lattice| {
+                let offsets = lattice.sample_tokens_offset(
+                    &self.dictionary.connection_cost_matrix,
+                    &self.mode,
+                    temperature,
+                    &mut rng,
+                );
+
+                self.push_tokens(
+                    &mut tokens,
+                    chunk,
+                    &offsets,
+                    None,
+                    &mut position,
+                    &mut byte_position,
+                );
+
+                Ok::<_, Infallible>(())
+            },
+        )
+        .unwrap_or_else(|never| match never {});
+
         Ok(tokens)
     }
+
+    /// Segments the input text and returns the `n` best segmentations with their total costs.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A `Cow<'a, str>` representing the input text.
+    /// * `n` - The maximum number of segmentations to return.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `LinderaResult<Vec<(Vec<Token<'a>>, i64)>>` sorted in ascending order of cost. The first element is the same segmentation that `segment` returns.
+    ///
+    /// # Process
+    ///
+    /// 1. **Sentence Splitting**:
+    ///    - The input text is split into sentences, and long sentences into chunks, in the same way as `segment`.
+    ///
+    /// 2. **N-best Search**:
+    ///    - For each sentence, the `n` best paths through the lattice are enumerated, like MeCab's `-N` option.
+    ///
+    /// 3. **Combination**:
+    ///    - The cost of a segmentation of the whole text is the sum of the costs of its sentences, so the per-sentence lists are merged while keeping only the `n` cheapest combinations.
+    ///
+    /// # Notes
+    ///
+    /// - Fewer than `n` segmentations are returned if the lattice does not contain that many paths.
+    pub fn segment_nbest<'a>(
+        &'a self,
+        text: Cow<'a, str>,
+        n: usize,
+    ) -> LinderaResult<Vec<(Vec<Token<'a>>, i64)>> {
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut sentences = Vec::new();
+        let mut sentence_paths = Vec::new();
+
+        // Each candidate holds the index of the path chosen for every sentence and its total cost.
+        let mut candidates: Vec<(Vec<usize>, i64)> = vec![(Vec::new(), 0)];
+
+        self.for_each_chunk(
+            &text,
+            &Constraints::default(),
+            |_chunk_start, chunk, lattice| {
+                let paths = lattice.nbest_tokens_offset(
+                    &self.dictionary.connection_cost_matrix,
+                    &self.mode,
+                    n,
+                );
+
+                let mut merged = Vec::with_capacity(candidates.len() * paths.len());
+                for (choices, cost) in candidates.iter() {
+                    for (path_idx, (_offsets, path_cost)) in paths.iter().enumerate() {
+                        let mut choices = choices.clone();
+                        choices.push(path_idx);
+                        merged.push((choices, cost + *path_cost as i64));
+                    }
+                }
+                merged.sort_by_key(|(_choices, cost)| *cost);
+                merged.truncate(n);
+                candidates = merged;
+
+                sentences.push(chunk);
+                sentence_paths.push(paths);
+
+                Ok::<_, Infallible>(())
+            },
+        )
+        .unwrap_or_else(|never| match never {});
+
+        let mut results = Vec::with_capacity(candidates.len());
+        for (choices, cost) in candidates {
+            let mut tokens: Vec<Token> = Vec::new();
+            let mut position = 0_usize;
+            let mut byte_position = 0_usize;
+            for (i, path_idx) in choices.into_iter().enumerate() {
+                self.push_tokens(
+                    &mut tokens,
+                    sentences[i],
+                    &sentence_paths[i][path_idx].0,
+                    None,
+                    &mut position,
+                    &mut byte_position,
+                );
+            }
+            results.push((tokens, cost));
+        }
+
+        Ok(results)
+    }
+
+    /// Builds the lattice of each sentence of the input text and returns a snapshot of it,
+    /// for debugging why a particular segmentation was chosen.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The input text.
+    ///
+    /// # Returns
+    ///
+    /// Returns one `LatticeDump` per sentence, in order. The text is split into sentences, and sentences longer than `max_sentence_length` into chunks, in the same way as `segment`. The byte offsets of each dump are relative to its sentence or chunk.
+    pub fn dump_lattice(&self, text: &str) -> Vec<LatticeDump> {
+        let mut dumps = Vec::new();
+
+        self.for_each_chunk(
+            text,
+            &Constraints::default(),
+            |_chunk_start, chunk, lattice| {
+                dumps.push(lattice.dump(
+                    chunk,
+                    &self.dictionary.connection_cost_matrix,
+                    &self.mode,
+                ));
+                Ok::<_, Infallible>(())
+            },
+        )
+        .unwrap_or_else(|never| match never {});
+
+        dumps
+    }
+
+    /// Sets up the lattice for a sentence and calculates the path costs.
+    fn build_lattice(
+        &self,
+        lattice: &mut Lattice,
+        sentence: &str,
+        constraints: &LatticeConstraints,
+    ) {
+        lattice.set_pruning(self.pruning);
+        lattice.set_unknown_word_mode(self.unknown_word_mode);
+        let user_dicts = self
+            .user_dictionaries
+            .iter()
+            .map(|user_dictionary| UserDictionaryLayer {
+                dict: &user_dictionary.dictionary.dict,
+                cost_bias: user_dictionary.cost_bias,
+            })
+            .collect::<Vec<_>>();
+        lattice.set_text_with_constraints(
+            &self.dictionary.prefix_dictionary,
+            &user_dicts,
+            &self.dictionary.character_definition,
+            &self.dictionary.unknown_dictionary,
+            sentence,
+            &self.mode,
+            constraints,
+        );
+        lattice.calculate_path_costs(&self.dictionary.connection_cost_matrix, &self.mode);
+    }
+
+    /// Appends the tokens of a sentence, given the lattice offsets and optionally the confidence of each, to `tokens`.
+    ///
+    /// A word of the user dictionary with a custom segmentation is appended as one token per part.
+    fn push_tokens<'a>(
+        &'a self,
+        tokens: &mut Vec<Token<'a>>,
+        sentence: &str,
+        offsets: &[(usize, WordId)],
+        confidence: Option<&[f64]>,
+        position: &mut usize,
+        byte_position: &mut usize,
+    ) {
+        for i in 0..offsets.len() {
+            let (byte_start, word_id) = offsets[i];
+            let byte_end = if i == offsets.len() - 1 {
+                sentence.len()
+            } else {
+                let (next_start, _word_id) = offsets[i + 1];
+                next_start
+            };
+
+            // retrieve token from its sentence byte positions
+            let surface = &sentence[byte_start..byte_end];
+
+            let parts = word_id.user_entry().and_then(|(_, entry_id)| {
+                self.user_dictionary_of(word_id)?
+                    .word_parts(entry_id as usize)
+            });
+
+            match parts {
+                Some(parts) => {
+                    for part in parts {
+                        let mut token =
+                            self.new_token(&part.surface, word_id, position, byte_position);
+                        token.details = Some(
+                            part.details
+                                .iter()
+                                .map(|detail| Cow::Borrowed(detail.as_str()))
+                                .collect(),
+                        );
+                        token.confidence = confidence.map(|confidence| confidence[i]);
+                        tokens.push(token);
+                    }
+                }
+                None => {
+                    let mut token = self.new_token(surface, word_id, position, byte_position);
+                    token.confidence = confidence.map(|confidence| confidence[i]);
+                    tokens.push(token);
+                }
+            }
+        }
+    }
+
+    /// Creates the token of `surface`, which starts at the given positions, and advances them.
+    fn new_token<'a>(
+        &'a self,
+        surface: &str,
+        word_id: WordId,
+        position: &mut usize,
+        byte_position: &mut usize,
+    ) -> Token<'a> {
+        // compute the token's absolute byte positions
+        let token_start = *byte_position;
+        *byte_position += surface.len();
+        let token_end = *byte_position;
+
+        // Use Cow::Owned to ensure the token data can be returned safely
+        let token = Token::new(
+            Cow::Owned(surface.to_string()), // Clone the string here
+            token_start,
+            token_end,
+            *position,
+            word_id,
+            &self.dictionary,
+            self.user_dictionary_of(word_id),
+        );
+
+        *position += 1;
+        token
+    }
+}
+
+/// Splits text into sentences using Japanese punctuation, without splitting where the constraints
+/// do not allow it. Returns the byte offset of each sentence along with it.
+fn split_sentences<'t>(text: &'t str, constraints: &Constraints) -> Vec<(usize, &'t str)> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    for sentence in text.split_inclusive(&['。', '、', '\n', '\t']) {
+        end += sentence.len();
+        if end < text.len() && !constraints.can_split_at(end) {
+            continue;
+        }
+        if start < end {
+            sentences.push((start, &text[start..end]));
+        }
+        start = end;
+    }
+    sentences
+}
+
+fn get_usize(config: &SegmenterConfig, key: &str) -> LinderaResult<Option<usize>> {
+    config
+        .get(key)
+        .map(|v| {
+            v.as_u64().map(|v| v as usize).ok_or_else(|| {
+                LinderaErrorKind::Parse.with_error(anyhow::anyhow!(
+                    "{} field must be a non-negative integer",
+                    key
+                ))
+            })
+        })
+        .transpose()
+}
+
+/// Returns the number of entries of a user dictionary.
+fn user_dictionary_len(user_dictionary: &UserDictionary) -> usize {
+    user_dictionary.dict.words_idx_data.len() / 4
+}
+
+/// Checks that the ids of the words of the user dictionary at `dictionary_index` fit in a `WordId`.
+/// The first user dictionary can have any number of entries.
+fn check_user_dictionary_size(
+    dictionary_index: usize,
+    user_dictionary: &UserDictionary,
+) -> LinderaResult<()> {
+    if dictionary_index > 0 && user_dictionary_len(user_dictionary) > MAX_USER_DICTIONARY_ENTRIES {
+        return Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+            "too many entries in the user dictionary: at most {} are supported",
+            MAX_USER_DICTIONARY_ENTRIES
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the cost bias of a user dictionary config, 0 if it has none.
+fn get_cost_bias(config: &UserDictionaryConfig) -> LinderaResult<i16> {
+    config
+        .get("cost_bias")
+        .map(|v| {
+            v.as_i64()
+                .and_then(|v| i16::try_from(v).ok())
+                .ok_or_else(|| {
+                    LinderaErrorKind::Parse.with_error(anyhow::anyhow!(
+                        "cost_bias field must be an integer between {} and {}",
+                        i16::MIN,
+                        i16::MAX
+                    ))
+                })
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+fn check_temperature(temperature: f64) -> LinderaResult<()> {
+    if temperature.is_finite() && temperature > 0.0 {
+        Ok(())
+    } else {
+        Err(LinderaErrorKind::Args.with_error(anyhow::anyhow!(
+            "temperature must be a positive number: {}",
+            temperature
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -1249,7 +2222,14 @@ mod tests {
             assert_eq!(token.byte_end, 36);
             assert_eq!(token.position, 5);
             assert_eq!(token.position_length, 1);
-            assert_eq!(token.details(), vec!["UNK"]);
+            assert!(token.word_id.is_unknown());
+            // The details are the features of the unk.def entry the word was built from.
+            let unknown_details = segmenter
+                .dictionary
+                .unknown_dictionary
+                .word_details(token.word_id.unknown_entry_id().unwrap());
+            assert_eq!(unknown_details.len(), 6);
+            assert_eq!(token.details(), unknown_details);
         }
     }
 
@@ -1867,7 +2847,14 @@ mod tests {
             assert_eq!(token.byte_end, 30);
             assert_eq!(token.position, 4);
             assert_eq!(token.position_length, 1);
-            assert_eq!(token.details(), vec!["UNK"]);
+            assert!(token.word_id.is_unknown());
+            // The details are the features of the unk.def entry the word was built from.
+            let unknown_details = segmenter
+                .dictionary
+                .unknown_dictionary
+                .word_details(token.word_id.unknown_entry_id().unwrap());
+            assert_eq!(unknown_details.len(), 6);
+            assert_eq!(token.details(), unknown_details);
         }
     }
 
@@ -2481,7 +3468,14 @@ mod tests {
             assert_eq!(token.byte_end, 30);
             assert_eq!(token.position, 4);
             assert_eq!(token.position_length, 1);
-            assert_eq!(token.details(), vec!["UNK"]);
+            assert!(token.word_id.is_unknown());
+            // The details are the features of the unk.def entry the word was built from.
+            let unknown_details = segmenter
+                .dictionary
+                .unknown_dictionary
+                .word_details(token.word_id.unknown_entry_id().unwrap());
+            assert_eq!(unknown_details.len(), 6);
+            assert_eq!(token.details(), unknown_details);
         }
     }
 
@@ -2904,7 +3898,8 @@ mod tests {
             assert_eq!(token.byte_end, 36);
             assert_eq!(token.position, 2);
             assert_eq!(token.position_length, 1);
-            assert_eq!(token.details(), vec!["UNK"]);
+            assert_eq!(token.category_name(), Some("KATAKANA"));
+            assert_eq!(token.details()[0], "名詞");
         }
     }
 
@@ -3005,7 +4000,8 @@ mod tests {
             assert_eq!(token.byte_end, 36);
             assert_eq!(token.position, 3);
             assert_eq!(token.position_length, 1);
-            assert_eq!(token.details(), vec!["UNK"]);
+            assert_eq!(token.category_name(), Some("KATAKANA"));
+            assert_eq!(token.details()[0], "名詞");
         }
     }
 
@@ -3040,4 +4036,896 @@ mod tests {
             .unwrap();
         assert!(!tokens.is_empty());
     }
+
+    #[test]
+    fn test_segment_nbest() {
+        use std::borrow::Cow;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::mini_ipadic;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+
+        let text = "東京都に行く。東京都";
+        let nbest = segmenter.segment_nbest(Cow::Borrowed(text), 4).unwrap();
+
+        // 3 segmentations of each "東京都" give 9 combinations, of which the 4 best are returned.
+        assert_eq!(nbest.len(), 4);
+
+        // The best segmentation is the one returned by `segment`.
+        let best = segmenter.segment(Cow::Borrowed(text)).unwrap();
+        assert_eq!(
+            nbest[0]
+                .0
+                .iter()
+                .map(|t| t.text.as_ref())
+                .collect::<Vec<_>>(),
+            best.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>()
+        );
+
+        for (i, (tokens, cost)) in nbest.iter().enumerate() {
+            if i > 0 {
+                assert!(nbest[i - 1].1 <= *cost);
+            }
+
+            // Offsets and positions are consistent across sentences.
+            let mut byte_position = 0;
+            for (position, token) in tokens.iter().enumerate() {
+                assert_eq!(token.position, position);
+                assert_eq!(token.byte_start, byte_position);
+                assert_eq!(&text[token.byte_start..token.byte_end], token.text);
+                byte_position = token.byte_end;
+            }
+            assert_eq!(byte_position, text.len());
+        }
+
+        assert!(segmenter
+            .segment_nbest(Cow::Borrowed(text), 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_segment_confidence() {
+        use std::borrow::Cow;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::mini_ipadic;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        let text = "東京都に行く。関西国際空港";
+
+        // The confidence is not computed by default.
+        let tokens = segmenter.segment(Cow::Borrowed(text)).unwrap();
+        assert!(tokens.iter().all(|t| t.confidence.is_none()));
+
+        let segmenter = segmenter.with_confidence_temperature(Some(1000.0)).unwrap();
+        let tokens = segmenter.segment(Cow::Borrowed(text)).unwrap();
+        for token in tokens.iter() {
+            let confidence = token.confidence.unwrap();
+            assert!(confidence > 0.0 && confidence <= 1.0);
+        }
+        // "東京都" competes with "東京" + "都" and "東" + "京都", while "に" does not compete with
+        // anything.
+        assert!(tokens[0].confidence.unwrap() < 1.0);
+        assert!((tokens[1].confidence.unwrap() - 1.0).abs() < 1e-9);
+
+        assert!(Segmenter::new(Mode::Normal, mini_ipadic(), None)
+            .with_confidence_temperature(Some(0.0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_segment_confidence_config() {
+        use crate::segmenter::{Segmenter, SegmenterConfig};
+        use crate::test_utils::mini_ipadic_path;
+
+        let dictionary_path = mini_ipadic_path();
+
+        let config = serde_json::json!({
+            "dictionary": {
+                "path": &*dictionary_path,
+            },
+            "confidence_temperature": 800.0,
+        });
+        let segmenter = Segmenter::from_config(&config as &SegmenterConfig).unwrap();
+        assert_eq!(segmenter.confidence_temperature, Some(800.0));
+
+        let config = serde_json::json!({
+            "dictionary": {
+                "path": &*dictionary_path,
+            },
+            "confidence_temperature": "high",
+        });
+        assert!(Segmenter::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_sample() {
+        use std::borrow::Cow;
+        use std::collections::HashSet;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::mini_ipadic;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        let text = "東京都に行く。東京都";
+
+        // Sampled segmentations cover the text, and a high temperature yields alternatives.
+        let mut segmentations = HashSet::new();
+        for _ in 0..100 {
+            let tokens = segmenter.sample(Cow::Borrowed(text), 100000.0).unwrap();
+            let mut byte_position = 0;
+            for (position, token) in tokens.iter().enumerate() {
+                assert_eq!(token.position, position);
+                assert_eq!(token.byte_start, byte_position);
+                assert_eq!(&text[token.byte_start..token.byte_end], token.text);
+                byte_position = token.byte_end;
+            }
+            assert_eq!(byte_position, text.len());
+            segmentations.insert(
+                tokens
+                    .iter()
+                    .map(|t| t.text.to_string())
+                    .collect::<Vec<_>>(),
+            );
+        }
+        assert!(segmentations.len() > 1);
+
+        // A low temperature yields the best segmentation.
+        let best = segmenter.segment(Cow::Borrowed(text)).unwrap();
+        let sampled = segmenter.sample(Cow::Borrowed(text), 1.0).unwrap();
+        assert_eq!(
+            sampled.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            best.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>()
+        );
+
+        assert!(segmenter.sample(Cow::Borrowed(text), -1.0).is_err());
+    }
+
+    #[test]
+    fn test_segment_with_constraints() {
+        use std::borrow::Cow;
+
+        use crate::mode::Mode;
+        use crate::segmenter::{Constraints, Segmenter, SpanConstraint};
+        use crate::test_utils::mini_ipadic;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        let text = "東京都に行く。関西国際空港";
+        let texts = |constraints: &Constraints| {
+            segmenter
+                .segment_with_constraints(Cow::Borrowed(text), constraints)
+                .unwrap()
+                .iter()
+                .map(|t| t.text.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            texts(&Constraints::default()),
+            vec!["東京都", "に", "行く", "。", "関西国際空港"]
+        );
+
+        // Spans are kept whole, in every sentence.
+        let constraints = Constraints {
+            spans: vec![
+                SpanConstraint {
+                    start: 0,
+                    end: 6,
+                    pos: None,
+                },
+                SpanConstraint {
+                    start: 27,
+                    end: 39,
+                    pos: None,
+                },
+            ],
+            ..Constraints::default()
+        };
+        assert_eq!(
+            texts(&constraints),
+            vec!["東京", "都", "に", "行く", "。", "関西", "国際空港"]
+        );
+
+        // A forced boundary splits a word, and a forbidden boundary joins words.
+        let constraints = Constraints {
+            forced_boundaries: vec![33],
+            forbidden_boundaries: vec![9],
+            ..Constraints::default()
+        };
+        assert_eq!(
+            texts(&constraints),
+            vec!["東京", "都に", "行く", "。", "関西", "国際", "空港"]
+        );
+
+        // A span can cross a sentence boundary.
+        let constraints = Constraints {
+            spans: vec![SpanConstraint {
+                start: 12,
+                end: 27,
+                pos: None,
+            }],
+            ..Constraints::default()
+        };
+        assert_eq!(
+            texts(&constraints),
+            vec!["東京都", "に", "行く。関西", "国際空港"]
+        );
+    }
+
+    #[test]
+    fn test_segment_with_constraints_pos() {
+        use std::borrow::Cow;
+
+        use crate::mode::Mode;
+        use crate::segmenter::{Constraints, Segmenter, SpanConstraint};
+        use crate::test_utils::mini_ipadic;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        let text = "関西国際空港";
+
+        // A word of the dictionary with the required part-of-speech.
+        let constraints = Constraints {
+            spans: vec![SpanConstraint {
+                start: 0,
+                end: 6,
+                pos: Some("名詞,固有名詞".to_string()),
+            }],
+            ..Constraints::default()
+        };
+        let mut tokens = segmenter
+            .segment_with_constraints(Cow::Borrowed(text), &constraints)
+            .unwrap();
+        assert_eq!(tokens[0].text, "関西");
+        assert_eq!(tokens[0].details()[..3], ["名詞", "固有名詞", "地域"]);
+
+        // No word has the required part-of-speech, so the span is inserted with it.
+        let constraints = Constraints {
+            spans: vec![SpanConstraint {
+                start: 0,
+                end: 6,
+                pos: Some("名詞,人名".to_string()),
+            }],
+            ..Constraints::default()
+        };
+        let mut tokens = segmenter
+            .segment_with_constraints(Cow::Borrowed(text), &constraints)
+            .unwrap();
+        assert_eq!(tokens[0].text, "関西");
+        assert!(tokens[0].word_id.is_unknown());
+        assert_eq!(tokens[0].details(), vec!["名詞", "人名"]);
+        assert_eq!(tokens[1].text, "国際空港");
+    }
+
+    #[test]
+    fn test_segment_with_invalid_constraints() {
+        use std::borrow::Cow;
+
+        use crate::mode::Mode;
+        use crate::segmenter::{Constraints, Segmenter, SpanConstraint};
+        use crate::test_utils::mini_ipadic;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        let text = "関西国際空港";
+        let segment = |constraints: Constraints| {
+            segmenter.segment_with_constraints(Cow::Borrowed(text), &constraints)
+        };
+        let span = |start, end| SpanConstraint {
+            start,
+            end,
+            pos: None,
+        };
+
+        // Out of the text, or not on a character boundary.
+        assert!(segment(Constraints {
+            spans: vec![span(0, 30)],
+            ..Constraints::default()
+        })
+        .is_err());
+        assert!(segment(Constraints {
+            forced_boundaries: vec![1],
+            ..Constraints::default()
+        })
+        .is_err());
+
+        // Overlapping spans.
+        assert!(segment(Constraints {
+            spans: vec![span(0, 6), span(3, 9)],
+            ..Constraints::default()
+        })
+        .is_err());
+
+        // Contradicting boundaries.
+        assert!(segment(Constraints {
+            spans: vec![span(0, 6)],
+            forced_boundaries: vec![3],
+            ..Constraints::default()
+        })
+        .is_err());
+        assert!(segment(Constraints {
+            spans: vec![span(0, 6)],
+            forbidden_boundaries: vec![6],
+            ..Constraints::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_dump_lattice() {
+        use std::borrow::Cow;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::mini_ipadic;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+
+        let text = "東京都に行く。関西国際空港";
+        let dumps = segmenter.dump_lattice(text);
+        assert_eq!(dumps.len(), 2);
+        assert_eq!(dumps[0].text, "東京都に行く。");
+        assert_eq!(dumps[1].text, "関西国際空港");
+
+        // The best paths of the lattices are the segmentation returned by `segment`.
+        let best = dumps
+            .iter()
+            .flat_map(|dump| {
+                let len = dump.best_path.len();
+                dump.best_path[1..len - 1]
+                    .iter()
+                    .map(|&id| dump.edges[id as usize].surface.clone())
+            })
+            .collect::<Vec<_>>();
+        let tokens = segmenter.segment(Cow::Borrowed(text)).unwrap();
+        assert_eq!(
+            best,
+            tokens
+                .iter()
+                .map(|t| t.text.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_segment_long_sentence() {
+        use std::borrow::Cow;
+
+        use lindera_dictionary::viterbi::Pruning;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::mini_ipadic;
+
+        let text = "東京都に行く関西国際空港へ行く京都の限定バッグ".repeat(50);
+        let tokens = |segmenter: &Segmenter| {
+            segmenter
+                .segment(Cow::Borrowed(&text))
+                .unwrap()
+                .iter()
+                .map(|t| (t.text.to_string(), t.byte_start, t.byte_end, t.position))
+                .collect::<Vec<_>>()
+        };
+
+        let expected = tokens(&Segmenter::new(Mode::Normal, mini_ipadic(), None));
+
+        // Chunks are split at safe boundaries, so the result is the same.
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None)
+            .with_max_sentence_length(Some(16))
+            .unwrap();
+        assert_eq!(tokens(&segmenter), expected);
+
+        // The other analyses work on the same chunks.
+        let dumps = segmenter.dump_lattice(&text);
+        assert!(dumps.len() > 1);
+        assert!(dumps.iter().all(|dump| dump.text.chars().count() <= 16));
+        let nbest = segmenter.segment_nbest(Cow::Borrowed(&text), 2).unwrap();
+        assert_eq!(
+            nbest[0]
+                .0
+                .iter()
+                .map(|t| (t.text.to_string(), t.byte_start, t.byte_end, t.position))
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        // A wide enough beam does not change the result either.
+        let segmenter = segmenter
+            .with_pruning(Pruning {
+                beam_width: Some(8),
+                max_edges_per_position: Some(8),
+            })
+            .unwrap();
+        assert_eq!(tokens(&segmenter), expected);
+    }
+
+    #[test]
+    fn test_segment_unknown_word_mode() {
+        use std::borrow::Cow;
+
+        use lindera_dictionary::viterbi::UnknownWordMode;
+
+        use crate::mode::Mode;
+        use crate::segmenter::{Segmenter, SegmenterConfig};
+        use crate::test_utils::{mini_ipadic, mini_ipadic_path};
+
+        let dictionary_path = mini_ipadic_path();
+
+        let texts = |segmenter: &Segmenter, text: &str| {
+            segmenter
+                .segment(Cow::Owned(text.to_string()))
+                .unwrap()
+                .iter()
+                .map(|t| t.text.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        assert_eq!(segmenter.unknown_word_mode, UnknownWordMode::Lindera);
+        assert_eq!(
+            texts(&segmenter, "大阪に行く"),
+            vec!["大", "阪", "に", "行く"]
+        );
+
+        let config = serde_json::json!({
+            "dictionary": {
+                "path": &*dictionary_path,
+            },
+            "unknown_word_mode": "mecab",
+        });
+        let segmenter = Segmenter::from_config(&config as &SegmenterConfig).unwrap();
+        assert_eq!(segmenter.unknown_word_mode, UnknownWordMode::Mecab);
+        assert_eq!(texts(&segmenter, "大阪に行く"), vec!["大阪", "に", "行く"]);
+        assert_eq!(
+            texts(&segmenter, "ロンドンに行く"),
+            vec!["ロンドン", "に", "行く"]
+        );
+
+        let config = serde_json::json!({
+            "dictionary": {
+                "path": &*dictionary_path,
+            },
+            "unknown_word_mode": "chasen",
+        });
+        assert!(Segmenter::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_segment_unknown_word_details() {
+        use std::borrow::Cow;
+        use std::collections::HashSet;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::mini_ipadic;
+        use crate::token_filter::japanese_keep_tags::JapaneseKeepTagsTokenFilter;
+        use crate::token_filter::TokenFilter;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        let mut tokens = segmenter.segment(Cow::Borrowed("ロンドンに行く")).unwrap();
+        assert_eq!(tokens[0].text, "ロンドン");
+        assert!(tokens[0].word_id.is_unknown());
+        assert_eq!(tokens[0].category_name(), Some("KATAKANA"));
+        assert_eq!(
+            tokens[0].details(),
+            vec!["名詞", "一般", "*", "*", "*", "*", "*"]
+        );
+        assert_eq!(tokens[1].category_name(), None);
+
+        // Part-of-speech filters see the details of unknown words.
+        let filter = JapaneseKeepTagsTokenFilter::new(HashSet::from(["名詞,一般".to_string()]));
+        filter.apply(&mut tokens).unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            vec!["ロンドン"]
+        );
+    }
+
+    #[test]
+    fn test_segment_origin() {
+        use std::borrow::Cow;
+
+        use lindera_dictionary::viterbi::EdgeType;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::{mini_ipadic, mini_ipadic_user_dictionary};
+        use crate::token::Origin;
+
+        let segmenter = Segmenter::new(
+            Mode::Normal,
+            mini_ipadic(),
+            Some(mini_ipadic_user_dictionary()),
+        );
+        let text = "東京都庁に行く大阪";
+        let tokens = segmenter.segment(Cow::Borrowed(text)).unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| (t.text.as_ref(), t.origin()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("東京都庁", Origin::User(0)),
+                ("に", Origin::System),
+                ("行く", Origin::System),
+                ("大", Origin::Unknown(Some("KANJI".to_string()))),
+                ("阪", Origin::Unknown(Some("KANJI".to_string()))),
+            ]
+        );
+
+        // The lattice tells user dictionary entries apart too.
+        let dump = &segmenter.dump_lattice(text)[0];
+        let edge = dump
+            .edges
+            .iter()
+            .find(|edge| edge.surface == "東京都庁")
+            .unwrap();
+        assert_eq!(edge.edge_type, EdgeType::USER);
+    }
+
+    #[test]
+    fn test_segment_with_mmap_dictionary() {
+        use std::borrow::Cow;
+
+        #[cfg(not(feature = "compress"))]
+        use lindera_dictionary::util::Data;
+
+        use crate::dictionary::load_dictionary_from_config;
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::{mini_ipadic, mini_ipadic_path};
+
+        let dictionary_path = mini_ipadic_path();
+
+        let config = serde_json::json!({
+            "path": &*dictionary_path,
+            "mmap": true,
+        });
+        let dictionary = load_dictionary_from_config(&config).unwrap();
+        // Compressed dictionaries are read into memory instead.
+        #[cfg(not(feature = "compress"))]
+        assert!(matches!(
+            dictionary.prefix_dictionary.words_data,
+            Data::Map(..)
+        ));
+        #[cfg(not(feature = "compress"))]
+        assert!(matches!(
+            dictionary.connection_cost_matrix.costs_data,
+            Data::Map(..)
+        ));
+
+        let text = "東京都に行く大阪";
+        let surfaces = |segmenter: &Segmenter| {
+            segmenter
+                .segment(Cow::Borrowed(text))
+                .unwrap()
+                .iter_mut()
+                .map(|t| (t.text.to_string(), t.details().join(",")))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            surfaces(&Segmenter::new(Mode::Normal, dictionary, None)),
+            surfaces(&Segmenter::new(Mode::Normal, mini_ipadic(), None))
+        );
+
+        let config = serde_json::json!({
+            "path": &*dictionary_path,
+            "mmap": "yes",
+        });
+        assert!(load_dictionary_from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_segment_with_dictionary_file() {
+        use std::borrow::Cow;
+
+        use lindera_dictionary::dictionary_builder::ipadic::IpadicBuilder;
+        use lindera_dictionary::dictionary_builder::DictionaryBuilder;
+
+        use crate::dictionary::{load_dictionary_from_config, load_dictionary_from_path};
+        use crate::error::LinderaErrorKind;
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::{mini_ipadic, mini_ipadic_dir, temp_dir};
+
+        let dir = temp_dir("dictionary-file");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mini-ipadic.linderadic");
+        IpadicBuilder::new()
+            .build_dictionary_file(&mini_ipadic_dir(), &path)
+            .unwrap();
+        assert!(path.is_file());
+        // The intermediate directory is removed.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let text = "東京都に行く大阪";
+        let surfaces = |segmenter: &Segmenter| {
+            segmenter
+                .segment(Cow::Borrowed(text))
+                .unwrap()
+                .iter_mut()
+                .map(|t| (t.text.to_string(), t.details().join(",")))
+                .collect::<Vec<_>>()
+        };
+        let expected = surfaces(&Segmenter::new(Mode::Normal, mini_ipadic(), None));
+        for mmap in [false, true] {
+            let config = serde_json::json!({ "path": path, "mmap": mmap });
+            let dictionary = load_dictionary_from_config(&config).unwrap();
+            assert_eq!(
+                surfaces(&Segmenter::new(Mode::Normal, dictionary, None)),
+                expected
+            );
+        }
+
+        // A corrupted file is reported instead of being loaded.
+        let mut bytes = std::fs::read(&path).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        std::fs::write(&path, bytes).unwrap();
+        let err = load_dictionary_from_path(&path).err().unwrap();
+        assert_eq!(err.kind(), LinderaErrorKind::Dictionary);
+    }
+
+    #[test]
+    fn test_segment_with_segmentation_userdic() {
+        use std::borrow::Cow;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::{mini_ipadic, mini_ipadic_user_dictionary};
+        use crate::token::Origin;
+
+        let segmenter = Segmenter::new(
+            Mode::Normal,
+            mini_ipadic(),
+            Some(mini_ipadic_user_dictionary()),
+        )
+        .with_confidence_temperature(Some(1000.0))
+        .unwrap();
+        let mut tokens = segmenter
+            .segment(Cow::Borrowed("関西国際空港に行く"))
+            .unwrap();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| (t.text.as_ref(), t.byte_start, t.byte_end, t.position))
+                .collect::<Vec<_>>(),
+            vec![
+                ("関西", 0, 6, 0),
+                ("国際", 6, 12, 1),
+                ("空港", 12, 18, 2),
+                ("に", 18, 21, 3),
+                ("行く", 21, 27, 4),
+            ]
+        );
+        assert_eq!(tokens[1].origin(), Origin::User(0));
+        assert_eq!(
+            tokens[1].details(),
+            vec![
+                "カスタム名詞",
+                "*",
+                "*",
+                "*",
+                "*",
+                "*",
+                "国際",
+                "コクサイ",
+                "*"
+            ]
+        );
+
+        // The parts share the confidence of the word.
+        assert!(tokens[0].confidence.is_some());
+        assert_eq!(tokens[0].confidence, tokens[2].confidence);
+    }
+
+    #[test]
+    fn test_segmenter_pruning_config() {
+        use lindera_dictionary::viterbi::Pruning;
+
+        use crate::segmenter::{Segmenter, SegmenterConfig};
+        use crate::test_utils::mini_ipadic_path;
+
+        let dictionary_path = mini_ipadic_path();
+
+        let config = serde_json::json!({
+            "dictionary": {
+                "path": &*dictionary_path,
+            },
+            "beam_width": 4,
+            "max_edges_per_position": 16,
+            "max_sentence_length": 1000,
+        });
+        let segmenter = Segmenter::from_config(&config as &SegmenterConfig).unwrap();
+        assert_eq!(
+            segmenter.pruning,
+            Pruning {
+                beam_width: Some(4),
+                max_edges_per_position: Some(16),
+            }
+        );
+        assert_eq!(segmenter.max_sentence_length, Some(1000));
+
+        for (key, value) in [
+            ("beam_width", serde_json::json!(0)),
+            ("max_edges_per_position", serde_json::json!(-1)),
+            ("max_sentence_length", serde_json::json!("long")),
+        ] {
+            let mut config = serde_json::json!({
+                "dictionary": {
+                    "path": &*dictionary_path,
+                },
+            });
+            config[key] = value;
+            assert!(Segmenter::from_config(&config).is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ipadic")]
+    fn test_segment_nbest_ipadic() {
+        use std::borrow::Cow;
+
+        let config_str = r#"
+        {
+            "dictionary": {
+                "kind": "ipadic"
+            },
+            "mode": "normal"
+        }
+        "#;
+        let config = serde_json::from_str::<SegmenterConfig>(config_str).unwrap();
+
+        let segmenter = Segmenter::from_config(&config).unwrap();
+
+        let text = "すもももももももものうち";
+        let nbest = segmenter.segment_nbest(Cow::Borrowed(text), 3).unwrap();
+        assert_eq!(nbest.len(), 3);
+
+        let best = segmenter.segment(Cow::Borrowed(text)).unwrap();
+        assert_eq!(
+            nbest[0]
+                .0
+                .iter()
+                .map(|t| t.text.as_ref())
+                .collect::<Vec<_>>(),
+            best.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>()
+        );
+        assert!(nbest[0].1 <= nbest[1].1);
+        assert!(nbest[1].1 <= nbest[2].1);
+    }
+
+    #[test]
+    fn test_lookup() {
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::{mini_ipadic, mini_ipadic_user_dictionary};
+
+        let segmenter = Segmenter::new(
+            Mode::Normal,
+            mini_ipadic(),
+            Some(mini_ipadic_user_dictionary()),
+        );
+
+        let entries = segmenter.lookup("東京都");
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].word_id.is_system());
+        assert_eq!(entries[0].details[6], "東京都");
+
+        let entries = segmenter.lookup("東京都庁");
+        assert_eq!(entries.len(), 1);
+        assert!(!entries[0].word_id.is_system());
+        assert_eq!(entries[0].details[1], "固有名詞");
+
+        let entries = segmenter.common_prefixes("東京都庁に行く");
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.surface.as_ref(), entry.word_id.is_system()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("東", true),
+                ("東京", true),
+                ("東京都", true),
+                ("東京都庁", false)
+            ]
+        );
+
+        let entries = segmenter.predict("東京", 10, Some("名詞,固有名詞,組織"));
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.surface.as_ref(), entry.word_cost))
+                .collect::<Vec<_>>(),
+            vec![("東京都庁", 1000)]
+        );
+        let entries = segmenter.predict("東京", 2, None);
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.surface.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["東京都庁", "東京"]
+        );
+
+        // Without a user dictionary, only the system dictionary is looked up.
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        assert!(segmenter.lookup("東京都庁").is_empty());
+        assert_eq!(segmenter.predict("東京", 10, None).len(), 2);
+    }
+
+    #[test]
+    fn test_layered_user_dictionaries() {
+        use std::borrow::Cow;
+
+        use crate::dictionary::WordId;
+        use crate::segmenter::{Segmenter, SegmenterConfig};
+        use crate::test_utils::{mini_ipadic_path, temp_dir};
+        use crate::token::Origin;
+
+        let dictionary_path = mini_ipadic_path();
+
+        let dir = temp_dir("layered-user-dictionaries");
+        std::fs::create_dir_all(&dir).unwrap();
+        let tenant_path = dir.join("tenant.csv");
+        std::fs::write(
+            &tenant_path,
+            "東京都庁,4,4,1000,名詞,固有名詞,組織,*,*,*,東京都庁,トチョウ,トチョー\n",
+        )
+        .unwrap();
+        let company_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../resources")
+            .join("mini_ipadic_userdic.csv");
+
+        let segmenter = |tenant_cost_bias: i64| {
+            let config: SegmenterConfig = serde_json::json!({
+                "dictionary": { "path": &*dictionary_path },
+                "user_dictionary": { "path": company_path, "kind": "ipadic" },
+                "user_dictionaries": [
+                    { "path": tenant_path, "kind": "ipadic", "cost_bias": tenant_cost_bias }
+                ]
+            });
+            Segmenter::from_config(&config).unwrap()
+        };
+
+        // The tenant dictionary wins with a negative bias, and its details are used.
+        let segmenter_with_bias = segmenter(-500);
+        assert_eq!(segmenter_with_bias.user_dictionaries.len(), 2);
+        let mut tokens = segmenter_with_bias
+            .segment(Cow::Borrowed("東京都庁に行く"))
+            .unwrap();
+        assert_eq!(tokens[0].text, "東京都庁");
+        assert_eq!(tokens[0].origin(), Origin::User(1));
+        assert_eq!(tokens[0].details()[7], "トチョウ");
+
+        // Otherwise the first dictionary wins the tie.
+        let segmenter_without_bias = segmenter(0);
+        let mut tokens = segmenter_without_bias
+            .segment(Cow::Borrowed("東京都庁に行く"))
+            .unwrap();
+        assert_eq!(tokens[0].origin(), Origin::User(0));
+        assert_eq!(tokens[0].details()[7], "トウキョウトチョウ");
+
+        // Lookups list the dictionaries in order, with the ids and the costs of the lattice.
+        let entries = segmenter_with_bias.lookup("東京都庁");
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.word_id, entry.word_cost, entry.details[7]))
+                .collect::<Vec<_>>(),
+            vec![
+                (WordId::user(0, 1), 1000, "トウキョウトチョウ"),
+                (WordId::user(1, 0), 500, "トチョウ"),
+            ]
+        );
+
+        let config: SegmenterConfig = serde_json::json!({
+            "dictionary": { "path": &*dictionary_path },
+            "user_dictionaries": [{ "path": tenant_path, "kind": "ipadic", "cost_bias": 40000 }]
+        });
+        assert!(Segmenter::from_config(&config).is_err());
+    }
 }
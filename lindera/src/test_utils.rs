@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+pub use lindera_dictionary::test_utils::{
+    mini_ipadic, mini_ipadic_dir, mini_ipadic_path, temp_dir,
+};
+
+use crate::dictionary::{
+    load_user_dictionary_from_csv_for_dictionary, DictionaryKind, UserDictionary,
+};
+
+/// Loads the user dictionary for the miniature IPADIC.
+pub fn mini_ipadic_user_dictionary() -> UserDictionary {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../resources")
+        .join("mini_ipadic_userdic.csv");
+    load_user_dictionary_from_csv_for_dictionary(DictionaryKind::IPADIC, &path, &mini_ipadic())
+        .unwrap()
+}
@@ -5,6 +5,18 @@ use serde::Serialize;
 use crate::dictionary::WordId;
 use lindera_dictionary::dictionary::{Dictionary, UserDictionary, UNK};
 
+/// Where the word of a token comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// An entry of the system dictionary.
+    System,
+    /// An entry of the user dictionary with the given index among those of the segmenter.
+    User(usize),
+    /// An unknown word, with the name of its character category if known.
+    Unknown(Option<String>),
+}
+
 #[derive(Serialize, Clone)]
 pub struct Token<'a> {
     /// The text content of the token, which is a copy-on-write string slice.
@@ -40,10 +52,17 @@ pub struct Token<'a> {
 
     /// An optional reference to a user-defined dictionary.
     ///
-    /// This dictionary can be used to add custom words or override existing words
-    /// in the default dictionary. If `None`, the default dictionary is used.
+    /// This is the user dictionary the word of the token comes from, which can be used to add
+    /// custom words or override existing words in the default dictionary. If `None`, the default
+    /// dictionary is used.
     pub user_dictionary: Option<&'a UserDictionary>,
 
+    /// The marginal probability of the token computed with the forward-backward algorithm over
+    /// the lattice, i.e. the probability that a path drawn from the lattice contains this token.
+    ///
+    /// This is `None` unless the segmenter is configured with a confidence temperature.
+    pub confidence: Option<f64>,
+
     /// An optional vector containing detailed information about the token.
     /// Each element in the vector is a `Cow` (Copy-On-Write) type, which allows
     /// for efficient handling of both owned and borrowed string data.
@@ -96,6 +115,7 @@ impl<'a> Token<'a> {
             word_id,
             dictionary,
             user_dictionary,
+            confidence: None,
             details: None,
         }
     }
@@ -110,7 +130,7 @@ impl<'a> Token<'a> {
     ///
     /// 1. **Check if details are already set**:
     ///    - If `self.details` is `None`, the method will attempt to fetch the details from either the system dictionary or the user dictionary.
-    ///    - If the `word_id` is unknown, a default value `UNK` is returned.
+    ///    - If the `word_id` is unknown, the features of its entry of the unknown dictionary (unk.def) are returned, or a default value `UNK` if the dictionary does not have them.
     /// 2. **Fetch details from dictionaries**:
     ///    - If the `word_id` corresponds to a system dictionary entry, details are fetched from `self.dictionary`.
     ///    - If the `word_id` corresponds to a user-defined dictionary, details are fetched from `self.user_dictionary`.
@@ -127,13 +147,25 @@ impl<'a> Token<'a> {
         // set details if it is not set yet.
         if self.details.is_none() {
             let tmp = if self.word_id.is_unknown() {
-                UNK.to_vec()
+                // Unknown words take the features of their entry of unk.def, if any.
+                let details = self
+                    .word_id
+                    .unknown_entry_id()
+                    .map(|entry_id| self.dictionary.unknown_dictionary.word_details(entry_id))
+                    .unwrap_or_default();
+                if details.is_empty() {
+                    UNK.to_vec()
+                } else {
+                    details
+                }
             } else if self.word_id.is_system() {
                 self.dictionary.word_details(self.word_id.id as usize)
             } else {
-                match self.user_dictionary {
-                    Some(user_dictionary) => user_dictionary.word_details(self.word_id.id as usize),
-                    None => UNK.to_vec(),
+                match (self.user_dictionary, self.word_id.user_entry()) {
+                    (Some(user_dictionary), Some((_, entry_id))) => {
+                        user_dictionary.word_details(entry_id as usize)
+                    }
+                    _ => UNK.to_vec(),
                 }
             };
 
@@ -147,6 +179,32 @@ impl<'a> Token<'a> {
             .unwrap_or_else(|| UNK.to_vec())
     }
 
+    /// Returns where the word of the token comes from: the system dictionary, a user dictionary, or neither (an unknown word).
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Origin`. Unknown words carry the name of their character category, as returned by `category_name`.
+    pub fn origin(&self) -> Origin {
+        if self.word_id.is_unknown() {
+            Origin::Unknown(self.category_name().map(str::to_string))
+        } else if self.word_id.is_system() {
+            Origin::System
+        } else {
+            Origin::User(self.word_id.user_entry().map_or(0, |(index, _)| index))
+        }
+    }
+
+    /// Returns the name of the character category (as defined in char.def) of an unknown word.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the token is not an unknown word, or if its category is not known.
+    pub fn category_name(&self) -> Option<&str> {
+        let entry_id = self.word_id.unknown_entry_id()?;
+        let category = self.dictionary.unknown_dictionary.category(entry_id)?;
+        Some(self.dictionary.character_definition.category_name(category))
+    }
+
     /// Retrieves the token's detail at the specified index, if available.
     ///
     /// # Arguments
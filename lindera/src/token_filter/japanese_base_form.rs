@@ -61,7 +61,7 @@ impl TokenFilter for JapaneseBaseFormTokenFilter {
     /// # Process
     ///
     /// 1. **Token Filtering**:
-    ///    - For each token, if the first detail (`detail[0]`) is `"UNK"` (unknown), the token is skipped, as it does not require normalization.
+    ///    - For each token, if it is an unknown word or its first detail (`detail[0]`) is `"UNK"`, the token is skipped, as it does not require normalization.
     ///
     /// 2. **Base Form Extraction**:
     ///    - Depending on the configured dictionary type (`IPADIC`, `IPADICNeologd`, or `UniDic`), the function determines which detail contains the base form of the token.
@@ -82,6 +82,10 @@ impl TokenFilter for JapaneseBaseFormTokenFilter {
     /// If any issue arises while processing tokens, the function will return an error in the form of `LinderaResult`.
     fn apply(&self, tokens: &mut Vec<Token<'_>>) -> LinderaResult<()> {
         for token in tokens.iter_mut() {
+            // Unknown words have no base form.
+            if token.word_id.is_unknown() {
+                continue;
+            }
             if let Some(detail) = token.get_detail(0) {
                 if detail == "UNK" {
                     continue;
@@ -128,7 +132,7 @@ mod tests {
         }
         "#;
         let result: Result<JapaneseBaseFormTokenFilterConfig, _> = serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -141,7 +145,7 @@ mod tests {
         }
         "#;
         let result: Result<JapaneseBaseFormTokenFilterConfig, _> = serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -158,7 +162,7 @@ mod tests {
         let config: JapaneseBaseFormTokenFilterConfig = serde_json::from_str(config_str).unwrap();
         let result = JapaneseBaseFormTokenFilter::from_config(&config);
 
-        assert_eq!(true, result.is_ok());
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -175,7 +179,7 @@ mod tests {
         let config: JapaneseBaseFormTokenFilterConfig = serde_json::from_str(config_str).unwrap();
         let result = JapaneseBaseFormTokenFilter::from_config(&config);
 
-        assert_eq!(true, result.is_ok());
+        assert!(result.is_ok());
     }
 
     #[cfg(feature = "ipadic")]
@@ -213,6 +217,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -237,6 +242,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("助詞"),
                     Cow::Borrowed("格助詞"),
@@ -261,6 +267,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("動詞"),
                     Cow::Borrowed("自立"),
@@ -285,6 +292,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("助動詞"),
                     Cow::Borrowed("*"),
@@ -343,6 +351,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -375,6 +384,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("普通名詞"),
@@ -407,6 +417,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("助詞"),
                     Cow::Borrowed("格助詞"),
@@ -439,6 +450,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("動詞"),
                     Cow::Borrowed("非自立可能"),
@@ -471,6 +483,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("助動詞"),
                     Cow::Borrowed("*"),
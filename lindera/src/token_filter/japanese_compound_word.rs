@@ -20,6 +20,8 @@ pub type JapaneseCompoundWordTokenFilterConfig = Value;
 pub struct JapaneseCompoundWordTokenFilter {
     kind: DictionaryKind,
     tags: HashSet<String>,
+    // Only read when a dictionary feature is enabled.
+    #[allow(dead_code)]
     new_tag: Option<String>,
 }
 
@@ -97,6 +99,11 @@ impl JapaneseCompoundWordTokenFilter {
         token1.text = Cow::Owned(format!("{}{}", token1.text, token2.text));
         token1.byte_end = token2.byte_end;
         token1.position_length += token2.position_length;
+        // The compound is at most as likely as the least likely of its parts.
+        token1.confidence = match (token1.confidence, token2.confidence) {
+            (Some(confidence1), Some(confidence2)) => Some(confidence1.min(confidence2)),
+            _ => None,
+        };
 
         let details = match self.kind {
             #[cfg(feature = "ipadic")]
@@ -371,6 +378,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("数"),
@@ -395,6 +403,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("数"),
@@ -419,6 +428,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("数"),
@@ -443,6 +453,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -467,6 +478,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -491,6 +503,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -515,6 +528,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("動詞"),
                     Cow::Borrowed("自立"),
@@ -231,6 +231,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -255,6 +256,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("サ変接続"),
@@ -279,6 +281,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![Cow::Borrowed("UNK")]),
             },
         ];
@@ -326,6 +329,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -350,6 +354,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -374,6 +379,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -398,6 +404,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -456,6 +463,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -480,6 +488,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("サ変接続"),
@@ -504,6 +513,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![Cow::Borrowed("UNK")]),
             },
         ];
@@ -551,6 +561,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -575,6 +586,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -599,6 +611,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -623,6 +636,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -681,6 +695,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -705,6 +720,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -729,6 +745,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -753,6 +770,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
@@ -811,6 +829,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -835,6 +854,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -859,6 +879,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("接尾"),
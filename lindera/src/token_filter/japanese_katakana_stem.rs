@@ -130,7 +130,7 @@ mod tests {
             "#;
         let result: Result<JapaneseKatakanaStemTokenFilterConfig, _> =
             serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -145,7 +145,7 @@ mod tests {
         let result: Result<JapaneseKatakanaStemTokenFilterConfig, _> =
             serde_json::from_str(config_str);
 
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -163,7 +163,7 @@ mod tests {
             serde_json::from_str(config_str).unwrap();
         let result = JapaneseKatakanaStemTokenFilter::from_config(&config);
 
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -221,6 +221,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("一般"),
@@ -245,6 +246,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("一般"),
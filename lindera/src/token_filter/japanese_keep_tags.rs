@@ -165,7 +165,7 @@ mod tests {
             }
             "#;
         let result: Result<JapaneseKeepTagsTokenFilterConfig, _> = serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -301,6 +301,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("一般"),
@@ -325,6 +326,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("助詞"),
                     Cow::Borrowed("係助詞"),
@@ -349,6 +351,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("一般"),
@@ -373,6 +376,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("助詞"),
                     Cow::Borrowed("係助詞"),
@@ -397,6 +401,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("一般"),
@@ -421,6 +426,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("助詞"),
                     Cow::Borrowed("連体化"),
@@ -445,6 +451,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("非自立"),
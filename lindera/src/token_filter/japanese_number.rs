@@ -97,7 +97,7 @@ impl TokenFilter for JapaneseNumberTokenFilter {
             let tag = details[0..tags_len].join(",");
 
             // Determine whether to convert the token based on the config tags.
-            let should_convert = self.tags.as_ref().map_or(true, |tags| tags.contains(&tag));
+            let should_convert = self.tags.as_ref().is_none_or(|tags| tags.contains(&tag));
 
             // If conversion is required, apply the Arabic numeral conversion.
             if should_convert {
@@ -832,7 +832,7 @@ mod tests {
                 "#;
             let result: Result<JapaneseNumberTokenFilterConfig, _> =
                 serde_json::from_str(config_str);
-            assert_eq!(result.is_ok(), true);
+            assert!(result.is_ok());
         }
         {
             let config_str = r#"
@@ -841,7 +841,7 @@ mod tests {
                 "#;
             let result: Result<JapaneseNumberTokenFilterConfig, _> =
                 serde_json::from_str(config_str);
-            assert_eq!(result.is_ok(), true);
+            assert!(result.is_ok());
         }
 
         {
@@ -854,7 +854,7 @@ mod tests {
                 "#;
             let result: Result<JapaneseNumberTokenFilterConfig, _> =
                 serde_json::from_str(config_str);
-            assert_eq!(result.is_ok(), true);
+            assert!(result.is_ok());
         }
     }
 
@@ -869,7 +869,7 @@ mod tests {
             let config: JapaneseNumberTokenFilterConfig = serde_json::from_str(config_str).unwrap();
             let result = JapaneseNumberTokenFilter::from_config(&config);
 
-            assert_eq!(true, result.is_ok());
+            assert!(result.is_ok());
         }
 
         {
@@ -883,7 +883,7 @@ mod tests {
             let config: JapaneseNumberTokenFilterConfig = serde_json::from_str(config_str).unwrap();
             let result = JapaneseNumberTokenFilter::from_config(&config);
 
-            assert_eq!(true, result.is_ok());
+            assert!(result.is_ok());
         }
     }
 
@@ -920,6 +920,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("数"),
@@ -952,6 +953,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("数"),
@@ -982,6 +984,7 @@ mod tests {
                     word_id: WordId{id:102657, is_system:true},
                     dictionary: &dictionary,
                     user_dictionary: None,
+                    confidence: None,
                     details: Some(vec![
                         Cow::Borrowed("名詞"),
                         Cow::Borrowed("数"),
@@ -1016,6 +1019,7 @@ mod tests {
                     },
                     dictionary: &dictionary,
                     user_dictionary: None,
+                    confidence: None,
                     details: Some(vec![
                         Cow::Borrowed("名詞"),
                         Cow::Borrowed("固有名詞"),
@@ -1040,6 +1044,7 @@ mod tests {
                     },
                     dictionary: &dictionary,
                     user_dictionary: None,
+                    confidence: None,
                     details: Some(vec![
                         Cow::Borrowed("名詞"),
                         Cow::Borrowed("固有名詞"),
@@ -1091,6 +1096,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("数"),
@@ -1123,6 +1129,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("数"),
@@ -1153,6 +1160,7 @@ mod tests {
                     word_id: WordId{id:102657, is_system:true},
                     dictionary: &dictionary,
                     user_dictionary: None,
+                    confidence: None,
                     details: Some(vec![
                         Cow::Borrowed("名詞"),
                         Cow::Borrowed("数"),
@@ -1187,6 +1195,7 @@ mod tests {
                     },
                     dictionary: &dictionary,
                     user_dictionary: None,
+                    confidence: None,
                     details: Some(vec![
                         Cow::Borrowed("名詞"),
                         Cow::Borrowed("固有名詞"),
@@ -1211,6 +1220,7 @@ mod tests {
                     },
                     dictionary: &dictionary,
                     user_dictionary: None,
+                    confidence: None,
                     details: Some(vec![
                         Cow::Borrowed("名詞"),
                         Cow::Borrowed("固有名詞"),
@@ -62,7 +62,7 @@ impl TokenFilter for JapaneseReadingFormTokenFilter {
     ///
     /// 1. **Token Detail Check**:
     ///    - For each token in the vector, the function checks the first detail (`get_detail(0)`).
-    ///    - If the first detail is `"UNK"`, the token is skipped and no further processing is done for that token.
+    ///    - If the token is an unknown word or its first detail is `"UNK"`, the token is skipped and no further processing is done for that token.
     ///
     /// 2. **Dictionary Type Handling**:
     ///    - Depending on the `config.kind` (which determines the type of dictionary used), the function selects the appropriate index in the token's details:
@@ -83,6 +83,10 @@ impl TokenFilter for JapaneseReadingFormTokenFilter {
     /// Returns a `LinderaResult<()>` if there is an issue during token processing or text conversion. However, under normal circumstances, it should process without errors.
     fn apply(&self, tokens: &mut Vec<Token<'_>>) -> LinderaResult<()> {
         for token in tokens.iter_mut() {
+            // Unknown words have no reading.
+            if token.word_id.is_unknown() {
+                continue;
+            }
             if let Some(detail) = token.get_detail(0) {
                 if detail == "UNK" {
                     continue;
@@ -131,7 +135,7 @@ mod tests {
             "#;
         let result: Result<JapaneseReadingFormTokenFilterConfig, _> =
             serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -145,7 +149,7 @@ mod tests {
             "#;
         let result: Result<JapaneseReadingFormTokenFilterConfig, _> =
             serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[cfg(feature = "ipadic")]
@@ -182,7 +186,7 @@ mod tests {
             serde_json::from_str(config_str).unwrap();
         let result = JapaneseReadingFormTokenFilter::from_config(&config);
 
-        assert_eq!(true, result.is_ok());
+        assert!(result.is_ok());
     }
 
     #[cfg(feature = "ipadic")]
@@ -221,6 +225,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -245,6 +250,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("サ変接続"),
@@ -269,6 +275,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![Cow::Borrowed("UNK")]),
             },
         ];
@@ -317,6 +324,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("固有名詞"),
@@ -349,6 +357,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("普通名詞"),
@@ -381,6 +390,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("普通名詞"),
@@ -413,6 +423,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("普通名詞"),
@@ -445,6 +456,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("名詞"),
                     Cow::Borrowed("普通名詞"),
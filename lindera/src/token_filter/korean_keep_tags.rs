@@ -114,7 +114,7 @@ mod tests {
         }
         "#;
         let result: Result<KoreanKeepTagsTokenFilterConfig, _> = serde_json::from_str(config_str);
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -129,7 +129,7 @@ mod tests {
         let config: KoreanKeepTagsTokenFilterConfig = serde_json::from_str(config_str).unwrap();
         let result = KoreanKeepTagsTokenFilter::from_config(&config);
 
-        assert_eq!(true, result.is_ok());
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -166,6 +166,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("*"),
@@ -189,6 +190,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("JKG"),
                     Cow::Borrowed("*"),
@@ -212,6 +214,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("*"),
@@ -235,6 +238,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("행위"),
@@ -258,6 +262,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("JKO"),
                     Cow::Borrowed("*"),
@@ -281,6 +286,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("VV+ETM"),
                     Cow::Borrowed("*"),
@@ -304,6 +310,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("*"),
@@ -327,6 +334,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("VX"),
                     Cow::Borrowed("*"),
@@ -350,6 +358,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("EF"),
                     Cow::Borrowed("*"),
@@ -38,6 +38,10 @@ impl TokenFilter for KoreanReadingFormTokenFilter {
 
     fn apply(&self, tokens: &mut Vec<Token<'_>>) -> LinderaResult<()> {
         for token in tokens.iter_mut() {
+            // Unknown words have no reading.
+            if token.word_id.is_unknown() {
+                continue;
+            }
             if let Some(detail) = token.get_detail(0) {
                 if detail == "UNK" {
                     continue;
@@ -82,6 +86,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("*"),
@@ -105,6 +110,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("JKG"),
                     Cow::Borrowed("*"),
@@ -128,6 +134,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("*"),
@@ -151,6 +158,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("행위"),
@@ -174,6 +182,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("JKO"),
                     Cow::Borrowed("*"),
@@ -197,6 +206,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("VV+ETM"),
                     Cow::Borrowed("*"),
@@ -220,6 +230,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("NNG"),
                     Cow::Borrowed("*"),
@@ -243,6 +254,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("VX"),
                     Cow::Borrowed("*"),
@@ -266,6 +278,7 @@ mod tests {
                 },
                 dictionary: &dictionary,
                 user_dictionary: None,
+                confidence: None,
                 details: Some(vec![
                     Cow::Borrowed("EF"),
                     Cow::Borrowed("*"),
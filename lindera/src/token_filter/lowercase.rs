@@ -74,6 +74,7 @@ mod tests {
             },
             dictionary: &dictionary,
             user_dictionary: None,
+            confidence: None,
             details: Some(vec![Cow::Borrowed("UNK")]),
         }];
 
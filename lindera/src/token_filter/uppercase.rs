@@ -73,6 +73,7 @@ mod tests {
             },
             dictionary: &dictionary,
             user_dictionary: None,
+            confidence: None,
             details: Some(vec![Cow::Borrowed("UNK")]),
         }];
 
@@ -4,6 +4,7 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
+use lindera_dictionary::viterbi::UnknownWordMode;
 use serde_json::{json, Value};
 
 use crate::character_filter::{correct_offset, BoxCharacterFilter, CharacterFilterLoader};
@@ -119,6 +120,53 @@ impl TokenizerBuilder {
         self
     }
 
+    /// Appends a user dictionary to the `user_dictionaries` of the segmenter, consulted after the
+    /// ones already there.
+    pub fn append_segmenter_user_dictionary(
+        &mut self,
+        path: &Path,
+        kind: Option<&DictionaryKind>,
+        cost_bias: i16,
+    ) -> &mut Self {
+        let mut user_dictionary = json!({ "path": path, "cost_bias": cost_bias });
+        if let Some(kind) = kind {
+            user_dictionary["kind"] = json!(kind.as_str());
+        }
+        match self.config["segmenter"]["user_dictionaries"].as_array_mut() {
+            Some(array) => array.push(user_dictionary),
+            None => self.config["segmenter"]["user_dictionaries"] = json!([user_dictionary]),
+        }
+        self
+    }
+
+    pub fn set_segmenter_confidence_temperature(&mut self, temperature: f64) -> &mut Self {
+        self.config["segmenter"]["confidence_temperature"] = json!(temperature);
+        self
+    }
+
+    pub fn set_segmenter_unknown_word_mode(
+        &mut self,
+        unknown_word_mode: &UnknownWordMode,
+    ) -> &mut Self {
+        self.config["segmenter"]["unknown_word_mode"] = json!(unknown_word_mode);
+        self
+    }
+
+    pub fn set_segmenter_beam_width(&mut self, beam_width: usize) -> &mut Self {
+        self.config["segmenter"]["beam_width"] = json!(beam_width);
+        self
+    }
+
+    pub fn set_segmenter_max_edges_per_position(&mut self, max_edges: usize) -> &mut Self {
+        self.config["segmenter"]["max_edges_per_position"] = json!(max_edges);
+        self
+    }
+
+    pub fn set_segmenter_max_sentence_length(&mut self, max_sentence_length: usize) -> &mut Self {
+        self.config["segmenter"]["max_sentence_length"] = json!(max_sentence_length);
+        self
+    }
+
     pub fn append_character_filter(&mut self, kind: &str, args: &Value) -> &mut Self {
         if let Some(array) = self.config["character_filters"].as_array_mut() {
             array.push(json!({ "kind": kind, "args": args }));
@@ -299,11 +347,55 @@ impl Tokenizer {
     /// - If no character filters are applied, the original `text` is used as-is for segmentation.
     /// - Token offsets are adjusted after the tokenization process if character filters were applied to ensure the byte positions of each token are accurate relative to the original text.
     pub fn tokenize<'a>(&'a self, text: &'a str) -> LinderaResult<Vec<Token<'a>>> {
+        let (normalized_text, offset_corrections) = self.apply_character_filters(text)?;
+
+        // Setment a text.
+        let mut tokens = self.segmenter.segment(normalized_text)?;
+
+        self.apply_token_filters(&mut tokens, &offset_corrections)?;
+
+        Ok(tokens)
+    }
+
+    /// Tokenizes the input text and returns the `n` best tokenizations with their total costs.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - A reference to the input text (`&str`) that will be tokenized.
+    /// * `n` - The maximum number of tokenizations to return.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `LinderaResult` containing up to `n` pairs of tokens and the total cost of the segmentation, in ascending order of cost.
+    ///
+    /// # Details
+    ///
+    /// - Character filters are applied once to the input text, then the segmenter's `segment_nbest` produces the candidate segmentations.
+    /// - Token filters and offset corrections are applied to every candidate in the same way as `tokenize`.
+    pub fn tokenize_nbest<'a>(
+        &'a self,
+        text: &'a str,
+        n: usize,
+    ) -> LinderaResult<Vec<(Vec<Token<'a>>, i64)>> {
+        let (normalized_text, offset_corrections) = self.apply_character_filters(text)?;
+
+        let mut nbest = self.segmenter.segment_nbest(normalized_text, n)?;
+
+        for (tokens, _cost) in nbest.iter_mut() {
+            self.apply_token_filters(tokens, &offset_corrections)?;
+        }
+
+        Ok(nbest)
+    }
+
+    /// Applies the character filters to the text and records what is needed to correct token offsets afterwards.
+    fn apply_character_filters<'a>(
+        &self,
+        text: &'a str,
+    ) -> LinderaResult<(Cow<'a, str>, OffsetCorrections)> {
         let mut normalized_text: Cow<'a, str> = Cow::Borrowed(text);
 
-        let mut text_len_vec: Vec<usize> = Vec::new();
-        let mut offsets_vec: Vec<Vec<usize>> = Vec::new();
-        let mut diffs_vec: Vec<Vec<i64>> = Vec::new();
+        let mut offset_corrections = OffsetCorrections::default();
 
         // Appy character filters to the text if it is not empty.
         for character_filter in &self.character_filters {
@@ -311,43 +403,65 @@ impl Tokenizer {
 
             if !offsets.is_empty() {
                 // Record the offsets of each character filter.
-                offsets_vec.insert(0, offsets);
+                offset_corrections.offsets_vec.insert(0, offsets);
 
                 // Record the diffs of each character filter.
-                diffs_vec.insert(0, diffs);
+                offset_corrections.diffs_vec.insert(0, diffs);
 
                 // Record the length of the text after each character filter is applied.
-                text_len_vec.insert(0, text_len);
+                offset_corrections.text_len_vec.insert(0, text_len);
             }
         }
 
-        // Setment a text.
-        let mut tokens = self.segmenter.segment(normalized_text)?;
+        Ok((normalized_text, offset_corrections))
+    }
 
+    /// Applies the token filters to the tokens and corrects their offsets for the character filters.
+    fn apply_token_filters(
+        &self,
+        tokens: &mut Vec<Token<'_>>,
+        offset_corrections: &OffsetCorrections,
+    ) -> LinderaResult<()> {
         // Apply token filters to the tokens if they are not empty.
         for token_filter in &self.token_filters {
-            token_filter.apply(&mut tokens)?;
+            token_filter.apply(tokens)?;
         }
 
         // Correct token offsets if character filters are applied.
-        if !offsets_vec.is_empty() {
+        if !offset_corrections.offsets_vec.is_empty() {
             for token in tokens.iter_mut() {
                 // Override details.
-                for (i, offsets) in offsets_vec.iter().enumerate() {
+                for (i, offsets) in offset_corrections.offsets_vec.iter().enumerate() {
                     // Override start.
-                    token.byte_start =
-                        correct_offset(token.byte_start, offsets, &diffs_vec[i], text_len_vec[i]);
+                    token.byte_start = correct_offset(
+                        token.byte_start,
+                        offsets,
+                        &offset_corrections.diffs_vec[i],
+                        offset_corrections.text_len_vec[i],
+                    );
                     // Override end.
-                    token.byte_end =
-                        correct_offset(token.byte_end, offsets, &diffs_vec[i], text_len_vec[i]);
+                    token.byte_end = correct_offset(
+                        token.byte_end,
+                        offsets,
+                        &offset_corrections.diffs_vec[i],
+                        offset_corrections.text_len_vec[i],
+                    );
                 }
             }
         }
 
-        Ok(tokens)
+        Ok(())
     }
 }
 
+/// Offsets, diffs and text lengths recorded by the character filters, newest first.
+#[derive(Default)]
+struct OffsetCorrections {
+    offsets_vec: Vec<Vec<usize>>,
+    diffs_vec: Vec<Vec<i64>>,
+    text_len_vec: Vec<usize>,
+}
+
 impl Clone for Tokenizer {
     /// Creates a deep clone of the `Tokenizer` instance, including all character filters, token filters, and the segmenter.
     ///
@@ -363,7 +477,7 @@ impl Clone for Tokenizer {
     ///
     /// # Notes
     ///
-    /// - This method performs deep cloning, meaning that all internal filters and segmenter instances are fully duplicated.
+    /// - The filters are deeply cloned, while the dictionaries of the segmenter are shared with the original instance rather than copied.
     /// - The `box_clone` method is used to clone the dynamically dispatched filter objects (`BoxCharacterFilter` and `BoxTokenFilter`).
     fn clone(&self) -> Self {
         let mut character_filters: Vec<BoxCharacterFilter> = Vec::new();
@@ -448,7 +562,8 @@ mod tests {
                 assert_eq!(token.byte_end, 15);
                 assert_eq!(token.position, 0);
                 assert_eq!(token.position_length, 1);
-                assert_eq!(token.details, Some(vec![Cow::Borrowed("UNK")]));
+                assert_eq!(token.category_name(), Some("ALPHA"));
+                assert_eq!(token.get_detail(0), Some("名詞"));
             }
             {
                 let token = tokens_iter.next().unwrap();
@@ -532,33 +647,25 @@ mod tests {
             let mut tokens = tokenizer.tokenize(text).unwrap();
             let mut tokens_iter = tokens.iter_mut();
             {
+                // The unknown number is a "名詞,数", so it is compounded with the counter.
                 let token = tokens_iter.next().unwrap();
-                assert_eq!(token.text, Cow::Borrowed("10"));
+                assert_eq!(token.text, Cow::Borrowed("10ガロン"));
                 assert_eq!(token.byte_start, 0);
-                assert_eq!(token.byte_end, 6);
-                assert_eq!(token.position, 0);
-                assert_eq!(token.position_length, 1);
-                assert_eq!(token.details, Some(vec![Cow::Borrowed("UNK")]));
-            }
-            {
-                let token = tokens_iter.next().unwrap();
-                assert_eq!(token.text, Cow::Borrowed("ガロン"));
-                assert_eq!(token.byte_start, 6);
                 assert_eq!(token.byte_end, 9);
-                assert_eq!(token.position, 1);
-                assert_eq!(token.position_length, 1);
+                assert_eq!(token.position, 0);
+                assert_eq!(token.position_length, 2);
                 assert_eq!(
                     token.details,
                     Some(vec![
                         Cow::Borrowed("名詞"),
-                        Cow::Borrowed("接尾"),
-                        Cow::Borrowed("助数詞"),
+                        Cow::Borrowed("数"),
+                        Cow::Borrowed("*"),
+                        Cow::Borrowed("*"),
+                        Cow::Borrowed("*"),
+                        Cow::Borrowed("*"),
                         Cow::Borrowed("*"),
                         Cow::Borrowed("*"),
                         Cow::Borrowed("*"),
-                        Cow::Borrowed("ガロン"),
-                        Cow::Borrowed("ガロン"),
-                        Cow::Borrowed("ガロン"),
                     ])
                 );
             }
@@ -590,15 +697,8 @@ mod tests {
                 let token = tokens_iter.next().unwrap();
                 let start = token.byte_start;
                 let end = token.byte_end;
-                assert_eq!(token.text, Cow::Borrowed("10"));
-                assert_eq!(&text[start..end], "１０");
-            }
-            {
-                let token = tokens_iter.next().unwrap();
-                let start = token.byte_start;
-                let end = token.byte_end;
-                assert_eq!(token.text, Cow::Borrowed("ガロン"));
-                assert_eq!(&text[start..end], "㌎");
+                assert_eq!(token.text, Cow::Borrowed("10ガロン"));
+                assert_eq!(&text[start..end], "１０㌎");
             }
             {
                 let token = tokens_iter.next().unwrap();
@@ -710,6 +810,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tokenize_nbest() {
+        use std::collections::HashMap;
+
+        use crate::character_filter::mapping::MappingCharacterFilter;
+        use crate::character_filter::BoxCharacterFilter;
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::mini_ipadic;
+        use crate::tokenizer::Tokenizer;
+
+        let segmenter = Segmenter::new(Mode::Normal, mini_ipadic(), None);
+        let mut tokenizer = Tokenizer::new(segmenter);
+        tokenizer.append_character_filter(BoxCharacterFilter::from(
+            MappingCharacterFilter::new(HashMap::from([(
+                "トーキョー".to_string(),
+                "東京".to_string(),
+            )]))
+            .unwrap(),
+        ));
+
+        let text = "トーキョー都に行く";
+        let nbest = tokenizer.tokenize_nbest(text, 2).unwrap();
+        assert_eq!(nbest.len(), 2);
+        assert!(nbest[0].1 <= nbest[1].1);
+
+        let best = tokenizer.tokenize(text).unwrap();
+        assert_eq!(
+            nbest[0]
+                .0
+                .iter()
+                .map(|t| t.text.as_ref())
+                .collect::<Vec<_>>(),
+            best.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>()
+        );
+
+        // Offsets of every candidate are corrected for the character filter.
+        for (tokens, _cost) in nbest.iter() {
+            assert_eq!(tokens.first().unwrap().byte_start, 0);
+            assert_eq!(tokens.last().unwrap().byte_end, text.len());
+        }
+        assert_eq!(
+            nbest[1]
+                .0
+                .iter()
+                .map(|t| (t.text.as_ref(), t.byte_start, t.byte_end))
+                .collect::<Vec<_>>(),
+            vec![
+                ("東京", 0, 15),
+                ("都", 15, 18),
+                ("に", 18, 21),
+                ("行く", 21, 27)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_clone_shares_dictionaries() {
+        use std::sync::Arc;
+
+        use crate::mode::Mode;
+        use crate::segmenter::Segmenter;
+        use crate::test_utils::{mini_ipadic, mini_ipadic_user_dictionary};
+        use crate::tokenizer::Tokenizer;
+
+        let segmenter = Segmenter::new(
+            Mode::Normal,
+            mini_ipadic(),
+            Some(mini_ipadic_user_dictionary()),
+        );
+        let tokenizer = Tokenizer::new(segmenter);
+        let cloned = tokenizer.clone();
+
+        assert!(Arc::ptr_eq(
+            &tokenizer.segmenter.dictionary,
+            &cloned.segmenter.dictionary
+        ));
+        assert!(Arc::ptr_eq(
+            &tokenizer.segmenter.user_dictionaries[0].dictionary,
+            &cloned.segmenter.user_dictionaries[0].dictionary
+        ));
+
+        // A dictionary can also be shared by segmenters built separately.
+        let other = Segmenter::new(
+            Mode::Decompose(Default::default()),
+            Arc::clone(&tokenizer.segmenter.dictionary),
+            None,
+        );
+        assert!(Arc::ptr_eq(
+            &tokenizer.segmenter.dictionary,
+            &other.dictionary
+        ));
+    }
+
     #[test]
     #[cfg(not(windows))]
     #[should_panic(expected = "No such file or directory")]